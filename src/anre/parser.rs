@@ -232,7 +232,7 @@ impl Parser<'_> {
             Program { expressions }
         };
 
-        Ok(program)
+        apply_directives(program)
     }
 
     fn parse_expression(&mut self) -> Result<Expression, AnreError> {
@@ -875,6 +875,58 @@ impl Parser<'_> {
     }
 }
 
+// The modifier functions without arguments at the beginning of the program
+// are directives, they apply to the whole program, e.g.
+//
+// ```anre
+// ignore_case()
+// start, "hello", end
+// ```
+//
+// is equivalent to `ignore_case((start, "hello", end))`, and
+// the traditional regular expression `(?i)^hello$`.
+fn apply_directives(program: Program) -> Result<Program, AnreError> {
+    let is_directive = |expression: &Expression| {
+        matches!(expression, Expression::FunctionCall(function_call)
+            if function_call.name.is_modifier() && function_call.args.is_empty())
+    };
+
+    let mut expressions = program.expressions;
+    let directive_count = expressions
+        .iter()
+        .take_while(|expression| is_directive(expression))
+        .count();
+
+    if expressions[directive_count..].iter().any(is_directive) {
+        return Err(AnreError::SyntaxIncorrect(
+            "Modifier directives can only be present at the beginning of expression.".to_owned(),
+        ));
+    }
+
+    if directive_count == 0 {
+        return Ok(Program { expressions });
+    }
+
+    let directives: Vec<Expression> = expressions.drain(..directive_count).collect();
+
+    let mut expression = if expressions.len() == 1 {
+        expressions.remove(0)
+    } else {
+        Expression::Group(expressions)
+    };
+
+    for directive in directives.into_iter().rev() {
+        if let Expression::FunctionCall(mut function_call) = directive {
+            function_call.args.push(expression);
+            expression = Expression::FunctionCall(function_call);
+        }
+    }
+
+    Ok(Program {
+        expressions: vec![expression],
+    })
+}
+
 enum NotationQuantifier {
     Repeat(usize),
     RepeatRange(usize, usize),
@@ -992,6 +1044,14 @@ fn function_name_from_str(name_str: &str, range: &Location) -> Result<FunctionNa
         "name" => FunctionName::Name,
         "index" => FunctionName::Index,
 
+        // Modifiers
+        "ignore_case" => FunctionName::IgnoreCase,
+        "multiline" => FunctionName::MultiLine,
+        "dot_all" => FunctionName::DotAll,
+        "not_ignore_case" => FunctionName::NotIgnoreCase,
+        "not_multiline" => FunctionName::NotMultiLine,
+        "not_dot_all" => FunctionName::NotDotAll,
+
         // Unexpect
         _ => {
             return Err(AnreError::MessageWithLocation(
@@ -1580,6 +1640,70 @@ start, a, b, c, d, end
         );
     }
 
+    #[test]
+    fn test_parse_modifiers() {
+        assert_eq!(
+            parse_from_str(
+                r#"
+'a', "bc".ignore_case(), dot_all(char_any)
+"#,
+            )
+            .unwrap()
+            .to_string(),
+            r#"'a'
+ignore_case("bc")
+dot_all(char_any)"#
+        );
+
+        assert_eq!(
+            parse_from_str(
+                r#"
+multiline((start, 'a', not_multiline(end)))
+"#,
+            )
+            .unwrap()
+            .to_string(),
+            r#"multiline((start, 'a', not_multiline(end)))"#
+        );
+
+        // directives
+        assert_eq!(
+            parse_from_str(
+                r#"
+ignore_case()
+multiline()
+start, "abc", end
+"#,
+            )
+            .unwrap()
+            .to_string(),
+            r#"ignore_case(multiline((start, "abc", end)))"#
+        );
+
+        assert_eq!(
+            parse_from_str(
+                r#"
+ignore_case()
+"abc"
+"#,
+            )
+            .unwrap()
+            .to_string(),
+            r#"ignore_case("abc")"#
+        );
+
+        // err: directive is not at the beginning
+        assert!(matches!(
+            parse_from_str(
+                r#"
+"abc"
+ignore_case()
+"#,
+            ),
+            Err(AnreError::SyntaxIncorrect(_))
+        ));
+    }
+
     #[test]
     fn test_parse_examples() {
         assert_eq!(
@@ -0,0 +1,930 @@
+// Copyright (c) 2025 Hemashushu <hippospark@gmail.com>, All rights reserved.
+//
+// This Source Code Form is subject to the terms of
+// the Mozilla Public License version 2.0 and additional exceptions.
+// For more details, see the LICENSE, LICENSE.additional, and CONTRIBUTING files.
+
+// The assembler parses the listing generated by `ObjectFile::get_debug_text`
+// and rebuilds the object file, e.g.
+//
+// ```text
+// = $0
+// - 0
+//   -> 1, Char 'a'
+// - 1
+//   -> 3, Look ahead $1
+// - 2
+//   -> 0, Jump
+// - 3
+//   -> 5, Capture end {0}
+// > 4
+//   -> 2, Capture start {0}
+// < 5
+// = $1
+// > 0, fixed start
+//   -> 1, String "xyz"
+// < 1
+// # {0}
+// ```
+//
+// - `= $N` starts the route N, it can be omitted if there is only one route.
+// - `> N`, `< N` and `- N` are the entry node, the exit node and the other nodes,
+//   the entry and exit nodes may be followed by ", fixed start" and ", fixed end".
+// - `  -> T, transition` is a transition item of the preceding node.
+// - `# {N}` and `# {N}, name` are the capture groups.
+// - `* optimized: ...` is the optimization stats.
+//
+// The nodes, routes and capture groups must be listed in the order of their indices.
+
+use crate::{
+    ast::{AnchorAssertionName, BoundaryAssertionName},
+    location::Location,
+    object_file::{ObjectFile, OptimizationStats, MAIN_ROUTE_INDEX},
+    prefilter::Prefilter,
+    transition::{
+        AnchorAssertionTransition, BackReferenceTransition, BoundaryAssertionTransition,
+        CaptureEndTransition, CaptureStartTransition, CharRange, CharSetItem, CharSetTransition,
+        CharTransition, CounterCheckTransition, CounterIncTransition, CounterResetTransition,
+        CounterSaveTransition, JumpTransition, LookAheadAssertionTransition,
+        LookBehindAssertionTransition, RepetitionTransition, RepetitionType, SpecialCharTransition,
+        StringTransition, Transition, TrieTransition,
+    },
+    AnreError,
+};
+
+/// Parses the listing generated by `ObjectFile::get_debug_text`.
+pub fn assemble(text: &str) -> Result<ObjectFile, AnreError> {
+    let mut assembler = Assembler {
+        object_file: ObjectFile::new(),
+        has_route_header: false,
+        has_entry_node: vec![],
+        has_exit_node: vec![],
+        node_references: vec![],
+        route_references: vec![],
+        capture_group_references: vec![],
+    };
+
+    let mut index = 0;
+    for (line_number, line) in text.split('\n').enumerate() {
+        let mut cursor = LineCursor {
+            chars: line.trim_end_matches('\r').chars().collect(),
+            position: 0,
+            index,
+            line_number,
+        };
+        index += line.chars().count() + 1;
+
+        if !cursor.chars.is_empty() {
+            assembler.parse_line(&mut cursor)?;
+        }
+    }
+
+    assembler.finish()
+}
+
+struct Assembler {
+    object_file: ObjectFile,
+
+    // Whether the routes are started with `= $N`.
+    has_route_header: bool,
+
+    // Whether the entry and exit nodes of each route are listed.
+    has_entry_node: Vec<bool>,
+    has_exit_node: Vec<bool>,
+
+    // The indices referenced by the transitions, they are checked
+    // after all lines are parsed.
+    node_references: Vec<(usize, usize, Location)>, // (route index, node index, location)
+    route_references: Vec<(usize, Location)>,
+    capture_group_references: Vec<(usize, Location)>,
+}
+
+impl Assembler {
+    fn parse_line(&mut self, cursor: &mut LineCursor) -> Result<(), AnreError> {
+        match cursor.peek() {
+            Some('=') => self.parse_route_header(cursor),
+            Some('>' | '<' | '-') => self.parse_node(cursor),
+            Some(' ') => self.parse_transition_item(cursor),
+            Some('#') => self.parse_capture_group(cursor),
+            Some('*') => self.parse_optimization_stats(cursor),
+            _ => Err(cursor.error("Unknown line.", cursor.chars.len())),
+        }
+    }
+
+    // `= $N`
+    fn parse_route_header(&mut self, cursor: &mut LineCursor) -> Result<(), AnreError> {
+        if !self.has_route_header && !self.object_file.routes.is_empty() {
+            return Err(cursor.error(
+                "The route header is missing for the first route.",
+                cursor.chars.len(),
+            ));
+        }
+
+        cursor.expect_str("= $")?;
+        let start = cursor.position;
+        let route_index = cursor.parse_number()?;
+        if route_index != self.object_file.routes.len() {
+            return Err(cursor.error_at(
+                start,
+                &format!("Expect route index: {}.", self.object_file.routes.len()),
+                cursor.position - start,
+            ));
+        }
+        cursor.expect_end()?;
+
+        self.has_route_header = true;
+        self.create_route();
+        Ok(())
+    }
+
+    fn create_route(&mut self) {
+        self.object_file.create_route();
+        self.has_entry_node.push(false);
+        self.has_exit_node.push(false);
+    }
+
+    // `> N`, `< N` or `- N`, followed by the anchoring of the route.
+    fn parse_node(&mut self, cursor: &mut LineCursor) -> Result<(), AnreError> {
+        if self.object_file.routes.is_empty() {
+            self.create_route();
+        }
+
+        let route_index = self.object_file.routes.len() - 1;
+        let prefix = cursor.next().unwrap();
+        cursor.expect_str(" ")?;
+
+        let start = cursor.position;
+        let node_index = cursor.parse_number()?;
+        let route = &mut self.object_file.routes[route_index];
+        if node_index != route.nodes.len() {
+            return Err(cursor.error_at(
+                start,
+                &format!("Expect node index: {}.", route.nodes.len()),
+                cursor.position - start,
+            ));
+        }
+        route.create_node();
+
+        match prefix {
+            '>' if self.has_entry_node[route_index] => {
+                return Err(cursor.error_at(0, "Duplicated entry node.", 1));
+            }
+            '>' => {
+                route.start_node_index = node_index;
+                self.has_entry_node[route_index] = true;
+            }
+            '<' if self.has_exit_node[route_index] => {
+                return Err(cursor.error_at(0, "Duplicated exit node.", 1));
+            }
+            '<' => {
+                route.end_node_index = node_index;
+                self.has_exit_node[route_index] = true;
+            }
+            _ => {}
+        }
+
+        while cursor.consume_str(", ") {
+            if prefix == '>' && cursor.consume_str("fixed start") {
+                route.is_fixed_start_position = true;
+            } else if prefix != '-' && cursor.consume_str("fixed end") {
+                route.is_fixed_end_position = true;
+            } else {
+                return Err(cursor.error("Unknown node attribute.", cursor.remaining()));
+            }
+        }
+        cursor.expect_end()
+    }
+
+    // `  -> T, transition`
+    fn parse_transition_item(&mut self, cursor: &mut LineCursor) -> Result<(), AnreError> {
+        let route_index = self.object_file.routes.len().wrapping_sub(1);
+        let source_node_index = match self.object_file.routes.last() {
+            Some(route) if !route.nodes.is_empty() => route.nodes.len() - 1,
+            _ => {
+                return Err(cursor.error(
+                    "The transition item must follow a node.",
+                    cursor.chars.len(),
+                ))
+            }
+        };
+
+        cursor.expect_str("  -> ")?;
+        let start = cursor.position;
+        let target_node_index = cursor.parse_number()?;
+        let location = cursor.location(start, cursor.position - start);
+        self.node_references
+            .push((route_index, target_node_index, location));
+
+        cursor.expect_str(", ")?;
+        let transition = self.parse_transition(cursor)?;
+        cursor.expect_end()?;
+
+        self.object_file.routes[route_index].create_transition_item(
+            source_node_index,
+            target_node_index,
+            transition,
+        );
+        Ok(())
+    }
+
+    fn parse_transition(&mut self, cursor: &mut LineCursor) -> Result<Transition, AnreError> {
+        let transition = if cursor.consume_str("Jump") {
+            Transition::Jump(JumpTransition)
+        } else if cursor.consume_str("Char ") {
+            let c = cursor.parse_quoted_char('\'')?;
+            if cursor.consume_str(", ignore case") {
+                Transition::Char(CharTransition::new_ignore_case(c))
+            } else {
+                Transition::Char(CharTransition::new(c))
+            }
+        } else if cursor.consume_str("Any char") {
+            let dot_matches_new_line = cursor.consume_str(", include new line");
+            Transition::SpecialChar(SpecialCharTransition::new(dot_matches_new_line, false))
+        } else if cursor.consume_str("Any byte") {
+            let dot_matches_new_line = cursor.consume_str(", include new line");
+            Transition::SpecialChar(SpecialCharTransition::new(dot_matches_new_line, true))
+        } else if cursor.consume_str("String ") {
+            let s = cursor.parse_quoted_string()?;
+            if cursor.consume_str(", ignore case") {
+                Transition::String(StringTransition::new_ignore_case(&s))
+            } else {
+                Transition::String(StringTransition::new(&s))
+            }
+        } else if cursor.consume_str("Charset ") {
+            let negative = cursor.consume_str("!");
+            let items = parse_char_set_items(cursor)?;
+            if cursor.consume_str(", ignore case") {
+                Transition::CharSet(CharSetTransition::new_ignore_case(items, negative))
+            } else {
+                Transition::CharSet(CharSetTransition::new(items, negative))
+            }
+        } else if cursor.consume_str("Trie ") {
+            let start = cursor.position;
+            let mut words = vec![cursor.parse_quoted_string()?];
+            while cursor.consume_str(", \"") {
+                cursor.position -= 1;
+                words.push(cursor.parse_quoted_string()?);
+            }
+            let length = cursor.position - start;
+
+            let ignore_case = cursor.consume_str(", ignore case");
+            let transition = TrieTransition::new(words, ignore_case);
+
+            // The engines assume that at most one word matches at a position.
+            if !transition.is_prefix_free() {
+                return Err(cursor.error_at(
+                    start,
+                    "The words of the trie must be non-empty and none of them can be a prefix of another.",
+                    length,
+                ));
+            }
+            Transition::Trie(transition)
+        } else if cursor.consume_str("Back reference ") {
+            let capture_group_index = self.parse_capture_group_reference(cursor)?;
+            Transition::BackReference(BackReferenceTransition::new(capture_group_index))
+        } else if cursor.consume_str("Anchor assertion ") {
+            let name = if cursor.consume_str("\"start\"") {
+                AnchorAssertionName::Start
+            } else if cursor.consume_str("\"end\"") {
+                AnchorAssertionName::End
+            } else {
+                return Err(cursor.error("Unknown anchor assertion.", cursor.remaining()));
+            };
+
+            let multi_line = cursor.consume_str(", multi-line");
+            let crlf = multi_line && cursor.consume_str(", crlf");
+            Transition::AnchorAssertion(AnchorAssertionTransition {
+                name,
+                multi_line,
+                crlf,
+            })
+        } else if cursor.consume_str("Boundary assertion ") {
+            let name = if cursor.consume_str("\"is_bound\"") {
+                BoundaryAssertionName::IsBound
+            } else if cursor.consume_str("\"is_not_bound\"") {
+                BoundaryAssertionName::IsNotBound
+            } else {
+                return Err(cursor.error("Unknown boundary assertion.", cursor.remaining()));
+            };
+            Transition::BoundaryAssertion(BoundaryAssertionTransition::new(name))
+        } else if cursor.consume_str("Capture start ") {
+            let capture_group_index = self.parse_capture_group_reference(cursor)?;
+            Transition::CaptureStart(CaptureStartTransition::new(capture_group_index))
+        } else if cursor.consume_str("Capture end ") {
+            let capture_group_index = self.parse_capture_group_reference(cursor)?;
+            Transition::CaptureEnd(CaptureEndTransition::new(capture_group_index))
+        } else if cursor.consume_str("Counter reset") {
+            Transition::CounterReset(CounterResetTransition)
+        } else if cursor.consume_str("Counter save") {
+            Transition::CounterSave(CounterSaveTransition)
+        } else if cursor.consume_str("Counter inc") {
+            Transition::CounterInc(CounterIncTransition)
+        } else if cursor.consume_str("Counter check ") {
+            let repetition_type = parse_repetition_type(cursor)?;
+            Transition::CounterCheck(CounterCheckTransition::new(repetition_type))
+        } else if cursor.consume_str("Repetition ") {
+            let repetition_type = parse_repetition_type(cursor)?;
+            Transition::Repetition(RepetitionTransition::new(repetition_type))
+        } else if cursor.consume_str("Look ahead ") {
+            let negative = cursor.consume_str("negative ");
+            let route_index = self.parse_route_reference(cursor)?;
+            Transition::LookAheadAssertion(LookAheadAssertionTransition::new(route_index, negative))
+        } else if cursor.consume_str("Look behind ") {
+            let negative = cursor.consume_str("negative ");
+            let route_index = self.parse_route_reference(cursor)?;
+            cursor.expect_str(", match length ")?;
+            let match_length_in_char = cursor.parse_number()?;
+            Transition::LookBehindAssertion(LookBehindAssertionTransition::new(
+                route_index,
+                negative,
+                match_length_in_char,
+            ))
+        } else {
+            return Err(cursor.error("Unknown transition.", cursor.remaining()));
+        };
+
+        Ok(transition)
+    }
+
+    // `{N}`
+    fn parse_capture_group_reference(
+        &mut self,
+        cursor: &mut LineCursor,
+    ) -> Result<usize, AnreError> {
+        let start = cursor.position;
+        cursor.expect_str("{")?;
+        let capture_group_index = cursor.parse_number()?;
+        cursor.expect_str("}")?;
+
+        let location = cursor.location(start, cursor.position - start);
+        self.capture_group_references
+            .push((capture_group_index, location));
+        Ok(capture_group_index)
+    }
+
+    // `$N`
+    fn parse_route_reference(&mut self, cursor: &mut LineCursor) -> Result<usize, AnreError> {
+        let start = cursor.position;
+        cursor.expect_str("$")?;
+        let route_index = cursor.parse_number()?;
+
+        let location = cursor.location(start, cursor.position - start);
+        self.route_references.push((route_index, location));
+        Ok(route_index)
+    }
+
+    // `# {N}` or `# {N}, name`
+    fn parse_capture_group(&mut self, cursor: &mut LineCursor) -> Result<(), AnreError> {
+        cursor.expect_str("# {")?;
+        let start = cursor.position;
+        let capture_group_index = cursor.parse_number()?;
+        let number_of_capture_groups = self.object_file.capture_group_names.len();
+        if capture_group_index != number_of_capture_groups {
+            return Err(cursor.error_at(
+                start,
+                &format!("Expect capture group index: {}.", number_of_capture_groups),
+                cursor.position - start,
+            ));
+        }
+        cursor.expect_str("}")?;
+
+        let name = if cursor.consume_str(", ") {
+            let name: String = cursor.chars[cursor.position..].iter().collect();
+            if name.is_empty() {
+                return Err(cursor.error("Expect capture group name.", 0));
+            }
+            cursor.position = cursor.chars.len();
+            Some(name)
+        } else {
+            None
+        };
+        cursor.expect_end()?;
+
+        self.object_file.create_capture_group(name);
+        Ok(())
+    }
+
+    // `* optimized: nodes A -> B, transitions C -> D`
+    fn parse_optimization_stats(&mut self, cursor: &mut LineCursor) -> Result<(), AnreError> {
+        cursor.expect_str("* optimized: nodes ")?;
+        let nodes_before = cursor.parse_number()?;
+        cursor.expect_str(" -> ")?;
+        let nodes_after = cursor.parse_number()?;
+        cursor.expect_str(", transitions ")?;
+        let transitions_before = cursor.parse_number()?;
+        cursor.expect_str(" -> ")?;
+        let transitions_after = cursor.parse_number()?;
+        cursor.expect_end()?;
+
+        self.object_file.optimization_stats = Some(OptimizationStats {
+            nodes_before,
+            nodes_after,
+            transitions_before,
+            transitions_after,
+        });
+        Ok(())
+    }
+
+    fn finish(mut self) -> Result<ObjectFile, AnreError> {
+        if self.object_file.routes.is_empty() {
+            self.create_route();
+        }
+
+        for (route_index, route) in self.object_file.routes.iter_mut().enumerate() {
+            if !route.nodes.is_empty() && !self.has_entry_node[route_index] {
+                return Err(AnreError::SyntaxIncorrect(format!(
+                    "The entry node of route {} is missing.",
+                    route_index
+                )));
+            }
+
+            // The exit node is not marked if it is the entry node.
+            if !self.has_exit_node[route_index] {
+                route.end_node_index = route.start_node_index;
+            }
+        }
+
+        for (route_index, node_index, location) in &self.node_references {
+            if *node_index >= self.object_file.routes[*route_index].nodes.len() {
+                return Err(AnreError::MessageWithLocation(
+                    "The node does not exist.".to_owned(),
+                    *location,
+                ));
+            }
+        }
+
+        for (route_index, location) in &self.route_references {
+            if *route_index == MAIN_ROUTE_INDEX || *route_index >= self.object_file.routes.len() {
+                return Err(AnreError::MessageWithLocation(
+                    "The route does not exist.".to_owned(),
+                    *location,
+                ));
+            }
+        }
+
+        if let Some(route_index) = self.object_file.find_recursive_route() {
+            return Err(AnreError::SyntaxIncorrect(format!(
+                "The route {} runs itself through lookaround assertions.",
+                route_index
+            )));
+        }
+
+        for (capture_group_index, location) in &self.capture_group_references {
+            if *capture_group_index >= self.object_file.capture_group_names.len() {
+                return Err(AnreError::MessageWithLocation(
+                    "The capture group does not exist.".to_owned(),
+                    *location,
+                ));
+            }
+        }
+
+        self.object_file.prefilter = Prefilter::new(&self.object_file);
+        Ok(self.object_file)
+    }
+}
+
+// `[items]`, e.g. `['a', '0'..'9']`
+fn parse_char_set_items(cursor: &mut LineCursor) -> Result<Vec<CharSetItem>, AnreError> {
+    cursor.expect_str("[")?;
+
+    let mut items = vec![];
+    if cursor.consume_str("]") {
+        return Ok(items);
+    }
+
+    loop {
+        let start = cursor.position;
+        let c = cursor.parse_quoted_char('\'')?;
+        let item = if cursor.consume_str("..") {
+            let end_included = cursor.parse_quoted_char('\'')?;
+            if c > end_included {
+                return Err(cursor.error_at(
+                    start,
+                    "The start of the char range is greater than the end.",
+                    cursor.position - start,
+                ));
+            }
+            CharSetItem::Range(CharRange {
+                start: c as u32,
+                end_included: end_included as u32,
+            })
+        } else {
+            CharSetItem::Char(c as u32)
+        };
+        items.push(item);
+
+        if cursor.consume_str("]") {
+            return Ok(items);
+        }
+        cursor.expect_str(", ")?;
+    }
+}
+
+// `times N` or `from M to N`, the `N` may be `MAX`.
+fn parse_repetition_type(cursor: &mut LineCursor) -> Result<RepetitionType, AnreError> {
+    if cursor.consume_str("times ") {
+        Ok(RepetitionType::Specified(cursor.parse_number()?))
+    } else {
+        cursor.expect_str("from ")?;
+        let from = cursor.parse_number()?;
+        cursor.expect_str(" to ")?;
+        let to = if cursor.consume_str("MAX") {
+            usize::MAX
+        } else {
+            cursor.parse_number()?
+        };
+        Ok(RepetitionType::Range(from, to))
+    }
+}
+
+// The chars of a line and the current position.
+struct LineCursor {
+    chars: Vec<char>,
+    position: usize,
+
+    // The char index of the line start in the text.
+    index: usize,
+    line_number: usize,
+}
+
+impl LineCursor {
+    fn location(&self, column: usize, length: usize) -> Location {
+        Location::new_range(self.index + column, self.line_number, column, length)
+    }
+
+    fn error_at(&self, column: usize, message: &str, length: usize) -> AnreError {
+        AnreError::MessageWithLocation(message.to_owned(), self.location(column, length))
+    }
+
+    fn error(&self, message: &str, length: usize) -> AnreError {
+        self.error_at(self.position, message, length)
+    }
+
+    fn remaining(&self) -> usize {
+        self.chars.len() - self.position
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.position).copied()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek();
+        if c.is_some() {
+            self.position += 1;
+        }
+        c
+    }
+
+    fn consume_str(&mut self, s: &str) -> bool {
+        let mut position = self.position;
+        for c in s.chars() {
+            if self.chars.get(position) != Some(&c) {
+                return false;
+            }
+            position += 1;
+        }
+        self.position = position;
+        true
+    }
+
+    fn expect_str(&mut self, s: &str) -> Result<(), AnreError> {
+        if self.consume_str(s) {
+            Ok(())
+        } else {
+            Err(self.error(&format!("Expect \"{}\".", s), 0))
+        }
+    }
+
+    fn expect_end(&self) -> Result<(), AnreError> {
+        if self.position == self.chars.len() {
+            Ok(())
+        } else {
+            Err(self.error("Unexpected text.", self.remaining()))
+        }
+    }
+
+    fn parse_number(&mut self) -> Result<usize, AnreError> {
+        let start = self.position;
+        while matches!(self.peek(), Some('0'..='9')) {
+            self.position += 1;
+        }
+
+        let digits: String = self.chars[start..self.position].iter().collect();
+        digits
+            .parse::<usize>()
+            .map_err(|_| self.error_at(start, "Expect a number.", self.position - start))
+    }
+
+    // Parses a char in the quotes, the escape sequences are generated by
+    // the `Display` of the transitions.
+    fn parse_quoted_char(&mut self, quote: char) -> Result<char, AnreError> {
+        self.expect_str(&quote.to_string())?;
+
+        let c = match self.parse_char(quote)? {
+            Some(c) => c,
+            None => return Err(self.error("Expect a char.", 1)),
+        };
+
+        self.expect_str(&quote.to_string())?;
+        Ok(c)
+    }
+
+    fn parse_quoted_string(&mut self) -> Result<String, AnreError> {
+        self.expect_str("\"")?;
+
+        let mut s = String::new();
+        while let Some(c) = self.parse_char('"')? {
+            s.push(c);
+        }
+
+        self.expect_str("\"")?;
+        Ok(s)
+    }
+
+    // Returns `None` if the closing quote or the end of line is reached.
+    fn parse_char(&mut self, quote: char) -> Result<Option<char>, AnreError> {
+        let start = self.position;
+        let c = match self.peek() {
+            Some(c) if c != quote => c,
+            _ => return Ok(None),
+        };
+        self.position += 1;
+
+        if c != '\\' {
+            return Ok(Some(c));
+        }
+
+        let escaped = match self.next() {
+            Some('t') => '\t',
+            Some('r') => '\r',
+            Some('n') => '\n',
+            Some('\\') => '\\',
+            Some(c) if c == quote => c,
+            Some('u') => {
+                self.expect_str("{")?;
+                let hex_start = self.position;
+                while matches!(self.peek(), Some(c) if c.is_ascii_hexdigit()) {
+                    self.position += 1;
+                }
+                let hex: String = self.chars[hex_start..self.position].iter().collect();
+                self.expect_str("}")?;
+
+                match u32::from_str_radix(&hex, 16).ok().and_then(char::from_u32) {
+                    Some(c) => c,
+                    None => {
+                        return Err(self.error_at(
+                            start,
+                            "Invalid unicode escape sequence.",
+                            self.position - start,
+                        ))
+                    }
+                }
+            }
+            _ => {
+                return Err(self.error_at(start, "Unsupported escape char.", self.position - start))
+            }
+        };
+
+        Ok(Some(escaped))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::{assert_eq, assert_str_eq};
+
+    use crate::{
+        compiler::{compile_from_regex_with_options, CompileOptions},
+        location::Location,
+        object_file::ObjectFile,
+        optimizer::optimize,
+        AnreError, Regex,
+    };
+
+    use super::assemble;
+
+    fn assert_round_trip(object_file: &ObjectFile) {
+        let text = object_file.get_debug_text();
+        let assembled = assemble(&text).unwrap();
+        assert_str_eq!(assembled.get_debug_text(), text);
+    }
+
+    #[test]
+    fn test_assemble_round_trip() {
+        // covers all transitions
+        let patterns = [
+            r#"abc"#,                                     // Char, String, Jump, Capture start/end
+            r#"a.c"#,                                     // Any char
+            r#"(?s)a.c"#,                                 // Any char, include new line
+            r#"(?i)hello[a-f\d]+"#,                       // ignore case
+            r#"[^\w\s'\\"]"#,                             // Charset
+            r#"^(?<year>\d{4})-(?<month>\d{1,2})$"#,      // Anchor assertion, named groups
+            r#"(?m)^\w+$"#,                               // Anchor assertion, multi-line
+            r#"\bfoo\B"#,                                 // Boundary assertion
+            r#"(a|b)\1"#,                                 // Back reference
+            r#"(?:ab){2,}?c*"#,                           // Counter, Repetition
+            r#"x{3}y{2,5}z+"#,                            // Counter check, Repetition
+            r#"(?<=ab)cd(?=ef)"#,                         // Look behind, Look ahead
+            r#"(?<!ij)x(?!gh)"#,                          // negative lookaround
+            r#"one|two|three|four|five|six|seven|eight"#, // Trie
+            r#"'\t'|"\n"|文字|\u{7}"#,                    // escaped chars
+            r#""#,
+        ];
+
+        let options = [
+            CompileOptions::default(),
+            CompileOptions {
+                multi_line: true,
+                crlf: true,
+                dot_matches_any_byte: true,
+                case_insensitive: true,
+                ..CompileOptions::default()
+            },
+        ];
+
+        for pattern in patterns {
+            for options in &options {
+                let mut object_file = compile_from_regex_with_options(pattern, options).unwrap();
+                assert_round_trip(&object_file);
+
+                optimize(&mut object_file);
+                assert_round_trip(&object_file);
+
+                // the anchoring of routes
+                let route = &mut object_file.routes[0];
+                route.is_fixed_start_position = true;
+                route.is_fixed_end_position = true;
+                assert_round_trip(&object_file);
+            }
+        }
+
+        // the exit node is the entry node
+        let text = "\
+> 0
+  -> 0, Jump";
+        let object_file = assemble(text).unwrap();
+        assert_eq!(object_file.routes[0].end_node_index, 0);
+        assert_str_eq!(object_file.get_debug_text(), text);
+    }
+
+    #[test]
+    fn test_assemble_and_match() {
+        let text = "\
+- 0
+  -> 1, Char 'a'
+  -> 2, Char 'b'
+- 1
+  -> 2, Char 'b'
+- 2
+  -> 4, Capture end {0}
+> 3, fixed start
+  -> 0, Capture start {0}
+< 4
+# {0}";
+
+        let re = Regex::from_object_file(assemble(text).unwrap()).unwrap();
+        assert!(re.is_match("abx"));
+        assert!(re.is_match("bx"));
+        assert!(!re.is_match("xab"));
+        assert_eq!(re.find("abx").unwrap().as_str(), "ab");
+    }
+
+    #[test]
+    fn test_assemble_errors() {
+        let error = |text: &str| match assemble(text) {
+            Err(AnreError::MessageWithLocation(message, location)) => (message, location),
+            other => panic!("unexpected result: {:?}", other.map(|o| o.get_debug_text())),
+        };
+
+        // unknown transition
+        assert_eq!(
+            error("> 0\n  -> 1, Foo\n< 1"),
+            (
+                "Unknown transition.".to_owned(),
+                Location::new_range(12, 1, 8, 3)
+            )
+        );
+
+        // the words of the trie are not prefix-free
+        let message =
+            "The words of the trie must be non-empty and none of them can be a prefix of another.";
+        assert_eq!(
+            error("> 0\n  -> 1, Trie \"a\", \"ab\"\n< 1"),
+            (message.to_owned(), Location::new_range(17, 1, 13, 9))
+        );
+        assert_eq!(
+            error("> 0\n  -> 1, Trie \"\"\n< 1"),
+            (message.to_owned(), Location::new_range(17, 1, 13, 2))
+        );
+        assert_eq!(
+            error("> 0\n  -> 1, Trie \"ab\", \"A\", ignore case\n< 1"),
+            (message.to_owned(), Location::new_range(17, 1, 13, 9))
+        );
+
+        // the node index is not in order
+        assert_eq!(
+            error("> 0\n- 2"),
+            (
+                "Expect node index: 1.".to_owned(),
+                Location::new_range(6, 1, 2, 1)
+            )
+        );
+
+        // the target node does not exist
+        assert_eq!(
+            error("> 0\n  -> 5, Jump\n< 1"),
+            (
+                "The node does not exist.".to_owned(),
+                Location::new_range(9, 1, 5, 1)
+            )
+        );
+
+        // the capture group does not exist
+        assert_eq!(
+            error("> 0\n  -> 1, Capture start {1}\n< 1\n# {0}"),
+            (
+                "The capture group does not exist.".to_owned(),
+                Location::new_range(26, 1, 22, 3)
+            )
+        );
+
+        // the route does not exist
+        assert_eq!(
+            error("= $0\n> 0\n  -> 1, Look ahead $1\n< 1"),
+            (
+                "The route does not exist.".to_owned(),
+                Location::new_range(28, 2, 19, 2)
+            )
+        );
+
+        // unclosed char
+        assert_eq!(
+            error("> 0\n  -> 1, Char 'a\n< 1"),
+            (
+                "Expect \"'\".".to_owned(),
+                Location::new_range(19, 1, 15, 0)
+            )
+        );
+
+        // invalid char range
+        assert_eq!(
+            error("> 0\n  -> 1, Charset ['z'..'a']\n< 1"),
+            (
+                "The start of the char range is greater than the end.".to_owned(),
+                Location::new_range(21, 1, 17, 8)
+            )
+        );
+
+        // trailing text
+        assert_eq!(
+            error("> 0\n  -> 1, Jump Jump\n< 1"),
+            (
+                "Unexpected text.".to_owned(),
+                Location::new_range(16, 1, 12, 5)
+            )
+        );
+
+        // transition item without node
+        assert_eq!(
+            error("  -> 1, Jump"),
+            (
+                "The transition item must follow a node.".to_owned(),
+                Location::new_range(0, 0, 0, 12)
+            )
+        );
+
+        // the entry node is missing
+        assert_eq!(
+            assemble("- 0\n< 1").err(),
+            Some(AnreError::SyntaxIncorrect(
+                "The entry node of route 0 is missing.".to_owned()
+            ))
+        );
+
+        // the route runs itself
+        assert_eq!(
+            assemble(
+                "= $0\n> 0\n  -> 1, Look ahead $1\n< 1\n\
+                = $1\n> 0, fixed start\n  -> 1, Look ahead $1\n< 1\n# {0}"
+            )
+            .err(),
+            Some(AnreError::SyntaxIncorrect(
+                "The route 1 runs itself through lookaround assertions.".to_owned()
+            ))
+        );
+
+        // the routes run each other
+        assert_eq!(
+            assemble(
+                "= $0\n> 0\n  -> 1, Look ahead $1\n< 1\n\
+                = $1\n> 0, fixed start\n  -> 1, Look ahead $2\n< 1\n\
+                = $2\n> 0, fixed start\n  -> 1, Look behind $1, match length 1\n< 1\n# {0}"
+            )
+            .err(),
+            Some(AnreError::SyntaxIncorrect(
+                "The route 1 runs itself through lookaround assertions.".to_owned()
+            ))
+        );
+    }
+}
@@ -54,6 +54,33 @@ pub enum Expression {
     Or(Box<Expression>, Box<Expression>),
 }
 
+impl Expression {
+    // Flattens the right-associative `Or` expressions into the list
+    // of alternatives, e.g. `a || (b || c)` into `[a, b, c]`.
+    pub fn get_alternatives(&self) -> Vec<&Expression> {
+        let mut alternatives = vec![];
+        let mut expression = self;
+        while let Expression::Or(left, right) = expression {
+            alternatives.extend(left.get_alternatives());
+            expression = right;
+        }
+        alternatives.push(expression);
+        alternatives
+    }
+
+    // Returns the value of the number literal, e.g. the times of `repeat`.
+    //
+    // The arguments of the function calls should be checked by
+    // `rulechecker::check_program` first.
+    pub fn get_number(&self) -> usize {
+        if let Expression::Literal(Literal::Number(n)) = self {
+            *n
+        } else {
+            unreachable!()
+        }
+    }
+}
+
 #[derive(Debug, PartialEq)]
 pub struct FunctionCall {
     pub name: FunctionName,
@@ -204,4 +231,31 @@ pub enum FunctionName {
     // Capture/Match
     Name,
     Index,
+
+    // Modifiers, they change the matching behavior of the argument expression.
+    IgnoreCase,
+    MultiLine,
+    DotAll,
+    NotIgnoreCase,
+    NotMultiLine,
+    NotDotAll,
+}
+
+impl FunctionName {
+    pub fn is_modifier(&self) -> bool {
+        matches!(
+            self,
+            FunctionName::IgnoreCase
+                | FunctionName::MultiLine
+                | FunctionName::DotAll
+                | FunctionName::NotIgnoreCase
+                | FunctionName::NotMultiLine
+                | FunctionName::NotDotAll
+        )
+    }
+}
+
+// Quotes the char for displaying, e.g. `'a'` and `'\n'`.
+pub fn quote_char(c: char) -> String {
+    format!("'{}'", c.escape_debug())
 }
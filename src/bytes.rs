@@ -0,0 +1,486 @@
+// Copyright (c) 2025 Hemashushu <hippospark@gmail.com>, All rights reserved.
+//
+// This Source Code Form is subject to the terms of
+// the Mozilla Public License version 2.0 and additional exceptions.
+// For more details, see the LICENSE, LICENSE.additional, and CONTRIBUTING files.
+
+//! The regular expression for searching arbitrary byte slices,
+//! e.g. binary logs or Latin-1 files.
+//!
+//! The API is intended to be compatible with the module `bytes` of crate 'regex':
+//! https://docs.rs/regex/latest/regex/bytes/index.html
+//!
+//! The text does not need to be valid UTF-8, each invalid byte is treated as
+//! a single char which does not equal any Unicode char, e.g. it is matched by
+//! the "char_any" (`.`) and the negative charsets (e.g. `[^a]`), but never
+//! matched by a literal char or a positive charset.
+
+use std::{
+    ops::{Index, Range},
+    time::Duration,
+};
+
+use crate::{
+    context::Context,
+    regex::{self, MatchCursor},
+    Anchored, AnreError, Engine, MatchError,
+};
+
+pub struct Regex {
+    regex: regex::Regex,
+}
+
+impl Regex {
+    pub fn new(pattern: &str) -> Result<Self, AnreError> {
+        let regex = regex::Regex::new(pattern)?;
+        Ok(Regex { regex })
+    }
+
+    pub fn from_anre(expression: &str) -> Result<Self, AnreError> {
+        let regex = regex::Regex::from_anre(expression)?;
+        Ok(Regex { regex })
+    }
+
+    /// Returns the engine selected for matching.
+    pub fn engine(&self) -> Engine {
+        self.regex.engine()
+    }
+
+    /// Returns the leftmost-first match, see `crate::Regex::find`.
+    pub fn find<'a, 'b>(&'a self, text: &'b [u8]) -> Option<Match<'a, 'b>> {
+        self.try_find(text).unwrap_or(None)
+    }
+
+    /// Returns the leftmost-first match, or an error if the limits are exceeded.
+    pub fn try_find<'a, 'b>(&'a self, text: &'b [u8]) -> Result<Option<Match<'a, 'b>>, MatchError> {
+        self.regex
+            .execute_pooled(text, 0..text.len(), Anchored::No, |context| {
+                self.new_match(text, context, 0)
+            })
+    }
+
+    pub fn find_iter<'a, 'b>(&'a self, text: &'b [u8]) -> Matches<'a, 'b> {
+        let number_of_capture_groups = self.regex.object_file.capture_group_names.len();
+        let context = Context::from_bytes(text, number_of_capture_groups);
+
+        Matches {
+            regex: self,
+            context,
+            cursor: MatchCursor::new(),
+        }
+    }
+
+    pub fn captures<'a, 'b>(&'a self, text: &'b [u8]) -> Option<Captures<'a, 'b>> {
+        self.try_captures(text).unwrap_or(None)
+    }
+
+    /// Returns the capture groups of the leftmost-first match,
+    /// or an error if the limits are exceeded.
+    pub fn try_captures<'a, 'b>(
+        &'a self,
+        text: &'b [u8],
+    ) -> Result<Option<Captures<'a, 'b>>, MatchError> {
+        self.regex
+            .execute_pooled(text, 0..text.len(), Anchored::No, |context| {
+                self.new_captures(text, context)
+            })
+    }
+
+    pub fn captures_iter<'a, 'b>(&'a self, text: &'b [u8]) -> CaptureMatches<'a, 'b> {
+        let number_of_capture_groups = self.regex.object_file.capture_group_names.len();
+        let context = Context::from_bytes(text, number_of_capture_groups);
+
+        CaptureMatches {
+            regex: self,
+            context,
+            cursor: MatchCursor::new(),
+        }
+    }
+
+    /// Returns `false` if the limits are exceeded,
+    /// use `try_is_match` to tell it from no match.
+    pub fn is_match(&self, text: &[u8]) -> bool {
+        self.try_is_match(text).unwrap_or(false)
+    }
+
+    /// Returns whether the text matches, or an error if the limits are exceeded.
+    pub fn try_is_match(&self, text: &[u8]) -> Result<bool, MatchError> {
+        self.regex
+            .is_match_pooled(text, 0..text.len(), Anchored::No)
+    }
+
+    fn new_match<'a, 'b>(
+        &'a self,
+        text: &'b [u8],
+        context: &Context,
+        index: usize,
+    ) -> Match<'a, 'b> {
+        let match_range = &context.match_ranges[index];
+        Match::new(
+            match_range.start,
+            match_range.end,
+            self.regex
+                .object_file
+                .get_capture_group_name_by_index(index),
+            &text[match_range.start..match_range.end],
+        )
+    }
+
+    fn new_captures<'a, 'b>(&'a self, text: &'b [u8], context: &Context) -> Captures<'a, 'b> {
+        let matches: Vec<Match> = (0..context.match_ranges.len())
+            .map(|idx| self.new_match(text, context, idx))
+            .collect();
+
+        Captures { matches }
+    }
+}
+
+/// A builder for configuring the options of a `bytes::Regex`,
+/// see `crate::RegexBuilder` for the common options.
+pub struct RegexBuilder {
+    builder: regex::RegexBuilder,
+}
+
+impl RegexBuilder {
+    /// Creates a builder for the traditional regular expression.
+    pub fn new(pattern: &str) -> Self {
+        RegexBuilder {
+            builder: regex::RegexBuilder::new(pattern),
+        }
+    }
+
+    /// Creates a builder for the ANRE expression.
+    pub fn from_anre(expression: &str) -> Self {
+        RegexBuilder {
+            builder: regex::RegexBuilder::from_anre(expression),
+        }
+    }
+
+    pub fn case_insensitive(&mut self, yes: bool) -> &mut Self {
+        self.builder.case_insensitive(yes);
+        self
+    }
+
+    pub fn multi_line(&mut self, yes: bool) -> &mut Self {
+        self.builder.multi_line(yes);
+        self
+    }
+
+    pub fn dot_matches_new_line(&mut self, yes: bool) -> &mut Self {
+        self.builder.dot_matches_new_line(yes);
+        self
+    }
+
+    pub fn swap_greed(&mut self, yes: bool) -> &mut Self {
+        self.builder.swap_greed(yes);
+        self
+    }
+
+    pub fn crlf(&mut self, yes: bool) -> &mut Self {
+        self.builder.crlf(yes);
+        self
+    }
+
+    /// Anchors every match of the expression, see `regex::RegexBuilder::anchored`.
+    pub fn anchored(&mut self, anchored: Anchored) -> &mut Self {
+        self.builder.anchored(anchored);
+        self
+    }
+
+    /// Sets the matching engine, the default is `Engine::Auto`.
+    ///
+    /// Forcing `Engine::PikeVm` on an expression with back references,
+    /// or with "char_any" when the Unicode mode is disabled,
+    /// causes an error when building.
+    pub fn engine(&mut self, engine: Engine) -> &mut Self {
+        self.builder.engine(engine);
+        self
+    }
+
+    pub fn step_limit(&mut self, limit: usize) -> &mut Self {
+        self.builder.step_limit(limit);
+        self
+    }
+
+    pub fn time_limit(&mut self, limit: Duration) -> &mut Self {
+        self.builder.time_limit(limit);
+        self
+    }
+
+    /// Uses the lazy DFA to find the matches, see `regex::RegexBuilder::lazy_dfa`.
+    pub fn lazy_dfa(&mut self, yes: bool) -> &mut Self {
+        self.builder.lazy_dfa(yes);
+        self
+    }
+
+    /// Optimizes the compiled expression, see `regex::RegexBuilder::optimize`.
+    pub fn optimize(&mut self, yes: bool) -> &mut Self {
+        self.builder.optimize(yes);
+        self
+    }
+
+    /// When the Unicode mode is disabled, the "char_any" (`.`) matches
+    /// any single byte (except '\n' and '\r', unless `dot_matches_new_line`
+    /// is enabled) instead of a UTF-8 char.
+    ///
+    /// The default is enabled.
+    pub fn unicode(&mut self, yes: bool) -> &mut Self {
+        self.builder.options.dot_matches_any_byte = !yes;
+        self
+    }
+
+    pub fn build(&self) -> Result<Regex, AnreError> {
+        let regex = self.builder.build()?;
+        Ok(Regex { regex })
+    }
+}
+
+pub struct CaptureMatches<'a, 'b> {
+    regex: &'a Regex,
+    context: Context<'b>,
+    cursor: MatchCursor,
+}
+
+impl<'a, 'b> Iterator for CaptureMatches<'a, 'b> {
+    type Item = Captures<'a, 'b>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // The iteration stops if the limits are exceeded.
+        if !self.cursor.next(&self.regex.regex, &mut self.context) {
+            return None;
+        }
+
+        let captures = self.regex.new_captures(self.context.bytes, &self.context);
+        Some(captures)
+    }
+}
+
+pub struct Matches<'a, 'b> {
+    regex: &'a Regex,
+    context: Context<'b>,
+    cursor: MatchCursor,
+}
+
+impl<'a, 'b> Iterator for Matches<'a, 'b> {
+    type Item = Match<'a, 'b>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // The iteration stops if the limits are exceeded.
+        if !self.cursor.next(&self.regex.regex, &mut self.context) {
+            return None;
+        }
+
+        let match_ = self.regex.new_match(self.context.bytes, &self.context, 0);
+        Some(match_)
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Captures<'a, 'b> {
+    pub matches: Vec<Match<'a, 'b>>,
+}
+
+impl<'a, 'b> Captures<'a, 'b> {
+    pub fn get(&self, index: usize) -> Option<&Match<'a, 'b>> {
+        self.matches.get(index)
+    }
+
+    pub fn name(&self, name: &str) -> Option<&Match<'a, 'b>> {
+        self.matches.iter().find(|item| match item.name {
+            Some(s) => s == name,
+            None => false,
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.matches.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl Index<usize> for Captures<'_, '_> {
+    type Output = [u8];
+
+    fn index(&self, index: usize) -> &Self::Output {
+        self.get(index)
+            .unwrap_or_else(|| panic!(
+                "Index {} is out of range of the capture group and the length of capture groups is {}.",
+                index, self.len()))
+            .as_bytes()
+    }
+}
+
+impl Index<&str> for Captures<'_, '_> {
+    type Output = [u8];
+
+    fn index(&self, name: &str) -> &Self::Output {
+        self.name(name)
+            .unwrap_or_else(|| panic!("Cannot find the capture group named \"{}\".", name))
+            .as_bytes()
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Match<'a, 'b> {
+    pub start: usize, // the position of byte stream (value included)
+    pub end: usize,   // the position of byte stream (value excluded)
+    pub name: Option<&'a str>,
+    pub value: &'b [u8],
+}
+
+impl<'a, 'b> Match<'a, 'b> {
+    pub fn new(start: usize, end: usize, name: Option<&'a str>, value: &'b [u8]) -> Self {
+        Match {
+            start,
+            end,
+            name,
+            value,
+        }
+    }
+
+    pub fn start(&self) -> usize {
+        self.start
+    }
+
+    pub fn end(&self) -> usize {
+        self.end
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn len(&self) -> usize {
+        self.end - self.start
+    }
+
+    pub fn range(&self) -> Range<usize> {
+        Range {
+            start: self.start,
+            end: self.end,
+        }
+    }
+
+    pub fn as_bytes(&self) -> &'b [u8] {
+        self.value
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use crate::Engine;
+
+    use super::{Match, Regex, RegexBuilder};
+
+    fn new_match(start: usize, end: usize, value: &[u8]) -> Match<'_, '_> {
+        Match::new(start, end, None, value)
+    }
+
+    #[test]
+    fn test_bytes_find() {
+        // binary data
+        let re = Regex::new(r"[a-z]+").unwrap();
+        let text = b"\x00\xff\xfeabc\x80\x81def\xc0";
+
+        assert_eq!(re.find(text), Some(new_match(3, 6, b"abc")));
+        assert_eq!(
+            re.find_iter(text).collect::<Vec<_>>(),
+            vec![new_match(3, 6, b"abc"), new_match(8, 11, b"def")]
+        );
+
+        // valid UTF-8 chars are still matched as chars
+        let re = Regex::new(r"文.").unwrap();
+        assert_eq!(
+            re.find("a文字b".as_bytes()),
+            Some(new_match(1, 7, "文字".as_bytes()))
+        );
+
+        // the invalid bytes are matched by "char_any" and negative charsets
+        let re = Regex::new(r"a.b").unwrap();
+        assert!(re.is_match(b"a\xffb"));
+        assert!(!re.is_match(b"a\xff\xffb"));
+
+        let re = Regex::new(r"a[^\n]+b").unwrap();
+        assert_eq!(
+            re.find(b"xa\xe6\x96b"),
+            Some(new_match(1, 5, b"a\xe6\x96b"))
+        );
+
+        // the invalid bytes are not word chars
+        let re = Regex::new(r"\b\w+\b").unwrap();
+        assert_eq!(
+            re.find_iter(b"\xe9t\xe9 caf\xe9").collect::<Vec<_>>(),
+            vec![new_match(1, 2, b"t"), new_match(4, 7, b"caf")]
+        );
+
+        // lookbehind
+        let re = Regex::new(r"(?<=a.)b").unwrap();
+        assert_eq!(re.find(b"a\xffb"), Some(new_match(2, 3, b"b")));
+    }
+
+    #[test]
+    fn test_bytes_captures() {
+        let re = Regex::new(r"(?<key>\w+)=(?<value>[^;]*);").unwrap();
+        let text = b"\x01name=caf\xe9;\x02id=42;";
+
+        let captures = re.captures(text).unwrap();
+        assert_eq!(&captures[0], b"name=caf\xe9;");
+        assert_eq!(&captures["key"], b"name");
+        assert_eq!(&captures["value"], b"caf\xe9");
+        assert_eq!(captures.get(2).unwrap().range(), 6..10);
+
+        let values: Vec<&[u8]> = re
+            .captures_iter(text)
+            .map(|captures| captures.name("value").unwrap().as_bytes())
+            .collect();
+        assert_eq!(values, vec![&b"caf\xe9"[..], &b"42"[..]]);
+    }
+
+    #[test]
+    fn test_bytes_unicode_mode() {
+        // the "char_any" matches a whole UTF-8 char by default
+        let re = Regex::new(r"^.$").unwrap();
+        assert!(re.is_match("文".as_bytes()));
+
+        // the "char_any" matches a single byte
+        let re = RegexBuilder::new(r"^...$").unicode(false).build().unwrap();
+        assert_ne!(re.engine(), Engine::PikeVm);
+        assert!(re.is_match("文".as_bytes()));
+        assert!(re.is_match(b"\x00\xff\x80"));
+        assert!(!re.is_match(b"a\nb"));
+        assert!(!re.is_match("文a".as_bytes()));
+
+        let re = RegexBuilder::new(r"a.{2}b")
+            .unicode(false)
+            .dot_matches_new_line(true)
+            .build()
+            .unwrap();
+        assert_eq!(re.find(b"xa\n\xe6bz"), Some(new_match(1, 5, b"a\n\xe6b")));
+
+        // the literal chars are still matched as UTF-8 chars
+        let re = RegexBuilder::new(r"文.").unicode(false).build().unwrap();
+        assert_eq!(
+            re.find("文字".as_bytes()),
+            Some(new_match(0, 4, b"\xe6\x96\x87\xe5"))
+        );
+
+        // the Pike VM engine does not support matching any byte
+        assert!(RegexBuilder::new(r".")
+            .unicode(false)
+            .engine(Engine::PikeVm)
+            .build()
+            .is_err());
+
+        let re = RegexBuilder::new(r"^...$")
+            .unicode(false)
+            .engine(Engine::Backtracking)
+            .build()
+            .unwrap();
+        assert!(re.is_match("文".as_bytes()));
+    }
+}
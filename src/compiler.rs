@@ -11,15 +11,16 @@ use crate::{
         Program,
     },
     object_file::{ObjectFile, Route},
-    rulechecker::{get_match_length, MatchLength},
+    prefilter::Prefilter,
+    rulechecker::{check_program, get_match_length, MatchLength},
     transition::{
-        add_char, add_preset_digit, add_preset_space, add_preset_word, add_range,
+        add_char, add_preset_digit, add_preset_space, add_preset_word, add_range, fold_case,
         AnchorAssertionTransition, BackReferenceTransition, BoundaryAssertionTransition,
         CaptureEndTransition, CaptureStartTransition, CharSetItem, CharSetTransition,
         CharTransition, CounterCheckTransition, CounterIncTransition, CounterResetTransition,
         CounterSaveTransition, JumpTransition, LookAheadAssertionTransition,
         LookBehindAssertionTransition, RepetitionTransition, RepetitionType, SpecialCharTransition,
-        StringTransition, Transition,
+        StringTransition, Transition, TrieTransition,
     },
     AnreError,
 };
@@ -38,13 +39,67 @@ pub fn compile_from_anre(s: &str) -> Result<ObjectFile, AnreError> {
 
 /// Compile from AST `Program`.
 pub fn compile(program: &Program) -> Result<ObjectFile, AnreError> {
+    compile_with_options(program, &CompileOptions::default())
+}
+
+/// Compile from traditional regular expression with the specified options.
+pub fn compile_from_regex_with_options(
+    s: &str,
+    options: &CompileOptions,
+) -> Result<ObjectFile, AnreError> {
+    let program = crate::traditional::parse_from_str(s)?;
+    compile_with_options(&program, options)
+}
+
+/// Compile from ANRE regular expression with the specified options.
+pub fn compile_from_anre_with_options(
+    s: &str,
+    options: &CompileOptions,
+) -> Result<ObjectFile, AnreError> {
+    let program = crate::anre::parse_from_str(s)?;
+    compile_with_options(&program, options)
+}
+
+/// Compile from AST `Program` with the specified options.
+pub fn compile_with_options(
+    program: &Program,
+    options: &CompileOptions,
+) -> Result<ObjectFile, AnreError> {
+    check_program(program)?;
+
     let mut route = ObjectFile::new();
-    let mut compiler = Compiler::new(program, &mut route);
+    let mut compiler = Compiler::new(program, &mut route, options.clone());
     compiler.compile()?;
 
+    route.prefilter = Prefilter::new(&route);
     Ok(route)
 }
 
+/// The options that change the behavior of the generated transitions.
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct CompileOptions {
+    // Letters match both their uppercase and lowercase forms.
+    pub case_insensitive: bool,
+
+    // The assertions "start" (`^`) and "end" (`$`) also match at
+    // the beginning and end of lines.
+    pub multi_line: bool,
+
+    // The "char_any" (`.`) also matches '\n' and '\r'.
+    pub dot_matches_new_line: bool,
+
+    // Greedy quantifiers become lazy and lazy quantifiers become greedy.
+    pub swap_greed: bool,
+
+    // In multi-line mode, '\r' is also treated as a line terminator, and
+    // the assertions never match between '\r' and '\n'.
+    pub crlf: bool,
+
+    // The "char_any" (`.`) matches a single byte instead of a UTF-8 char,
+    // it is used for searching byte slices, see `bytes::RegexBuilder::unicode`.
+    pub dot_matches_any_byte: bool,
+}
+
 pub struct Compiler<'a> {
     // The AST
     program: &'a Program,
@@ -54,15 +109,19 @@ pub struct Compiler<'a> {
 
     // Index of the current route
     current_route_index: usize,
+
+    // The compilation options
+    options: CompileOptions,
 }
 
 impl<'a> Compiler<'a> {
-    fn new(program: &'a Program, object_file: &'a mut ObjectFile) -> Self {
+    fn new(program: &'a Program, object_file: &'a mut ObjectFile, options: CompileOptions) -> Self {
         let current_route_index = object_file.create_route();
         Compiler {
             program,
             object_file,
             current_route_index,
+            options,
         }
     }
 
@@ -97,13 +156,26 @@ impl<'a> Compiler<'a> {
         // Create the first (index 0) capture group to represent the program itself.
         let capture_group_index = self.object_file.create_capture_group(None);
 
-        let expressions = &program.expressions;
+        // The modifiers at the top level of the program are flattened, so that
+        // the assertions "start" and "end" inside them can still be checked,
+        // e.g. `(?i)^abc$` and `^(?i:abc)$`.
+        let mut items = vec![];
+        flatten_modifiers(&program.expressions, &self.options, &mut items);
+
+        let base_options = self.options.clone();
+        let expression_count = items.len();
 
         let mut is_fixed_start_position = false;
         let mut components = vec![];
 
-        for (expression_index, expression) in expressions.iter().enumerate() {
-            if matches!(
+        for (expression_index, (expression, options)) in items.into_iter().enumerate() {
+            self.options = options;
+
+            if self.options.multi_line {
+                // In multi-line mode, the assertions "start" and "end" can
+                // be present anywhere, and the start position is not fixed.
+                components.push(self.emit_expression(expression)?);
+            } else if matches!(
                 expression,
                 Expression::AnchorAssertion(AnchorAssertionName::Start)
             ) {
@@ -120,7 +192,7 @@ impl<'a> Compiler<'a> {
                 expression,
                 Expression::AnchorAssertion(AnchorAssertionName::End)
             ) {
-                if expression_index != expressions.len() - 1 {
+                if expression_index != expression_count - 1 {
                     return Err(AnreError::SyntaxIncorrect(
                         "The assertion \"end\" can only be present at the end of expression."
                             .to_owned(),
@@ -133,6 +205,8 @@ impl<'a> Compiler<'a> {
             }
         }
 
+        self.options = base_options;
+
         let program_component = if components.is_empty() {
             // empty expression
             self.emit_empty()?
@@ -210,6 +284,9 @@ impl<'a> Compiler<'a> {
             // }
             Expression::Literal(literal) => self.emit_literal(literal)?,
             Expression::BackReference(back_reference) => self.emit_backreference(back_reference)?,
+            Expression::AnchorAssertion(name) if self.options.multi_line => {
+                self.emit_anchor_assertion(name)?
+            }
             Expression::AnchorAssertion(name) => {
                 // syntax error
                 match name {
@@ -228,7 +305,12 @@ impl<'a> Compiler<'a> {
             Expression::BoundaryAssertion(name) => self.emit_boundary_assertion(name)?,
             Expression::Group(expressions) => self.emit_group(expressions)?,
             Expression::FunctionCall(function_call) => self.emit_function_call(function_call)?,
-            Expression::Or(left, right) => self.emit_logic_or(left, right)?,
+            Expression::Or(left, right) => {
+                match get_literal_alternatives(expression, self.options.case_insensitive) {
+                    Some(words) => self.emit_literal_alternatives(words)?,
+                    None => self.emit_logic_or(left, right)?,
+                }
+            }
         };
 
         Ok(result)
@@ -355,7 +437,37 @@ impl<'a> Compiler<'a> {
         Ok(Component::new(in_state_index, out_state_index))
     }
 
+    fn emit_literal_alternatives(&mut self, words: Vec<String>) -> Result<Component, AnreError> {
+        // A large alternation of literals (e.g. a list of keywords) is compiled
+        // into a single trie transition instead of a deep chain of branches,
+        // so the text is scanned only once at each position.
+        //
+        // ```diagram
+        //         trie trans
+        //  in  /-------------\  out
+        // ==o==-------------==o==
+        //   |                 |
+        //   \--- component ---/
+        // ```
+        let trie_transition = TrieTransition::new(words, self.options.case_insensitive);
+
+        let route = self.get_current_route_ref_mut();
+        let in_node_index = route.create_node();
+        let out_node_index = route.create_node();
+        let transition = Transition::Trie(trie_transition);
+
+        route.create_transition_item(in_node_index, out_node_index, transition);
+        Ok(Component::new(in_node_index, out_node_index))
+    }
+
     fn emit_function_call(&mut self, function_call: &FunctionCall) -> Result<Component, AnreError> {
+        if function_call.args.is_empty() {
+            return Err(AnreError::SyntaxIncorrect(format!(
+                "Expect an expression for the argument of function \"{}\".",
+                function_call.name
+            )));
+        }
+
         let expression = &function_call.args[0];
         let args = &function_call.args[1..];
 
@@ -366,7 +478,7 @@ impl<'a> Compiler<'a> {
                 | FunctionName::ZeroOrMoreLazy
                 | FunctionName::RepeatRangeLazy
                 | FunctionName::AtLeastLazy
-        );
+        ) ^ self.options.swap_greed;
 
         match &function_call.name {
             // Quantifier
@@ -502,9 +614,41 @@ impl<'a> Compiler<'a> {
             // Capture
             FunctionName::Name => self.emit_capture_group_by_name(expression, args),
             FunctionName::Index => self.emit_capture_group_by_index(expression),
+
+            // Modifiers
+            FunctionName::IgnoreCase
+            | FunctionName::MultiLine
+            | FunctionName::DotAll
+            | FunctionName::NotIgnoreCase
+            | FunctionName::NotMultiLine
+            | FunctionName::NotDotAll => {
+                if !args.is_empty() {
+                    return Err(AnreError::SyntaxIncorrect(format!(
+                        "The modifier \"{}\" accepts only one expression.",
+                        function_call.name
+                    )));
+                }
+
+                self.emit_modifier(expression, &function_call.name)
+            }
         }
     }
 
+    fn emit_modifier(
+        &mut self,
+        expression: &Expression,
+        name: &FunctionName,
+    ) -> Result<Component, AnreError> {
+        // the modifier only applies to the argument expression,
+        // so save the current options and restore them after compiling.
+        let saved_options = self.options.clone();
+        apply_modifier(&mut self.options, name);
+
+        let result = self.emit_expression(expression);
+        self.options = saved_options;
+        result
+    }
+
     /// Short-cut component.
     fn emit_empty(&mut self) -> Result<Component, AnreError> {
         let route = self.get_current_route_ref_mut();
@@ -538,30 +682,47 @@ impl<'a> Compiler<'a> {
     }
 
     fn emit_literal_char(&mut self, character: char) -> Result<Component, AnreError> {
+        let char_transition = if self.options.case_insensitive {
+            CharTransition::new_ignore_case(character)
+        } else {
+            CharTransition::new(character)
+        };
+
         let route = self.get_current_route_ref_mut();
         let in_node_index = route.create_node();
         let out_node_index = route.create_node();
-        let transition = Transition::Char(CharTransition::new(character));
+        let transition = Transition::Char(char_transition);
 
         route.create_transition_item(in_node_index, out_node_index, transition);
         Ok(Component::new(in_node_index, out_node_index))
     }
 
     fn emit_literal_special_char(&mut self) -> Result<Component, AnreError> {
+        let dot_matches_new_line = self.options.dot_matches_new_line;
+        let dot_matches_any_byte = self.options.dot_matches_any_byte;
         let route = self.get_current_route_ref_mut();
         let in_out_index = route.create_node();
         let out_out_index = route.create_node();
-        let transition = Transition::SpecialChar(SpecialCharTransition);
+        let transition = Transition::SpecialChar(SpecialCharTransition::new(
+            dot_matches_new_line,
+            dot_matches_any_byte,
+        ));
 
         route.create_transition_item(in_out_index, out_out_index, transition);
         Ok(Component::new(in_out_index, out_out_index))
     }
 
     fn emit_literal_string(&mut self, s: &str) -> Result<Component, AnreError> {
+        let string_transition = if self.options.case_insensitive {
+            StringTransition::new_ignore_case(s)
+        } else {
+            StringTransition::new(s)
+        };
+
         let route = self.get_current_route_ref_mut();
         let in_node_index = route.create_node();
         let out_node_index = route.create_node();
-        let transition = Transition::String(StringTransition::new(s));
+        let transition = Transition::String(string_transition);
 
         route.create_transition_item(in_node_index, out_node_index, transition);
         Ok(Component::new(in_node_index, out_node_index))
@@ -591,14 +752,19 @@ impl<'a> Compiler<'a> {
     }
 
     fn emit_literal_charset(&mut self, charset: &CharSet) -> Result<Component, AnreError> {
-        let route = self.get_current_route_ref_mut();
-        let in_node_index = route.create_node();
-        let out_node_index = route.create_node();
-
         let mut items: Vec<CharSetItem> = vec![];
         append_charset(charset, &mut items)?;
 
-        let transition = Transition::CharSet(CharSetTransition::new(items, charset.negative));
+        let charset_transition = if self.options.case_insensitive {
+            CharSetTransition::new_ignore_case(items, charset.negative)
+        } else {
+            CharSetTransition::new(items, charset.negative)
+        };
+
+        let route = self.get_current_route_ref_mut();
+        let in_node_index = route.create_node();
+        let out_node_index = route.create_node();
+        let transition = Transition::CharSet(charset_transition);
         route.create_transition_item(in_node_index, out_node_index, transition);
         Ok(Component::new(in_node_index, out_node_index))
     }
@@ -607,10 +773,16 @@ impl<'a> Compiler<'a> {
         &mut self,
         name: &AnchorAssertionName,
     ) -> Result<Component, AnreError> {
+        let anchor_assertion_transition = if self.options.multi_line {
+            AnchorAssertionTransition::new_multi_line(*name, self.options.crlf)
+        } else {
+            AnchorAssertionTransition::new(*name)
+        };
+
         let route = self.get_current_route_ref_mut();
         let in_node_index = route.create_node();
         let out_node_index = route.create_node();
-        let transition = Transition::AnchorAssertion(AnchorAssertionTransition::new(*name));
+        let transition = Transition::AnchorAssertion(anchor_assertion_transition);
 
         route.create_transition_item(in_node_index, out_node_index, transition);
         Ok(Component::new(in_node_index, out_node_index))
@@ -686,10 +858,11 @@ impl<'a> Compiler<'a> {
         expression: &Expression,
         args: &[Expression],
     ) -> Result<Component, AnreError> {
-        let name = if let Expression::Literal(Literal::String(s)) = &args[0] {
-            s.to_owned()
-        } else {
-            unreachable!();
+        // the name can be a string or an identifier, e.g. `name(char_word+, tag_name)`
+        let name = match &args[0] {
+            Expression::Literal(Literal::String(s))
+            | Expression::BackReference(BackReference::Name(s)) => s.to_owned(),
+            _ => unreachable!(),
         };
 
         self.continue_emit_capture_group(expression, Some(name))
@@ -1086,6 +1259,110 @@ impl Component {
     }
 }
 
+fn apply_modifier(options: &mut CompileOptions, name: &FunctionName) {
+    match name {
+        FunctionName::IgnoreCase => options.case_insensitive = true,
+        FunctionName::MultiLine => options.multi_line = true,
+        FunctionName::DotAll => options.dot_matches_new_line = true,
+        FunctionName::NotIgnoreCase => options.case_insensitive = false,
+        FunctionName::NotMultiLine => options.multi_line = false,
+        FunctionName::NotDotAll => options.dot_matches_new_line = false,
+        _ => unreachable!(),
+    }
+}
+
+// Expand the modifiers recursively, and pair each expression with
+// the options that apply to it.
+fn flatten_modifiers<'b>(
+    expressions: &'b [Expression],
+    options: &CompileOptions,
+    items: &mut Vec<(&'b Expression, CompileOptions)>,
+) {
+    for expression in expressions {
+        if let Expression::FunctionCall(function_call) = expression {
+            if function_call.name.is_modifier() && function_call.args.len() == 1 {
+                let mut modified_options = options.clone();
+                apply_modifier(&mut modified_options, &function_call.name);
+
+                match &function_call.args[0] {
+                    Expression::Group(sub_expressions) => {
+                        flatten_modifiers(sub_expressions, &modified_options, items)
+                    }
+                    sub_expression => flatten_modifiers(
+                        std::slice::from_ref(sub_expression),
+                        &modified_options,
+                        items,
+                    ),
+                }
+                continue;
+            }
+        }
+
+        items.push((expression, options.clone()));
+    }
+}
+
+// The minimum number of alternatives to compile into a trie,
+// a short alternation is compiled into branches.
+const MIN_TRIE_ALTERNATIVES: usize = 8;
+
+// Returns the strings of an alternation which consists only of
+// literal chars and strings, e.g. `"foo" || "bar" || 'x'` and `foo|bar|x`.
+//
+// Returns `None` if there are too few alternatives, or a string is
+// a prefix of another string (e.g. `"foo" || "foobar"`), since the
+// alternatives are tried in order, and a trie can not keep the priority.
+fn get_literal_alternatives(expression: &Expression, ignore_case: bool) -> Option<Vec<String>> {
+    let mut words = vec![];
+    if !collect_literal_alternatives(expression, &mut words) || words.len() < MIN_TRIE_ALTERNATIVES
+    {
+        return None;
+    }
+
+    let mut keys: Vec<Vec<u32>> = words
+        .iter()
+        .map(|word| {
+            word.chars()
+                .map(|c| {
+                    if ignore_case {
+                        fold_case(c as u32)
+                    } else {
+                        c as u32
+                    }
+                })
+                .collect()
+        })
+        .collect();
+
+    // After sorting, a string which is a prefix of other strings
+    // is followed by one of them.
+    keys.sort();
+    keys.dedup();
+    let is_prefix_free = keys.windows(2).all(|pair| !pair[1].starts_with(&pair[0]));
+
+    is_prefix_free.then_some(words)
+}
+
+fn collect_literal_alternatives(expression: &Expression, words: &mut Vec<String>) -> bool {
+    match expression {
+        Expression::Or(left, right) => {
+            collect_literal_alternatives(left, words) && collect_literal_alternatives(right, words)
+        }
+        Expression::Group(expressions) if expressions.len() == 1 => {
+            collect_literal_alternatives(&expressions[0], words)
+        }
+        Expression::Literal(Literal::Char(c)) => {
+            words.push(c.to_string());
+            true
+        }
+        Expression::Literal(Literal::String(s)) if !s.is_empty() => {
+            words.push(s.to_owned());
+            true
+        }
+        _ => false,
+    }
+}
+
 fn append_preset_charset_positive_only(
     name: &PresetCharSetName,
     items: &mut Vec<CharSetItem>,
@@ -1141,7 +1418,10 @@ mod tests {
         AnreError,
     };
 
-    use super::{compile_from_anre, compile_from_regex};
+    use super::{
+        compile_from_anre, compile_from_anre_with_options, compile_from_regex,
+        compile_from_regex_with_options, CompileOptions,
+    };
 
     fn generate_routes(anre: &str, regex: &str) -> [ObjectFile; 2] {
         [
@@ -1444,6 +1724,61 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_compile_literal_alternatives() {
+        // many literals are compiled into a trie
+        for route in generate_routes(
+            r#""one" || "two" || "three" || "four" || "five" || 'x' || "seven" || "eight""#,
+            r#"one|two|three|four|five|x|seven|eight"#,
+        ) {
+            let s = route.get_debug_text();
+
+            assert_str_eq!(
+                s,
+                "\
+- 0
+  -> 1, Trie \"one\", \"two\", \"three\", \"four\", \"five\", \"x\", \"seven\", \"eight\"
+- 1
+  -> 3, Capture end {0}
+> 2
+  -> 0, Capture start {0}
+< 3
+# {0}"
+            );
+        }
+
+        // a string is the prefix of another string
+        {
+            let route = compile_from_regex(r#"a|b|c|d|e|f|g|gh"#).unwrap();
+            let s = route.get_debug_text();
+            assert!(!s.contains("Trie"));
+        }
+
+        // the strings are the same after case folding
+        {
+            let options = CompileOptions {
+                case_insensitive: true,
+                ..CompileOptions::default()
+            };
+            let route = compile_from_regex_with_options(r#"a|b|c|d|e|f|g|G"#, &options).unwrap();
+            let s = route.get_debug_text();
+            assert!(s.contains(
+                "Trie \"a\", \"b\", \"c\", \"d\", \"e\", \"f\", \"g\", \"G\", ignore case"
+            ));
+
+            let route = compile_from_regex_with_options(r#"a|b|c|d|e|f|g|Gh"#, &options).unwrap();
+            let s = route.get_debug_text();
+            assert!(!s.contains("Trie"));
+        }
+
+        // too few alternatives
+        {
+            let route = compile_from_regex(r#"a|b|c"#).unwrap();
+            let s = route.get_debug_text();
+            assert!(!s.contains("Trie"));
+        }
+    }
+
     #[test]
     fn test_compile_special_char() {
         for route in generate_routes(r#"'a', char_any"#, r#"a."#) {
@@ -1687,7 +2022,7 @@ define(letter, ['a'..'f', char_space])
   -> 5, Char 'a'
 - 5
   -> 7, Capture end {0}
-> 6
+> 6, fixed start
   -> 0, Capture start {0}
 < 7
 # {0}"
@@ -1745,7 +2080,10 @@ define(letter, ['a'..'f', char_space])
     #[test]
     fn test_compile_capture_group_by_name() {
         // function call, and rear function call
-        for route in generate_routes(r#"name('a', "foo"), 'b'.name("bar")"#, r#"(?<foo>a)(?<bar>b)"#) {
+        for route in generate_routes(
+            r#"name('a', "foo"), 'b'.name("bar")"#,
+            r#"(?<foo>a)(?<bar>b)"#,
+        ) {
             let s = route.get_debug_text();
 
             assert_str_eq!(
@@ -1927,7 +2265,7 @@ define(letter, ['a'..'f', char_space])
     fn test_compile_backreference() {
         for route in generate_routes(
             r#"'a'.name("foo"), 'b', foo"#, // anre
-            r#"(?<foo>a)b\k<foo>"#,       // regex
+            r#"(?<foo>a)b\k<foo>"#,         // regex
         ) {
             let s = route.get_debug_text();
 
@@ -2582,7 +2920,7 @@ define(letter, ['a'..'f', char_space])
   -> 2, Capture start {0}
 < 5
 = $1
-> 0
+> 0, fixed start
   -> 1, String \"xyz\"
 < 1
 # {0}"
@@ -2612,7 +2950,7 @@ define(letter, ['a'..'f', char_space])
   -> 2, Capture start {0}
 < 5
 = $1
-> 0
+> 0, fixed start
   -> 1, String \"xyz\"
 < 1
 # {0}"
@@ -2653,7 +2991,7 @@ define(letter, ['a'..'f', char_space])
   -> 2, Capture start {0}
 < 5
 = $1
-> 0
+> 0, fixed start
   -> 1, String \"xyz\"
 < 1
 # {0}"
@@ -2683,7 +3021,7 @@ define(letter, ['a'..'f', char_space])
   -> 2, Capture start {0}
 < 5
 = $1
-> 0
+> 0, fixed start
   -> 1, String \"xyz\"
 < 1
 # {0}"
@@ -2711,4 +3049,56 @@ define(letter, ['a'..'f', char_space])
             ));
         }
     }
+
+    #[test]
+    fn test_compile_with_options() {
+        let options = CompileOptions {
+            case_insensitive: true,
+            multi_line: true,
+            dot_matches_new_line: true,
+            swap_greed: false,
+            crlf: true,
+            dot_matches_any_byte: false,
+        };
+
+        for route in [
+            compile_from_anre_with_options(r#"start, "abc", ['x'..'z'], char_any, end"#, &options)
+                .unwrap(),
+            compile_from_regex_with_options(r#"^abc[x-z].$"#, &options).unwrap(),
+        ] {
+            let s = route.get_debug_text();
+
+            assert_str_eq!(
+                s,
+                "\
+- 0
+  -> 1, Anchor assertion \"start\", multi-line, crlf
+- 1
+  -> 2, Jump
+- 2
+  -> 3, String \"abc\", ignore case
+- 3
+  -> 4, Jump
+- 4
+  -> 5, Charset ['x'..'z'], ignore case
+- 5
+  -> 6, Jump
+- 6
+  -> 7, Any char, include new line
+- 7
+  -> 8, Jump
+- 8
+  -> 9, Anchor assertion \"end\", multi-line, crlf
+- 9
+  -> 11, Capture end {0}
+> 10
+  -> 0, Capture start {0}
+< 11
+# {0}"
+            );
+
+            // the start position is not fixed in multi-line mode
+            assert!(!route.routes[MAIN_ROUTE_INDEX].is_fixed_start_position);
+        }
+    }
 }
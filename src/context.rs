@@ -4,7 +4,13 @@
 // the Mozilla Public License version 2.0 and additional exceptions.
 // For more details, see the LICENSE, LICENSE.additional, and CONTRIBUTING files.
 
-use crate::object_file::ObjectFile;
+use std::{
+    collections::HashMap,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use crate::{dfa::DfaCache, object_file::ObjectFile, MatchError};
 
 /// Context for a running process.
 pub struct Context<'a> {
@@ -49,6 +55,174 @@ pub struct Context<'a> {
     //                                              transition    |
     // ```
     pub counter_stack: Vec<usize>,
+
+    // The transition stacks of the finished routines, they are
+    // reused by the new routines to avoid allocations.
+    pub spare_transition_stacks: Vec<Vec<TransitionStackItem>>,
+
+    // The states of the lazy DFA, see `dfa.rs`.
+    pub dfa_cache: DfaCache,
+
+    // The results of the lookaround assertions of the Pike VM, keyed by
+    // the route index and the position, see `pikevm.rs`.
+    // They depend only on the text, so they are kept for the whole operation.
+    pub lookaround_results: HashMap<(usize, usize), Option<Vec<MatchRange>>>,
+
+    // The limits of the number of executed transitions and the matching time.
+    pub budget: Budget,
+}
+
+/// The reusable buffers of matching operations.
+///
+/// A `Context` borrows the text, so it can not be kept between
+/// the matching operations, instead its buffers are moved into a cache
+/// when the operation finishes, and moved to the context of the next
+/// operation, so the buffers are allocated only once.
+///
+/// A cache can be used with any `Regex`, but only by one operation at a time,
+/// see `Regex::try_search_with`.
+#[derive(Default)]
+pub struct Cache {
+    routines: Vec<Routine>,
+    match_ranges: Vec<MatchRange>,
+    counter_stack: Vec<usize>,
+    spare_transition_stacks: Vec<Vec<TransitionStackItem>>,
+    dfa_cache: DfaCache,
+}
+
+impl Cache {
+    pub fn new() -> Self {
+        Cache::default()
+    }
+}
+
+/// A thread-safe pool of caches, it is used by the methods of `Regex`
+/// which do not take a cache explicitly.
+#[derive(Default)]
+pub struct CachePool {
+    caches: Mutex<Vec<Cache>>,
+}
+
+impl CachePool {
+    /// Takes a cache from the pool, or creates a new one if the pool is empty.
+    pub fn get(&self) -> Cache {
+        self.lock().pop().unwrap_or_default()
+    }
+
+    /// Gives back the cache to the pool.
+    pub fn put(&self, cache: Cache) {
+        self.lock().push(cache);
+    }
+
+    // The caches are still valid even if another thread
+    // panicked while holding the lock.
+    fn lock(&self) -> std::sync::MutexGuard<'_, Vec<Cache>> {
+        self.caches
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// The limits of a matching operation.
+///
+/// Every executed transition (including the transitions of sub-routines
+/// generated by lookaround assertions) takes a step.
+/// Once the budget is exceeded, all the remaining transitions fail
+/// and the error is recorded.
+pub struct Budget {
+    pub step_limit: Option<usize>,
+    pub deadline: Option<Instant>,
+    pub steps: usize,
+    pub error: Option<MatchError>,
+}
+
+// Reading the system clock is relatively slow, so the deadline is
+// checked once every specified number of steps.
+const DEADLINE_CHECK_INTERVAL: usize = 1024;
+
+impl Budget {
+    pub fn new(step_limit: Option<usize>, time_limit: Option<Duration>) -> Self {
+        Budget {
+            step_limit,
+            deadline: time_limit.map(|duration| Instant::now() + duration),
+            steps: 0,
+            error: None,
+        }
+    }
+
+    pub fn unlimited() -> Self {
+        Budget::new(None, None)
+    }
+
+    /// Takes a step, returns `false` if the budget is exceeded.
+    #[inline]
+    pub fn take_step(&mut self) -> bool {
+        if self.error.is_some() {
+            return false;
+        }
+
+        self.steps += 1;
+
+        if let Some(step_limit) = self.step_limit {
+            if self.steps > step_limit {
+                self.error = Some(MatchError::BudgetExceeded);
+                return false;
+            }
+        }
+
+        if let Some(deadline) = self.deadline {
+            if self.steps.is_multiple_of(DEADLINE_CHECK_INTERVAL) && Instant::now() >= deadline {
+                self.error = Some(MatchError::Timeout);
+                return false;
+            }
+        }
+
+        true
+    }
+
+    /// Takes a batch of steps at once, returns the number of steps taken,
+    /// or 0 if the budget is exceeded.
+    ///
+    /// It is used by the JIT code, which counts down the steps by itself,
+    /// the unused steps should be given back by `return_steps`.
+    pub fn take_steps(&mut self) -> usize {
+        if self.error.is_some() {
+            return 0;
+        }
+
+        if self.step_limit.is_none() && self.deadline.is_none() {
+            return usize::MAX;
+        }
+
+        if let Some(deadline) = self.deadline {
+            if Instant::now() >= deadline {
+                self.error = Some(MatchError::Timeout);
+                return 0;
+            }
+        }
+
+        let count = match self.step_limit {
+            Some(step_limit) if self.steps >= step_limit => {
+                self.error = Some(MatchError::BudgetExceeded);
+                return 0;
+            }
+            Some(step_limit) => (step_limit - self.steps).min(DEADLINE_CHECK_INTERVAL),
+            None => DEADLINE_CHECK_INTERVAL,
+        };
+
+        self.steps += count;
+        count
+    }
+
+    pub fn return_steps(&mut self, count: usize) {
+        if self.step_limit.is_some() || self.deadline.is_some() {
+            self.steps -= count;
+        }
+    }
+
+    pub fn is_exceeded(&self) -> bool {
+        self.error.is_some()
+    }
 }
 
 /// Represents a routine generated by a route during process execution.
@@ -142,12 +316,77 @@ impl<'a> Context<'a> {
             bytes,
             routines: vec![],
             counter_stack: vec![],
+            spare_transition_stacks: vec![],
+            dfa_cache: DfaCache::default(),
+            lookaround_results: HashMap::new(),
+            budget: Budget::unlimited(),
 
             // Allocate the vector of 'match ranges' for the capture groups.
             match_ranges: vec![MatchRange::default(); number_of_capture_groups],
         }
     }
 
+    /// Creates a context with the buffers of the cache, they should be
+    /// given back by `into_cache` after the matching operation.
+    pub fn from_cache(
+        bytes: &'a [u8],
+        number_of_capture_groups: usize, // Number of pre-allocated match slots.
+        cache: Cache,
+    ) -> Self {
+        let Cache {
+            mut routines,
+            mut match_ranges,
+            mut counter_stack,
+            spare_transition_stacks,
+            dfa_cache,
+        } = cache;
+
+        // The buffers may be left dirty by an aborted operation.
+        routines.clear();
+        counter_stack.clear();
+        match_ranges.clear();
+        match_ranges.resize(number_of_capture_groups, MatchRange::default());
+
+        Context {
+            bytes,
+            routines,
+            match_ranges,
+            counter_stack,
+            spare_transition_stacks,
+            dfa_cache,
+            lookaround_results: HashMap::new(),
+            budget: Budget::unlimited(),
+        }
+    }
+
+    /// Moves the buffers of the context into a cache.
+    pub fn into_cache(self) -> Cache {
+        Cache {
+            routines: self.routines,
+            match_ranges: self.match_ranges,
+            counter_stack: self.counter_stack,
+            spare_transition_stacks: self.spare_transition_stacks,
+            dfa_cache: self.dfa_cache,
+        }
+    }
+
+    /// Starts a new routine, it reuses a spare transition stack if there is one.
+    pub fn push_routine(&mut self, start_position: usize, end_position: usize, route_index: usize) {
+        let mut routine = Routine::new(start_position, end_position, route_index);
+        if let Some(transition_stack) = self.spare_transition_stacks.pop() {
+            routine.transition_stack = transition_stack;
+        }
+        self.routines.push(routine);
+    }
+
+    /// Finishes the current routine, its transition stack is kept for reuse.
+    pub fn pop_routine(&mut self) {
+        if let Some(mut routine) = self.routines.pop() {
+            routine.transition_stack.clear();
+            self.spare_transition_stacks.push(routine.transition_stack);
+        }
+    }
+
     pub fn push_transitions_of_node(
         &mut self,
         object_file: &ObjectFile,
@@ -0,0 +1,432 @@
+// Copyright (c) 2025 Hemashushu <hippospark@gmail.com>, All rights reserved.
+//
+// This Source Code Form is subject to the terms of
+// the Mozilla Public License version 2.0 and additional exceptions.
+// For more details, see the LICENSE, LICENSE.additional, and CONTRIBUTING files.
+
+use crate::{
+    ast::{BackReference, Expression, FunctionName, Literal},
+    rulechecker::check_program,
+    AnreError,
+};
+
+/// Converts the traditional regular expression into ANRE.
+///
+/// The back references by index (e.g. `\1`) are converted into the back
+/// references by name since ANRE refers to the capture groups by name only,
+/// an error is returned if the referenced capture group is not named.
+///
+/// e.g.
+///
+/// ```
+/// use regex_anre::regex_to_anre;
+///
+/// assert_eq!(
+///     regex_to_anre(r"(?<year>\d{4})-\d+").unwrap(),
+///     "name(repeat(char_digit, 4), \"year\")\n'-'\none_or_more(char_digit)"
+/// );
+/// ```
+pub fn regex_to_anre(s: &str) -> Result<String, AnreError> {
+    let mut program = crate::traditional::parse_from_str(s)?;
+
+    let mut capture_group_names = vec![];
+    for expression in &program.expressions {
+        collect_capture_group_names(expression, &mut capture_group_names);
+    }
+
+    for expression in &mut program.expressions {
+        replace_back_references_by_index(expression, &capture_group_names)?;
+    }
+
+    Ok(program.to_string())
+}
+
+/// Converts the ANRE regular expression into the traditional regular expression.
+///
+/// e.g.
+///
+/// ```
+/// use regex_anre::anre_to_regex;
+///
+/// assert_eq!(
+///     anre_to_regex(r#"name(char_digit.repeat(4), year), '-', char_digit+"#).unwrap(),
+///     r"(?<year>\d{4})-\d+"
+/// );
+/// ```
+///
+/// Returns an error if the expression can not be written in the traditional
+/// regular expression, e.g. an empty group, or a negative preset charset
+/// in a charset (`[char_not_word]`).
+pub fn anre_to_regex(s: &str) -> Result<String, AnreError> {
+    let program = crate::anre::parse_from_str(s)?;
+    check_program(&program)?;
+    crate::traditional::print_program(&program)
+}
+
+// Collects the names of the capture groups in the order of their indices,
+// i.e. the order in which the compiler creates them.
+fn collect_capture_group_names(expression: &Expression, names: &mut Vec<Option<String>>) {
+    match expression {
+        Expression::Group(expressions) => {
+            for expression in expressions {
+                collect_capture_group_names(expression, names);
+            }
+        }
+        Expression::Or(left, right) => {
+            collect_capture_group_names(left, names);
+            collect_capture_group_names(right, names);
+        }
+        Expression::FunctionCall(function_call) => {
+            let args = &function_call.args;
+            match function_call.name {
+                FunctionName::Name => {
+                    let name = match &args[1] {
+                        Expression::Literal(Literal::String(s)) => s,
+                        Expression::BackReference(BackReference::Name(s)) => s,
+                        _ => unreachable!(),
+                    };
+                    names.push(Some(name.to_owned()));
+                    collect_capture_group_names(&args[0], names);
+                }
+                FunctionName::Index => {
+                    names.push(None);
+                    collect_capture_group_names(&args[0], names);
+                }
+                FunctionName::IsAfter | FunctionName::IsNotAfter => {
+                    // the preceding expression is compiled first.
+                    collect_capture_group_names(&args[1], names);
+                    collect_capture_group_names(&args[0], names);
+                }
+                _ => {
+                    for arg in args {
+                        collect_capture_group_names(arg, names);
+                    }
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+fn replace_back_references_by_index(
+    expression: &mut Expression,
+    names: &[Option<String>],
+) -> Result<(), AnreError> {
+    match expression {
+        Expression::BackReference(back_reference) => {
+            if let BackReference::Index(index) = back_reference {
+                match names.get(index.wrapping_sub(1)) {
+                    Some(Some(name)) => *back_reference = BackReference::Name(name.to_owned()),
+                    _ => {
+                        return Err(AnreError::SyntaxIncorrect(format!(
+                            "The back reference \"\\{}\" can not be written in ANRE, since it does not refer to a named capture group.",
+                            index
+                        )));
+                    }
+                }
+            }
+        }
+        Expression::Group(expressions) => {
+            for expression in expressions {
+                replace_back_references_by_index(expression, names)?;
+            }
+        }
+        Expression::Or(left, right) => {
+            replace_back_references_by_index(left, names)?;
+            replace_back_references_by_index(right, names)?;
+        }
+        Expression::FunctionCall(function_call) => {
+            for arg in &mut function_call.args {
+                replace_back_references_by_index(arg, names)?;
+            }
+        }
+        _ => {}
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use crate::AnreError;
+
+    use super::{anre_to_regex, regex_to_anre};
+
+    #[test]
+    fn test_regex_to_anre() {
+        assert_eq!(regex_to_anre(r#"abc"#).unwrap(), r#""abc""#);
+        assert_eq!(regex_to_anre(r#"a|b|c"#).unwrap(), r#"'a' || ('b' || 'c')"#);
+        assert_eq!(
+            regex_to_anre(r#"ab|c(?:d|e)f"#).unwrap(),
+            r#""ab" || ('c', 'd' || 'e', 'f')"#
+        );
+        assert_eq!(
+            regex_to_anre(r#"[^a-z_\d\]]"#).unwrap(),
+            r#"!['a'..'z', '_', char_digit, ']']"#
+        );
+        assert_eq!(regex_to_anre(r#"\t"'"#).unwrap(), r#""\t\"'""#);
+        assert_eq!(
+            regex_to_anre(r#"(?<=a)b(?!c)"#).unwrap(),
+            r#"is_after(is_not_before('b', 'c'), 'a')"#
+        );
+
+        // back references by index are converted into the back references by name
+        assert_eq!(
+            regex_to_anre(r#"(a)(?<=(?<x>b))c\2"#).unwrap(),
+            "index('a')
+is_after(('c', x), name('b', \"x\"))"
+        );
+
+        assert!(matches!(
+            regex_to_anre(r#"(a)\1"#),
+            Err(AnreError::SyntaxIncorrect(_))
+        ));
+        assert!(matches!(
+            regex_to_anre(r#"(?<x>a)\2"#),
+            Err(AnreError::SyntaxIncorrect(_))
+        ));
+    }
+
+    #[test]
+    fn test_anre_to_regex() {
+        assert_eq!(anre_to_regex(r#""abc""#).unwrap(), r#"abc"#);
+        assert_eq!(anre_to_regex(r#"'a', "bc""#).unwrap(), r#"a(?:bc)"#);
+        assert_eq!(anre_to_regex(r#"("ab" || 'c')+"#).unwrap(), r#"(?:ab|c)+"#);
+        assert_eq!(
+            anre_to_regex(r#"'a' || 'b', 'c' || 'd'"#).unwrap(),
+            r#"(?:a|b)(?:c|d)"#
+        );
+        assert_eq!(
+            anre_to_regex(r#"".*(){}|$^+?\\""#).unwrap(),
+            r#"\.\*\(\)\{\}\|\$\^\+\?\\"#
+        );
+        assert_eq!(
+            anre_to_regex(r#"['-', 'a'..'z', '^', ']', char_hex]"#).unwrap(),
+            r#"[-a-z\^\]0-9a-fA-F]"#
+        );
+        assert_eq!(
+            anre_to_regex(r#"is_before('a', 'b').one_or_more(), 'c'.repeat_range(0, 2)"#).unwrap(),
+            r#"(?:a(?=b))+c{0,2}"#
+        );
+        assert_eq!(
+            anre_to_regex(r#"'x', is_after('a', 'b'), 'y'"#).unwrap(),
+            r#"x(?:(?<=b)a)y"#
+        );
+        assert_eq!(
+            anre_to_regex(r#"name(char_digit, n), n, '1', ignore_case('a')"#).unwrap(),
+            r#"(?<n>\d)\k<n>1(?i:a)"#
+        );
+
+        // the expressions that can not be written in traditional regex
+        assert!(anre_to_regex(r#"'a', ()"#).is_err());
+        assert!(anre_to_regex(r#""""#).is_err());
+        assert!(anre_to_regex(r#"[char_not_word]"#).is_err());
+
+        // err: the names which are not identifiers
+        assert!(anre_to_regex(r#"name('a', "x y")"#).is_err());
+        assert!(anre_to_regex(r#"'a'.name("x-y")"#).is_err());
+        assert!(anre_to_regex(r#"'a'.name("")"#).is_err());
+        assert_eq!(
+            anre_to_regex(r#"'a'.name("名字_1")"#).unwrap(),
+            r#"(?<名字_1>a)"#
+        );
+
+        // err: invalid arguments
+        for expression in [
+            r#"repeat('a')"#,
+            r#"name('a')"#,
+            r#"name('a', 1)"#,
+            r#"is_before('a')"#,
+        ] {
+            assert!(
+                matches!(
+                    anre_to_regex(expression),
+                    Err(AnreError::SyntaxIncorrect(_))
+                ),
+                "{}",
+                expression
+            );
+        }
+    }
+
+    // A tiny xorshift generator, so that the generated patterns are the same
+    // on every run.
+    struct Random {
+        state: u64,
+    }
+
+    impl Random {
+        fn next(&mut self, max: usize) -> usize {
+            self.state ^= self.state << 13;
+            self.state ^= self.state >> 7;
+            self.state ^= self.state << 17;
+            (self.state % max as u64) as usize
+        }
+
+        fn pick<'a>(&mut self, items: &[&'a str]) -> &'a str {
+            items[self.next(items.len())]
+        }
+    }
+
+    // Generates a random traditional regular expression.
+    struct PatternGenerator {
+        random: Random,
+        capture_group_count: usize,
+    }
+
+    impl PatternGenerator {
+        fn generate(&mut self) -> String {
+            self.capture_group_count = 0;
+            self.alternation(2)
+        }
+
+        fn alternation(&mut self, depth: usize) -> String {
+            let mut s = self.sequence(depth);
+            while self.random.next(4) == 0 {
+                s.push('|');
+                s.push_str(&self.sequence(depth));
+            }
+            s
+        }
+
+        fn sequence(&mut self, depth: usize) -> String {
+            let mut s = String::new();
+            let count = self.random.next(4) + 1;
+            for index in 0..count {
+                match self.random.next(12) {
+                    // the look behind consumes the rest of the sequence
+                    0 if depth > 0 => {
+                        let prefix = self.random.pick(&["(?<=", "(?<!"]);
+                        s.push_str(prefix);
+                        s.push_str(&self.sequence(depth - 1));
+                        s.push(')');
+                        s.push_str(&self.item(depth));
+                        return s;
+                    }
+                    // the modifier applies to the rest of the scope
+                    1 if index + 1 < count => {
+                        s.push_str(self.random.pick(&["(?i)", "(?m)", "(?-s)"]));
+                    }
+                    _ => {}
+                }
+                s.push_str(&self.item(depth));
+            }
+            s
+        }
+
+        fn item(&mut self, depth: usize) -> String {
+            let mut s = self.atom(depth);
+            if self.random.next(5) == 0 && depth > 0 {
+                s.push_str(self.random.pick(&["(?=", "(?!"]));
+                s.push_str(&self.sequence(depth - 1));
+                s.push(')');
+            }
+            if self.random.next(3) == 0 {
+                s.push_str(self.random.pick(&[
+                    "?", "*", "+", "??", "*?", "+?", "{2}", "{1,3}", "{2,}", "{1,3}?", "{2,}?",
+                ]));
+            }
+            s
+        }
+
+        fn atom(&mut self, depth: usize) -> String {
+            let limit = if depth > 0 { 10 } else { 5 };
+            match self.random.next(limit) {
+                0 | 1 => self
+                    .random
+                    .pick(&[
+                        "a",
+                        "b",
+                        "0",
+                        "'",
+                        "\"",
+                        "-",
+                        "\\.",
+                        "\\*",
+                        "\\(",
+                        "\\|",
+                        "\\\\",
+                        "\\^",
+                        "\\$",
+                        "\\{",
+                        "\\t",
+                        "\\u{1f600}",
+                    ])
+                    .to_owned(),
+                2 => self
+                    .random
+                    .pick(&[
+                        "[a-c_]",
+                        "[^\\d-]",
+                        "[\\]x\\[]",
+                        "[-a]",
+                        "[!--]",
+                        "[\\^\\\\]",
+                        "[\\w\\s]",
+                    ])
+                    .to_owned(),
+                3 => self
+                    .random
+                    .pick(&["\\w", "\\W", "\\d", "\\D", "\\s", "\\S", "."])
+                    .to_owned(),
+                4 => self.random.pick(&["^", "$", "\\b", "\\B"]).to_owned(),
+                5 => {
+                    self.capture_group_count += 1;
+                    format!("({})", self.alternation(depth - 1))
+                }
+                6 => {
+                    self.capture_group_count += 1;
+                    let name = format!("n{}", self.capture_group_count);
+                    format!("(?<{}>{})", name, self.alternation(depth - 1))
+                }
+                7 => format!("(?:{})", self.alternation(depth - 1)),
+                8 => {
+                    let flags = self.random.pick(&["i", "m", "s", "-i", "-m", "-s"]);
+                    format!("(?{}:{})", flags, self.alternation(depth - 1))
+                }
+                _ if self.capture_group_count > 0 => {
+                    format!("\\k<n{}>", self.random.next(self.capture_group_count) + 1)
+                }
+                _ => "a".to_owned(),
+            }
+        }
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let mut generator = PatternGenerator {
+            random: Random {
+                state: 0x2545_f491_4f6c_dd1d,
+            },
+            capture_group_count: 0,
+        };
+
+        for _ in 0..2000 {
+            let pattern = generator.generate();
+            let program = crate::traditional::parse_from_str(&pattern).unwrap();
+
+            // regex -> ANRE
+            let anre = regex_to_anre(&pattern).unwrap();
+            assert_eq!(
+                crate::anre::parse_from_str(&anre).unwrap(),
+                program,
+                "pattern: {}, ANRE: {}",
+                pattern,
+                anre
+            );
+
+            // ANRE -> regex
+            let regex = anre_to_regex(&anre).unwrap();
+            assert_eq!(
+                crate::traditional::parse_from_str(&regex).unwrap(),
+                program,
+                "pattern: {}, ANRE: {}, regex: {}",
+                pattern,
+                anre,
+                regex
+            );
+        }
+    }
+}
@@ -0,0 +1,707 @@
+// Copyright (c) 2025 Hemashushu <hippospark@gmail.com>, All rights reserved.
+//
+// This Source Code Form is subject to the terms of
+// the Mozilla Public License version 2.0 and additional exceptions.
+// For more details, see the LICENSE, LICENSE.additional, and CONTRIBUTING files.
+
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+    ast::{AnchorAssertionName, BoundaryAssertionName},
+    context::Context,
+    object_file::{ObjectFile, Route, MAIN_ROUTE_INDEX},
+    pikevm::saturate_repetition_count,
+    regex::Anchored,
+    transition::{fold_case, is_word_char, ExecuteResult, Transition},
+    utf8reader::{read_char, read_previous_char, INVALID_CODEPOINT},
+};
+
+// The lazy DFA moves the same threads as the Pike VM (see `pikevm.rs`),
+// but the capture groups are not tracked, so the list of threads at
+// the next position depends only on the list at the current position
+// and the current char.
+//
+// Each distinct list of threads is a DFA state, and the transitions between
+// the states are computed on demand and cached, so once the states
+// of a text are built, each char costs only one table lookup.
+//
+// ```diagram
+//  position:      0       1       2       3
+//  state:      [s0] -a-> [s1] -b-> [s1] -c-> [s2]
+//                         ^ computed once, then looked up
+// ```
+//
+// The assertions (e.g. `^`, `$` and `\b`) depend on the chars on both sides
+// of a position, so a state holds the threads *before* following
+// the non-consuming transitions, together with the information of
+// the previous char, and the transitions are followed when the
+// current char is read. As a result, a match is reported by the state
+// of the next position (see `State::is_match`), and the match at the end
+// of the text range is checked separately.
+//
+// The lazy DFA only finds where the leftmost-first match ends,
+// the engine is then run on the short range of the match to resolve
+// the capture groups.
+
+/// The result of the lazy DFA search.
+pub enum DfaResult {
+    NoMatch,
+
+    // The leftmost-first match ends at `end`, and it starts
+    // at or after `window_start`.
+    Match { window_start: usize, end: usize },
+
+    // The cache is cleared too many times, the engine should be used instead.
+    GaveUp,
+}
+
+// The maximum number of cached states, the cache is cleared once it is full.
+const MAX_STATES: usize = 4096;
+
+// The search gives up if the cache is cleared more than this number of times,
+// the states are not reused enough for the DFA to be faster than the engine.
+const MAX_CACHE_CLEARS: usize = 8;
+
+// The transition index of a thread which is at the entry of a node.
+const NODE_ENTRY: usize = usize::MAX;
+
+// The index of a transition which has not been computed.
+const UNKNOWN_STATE: u32 = u32::MAX;
+
+// The information of the char before the position.
+const LOOK_START: u8 = 1; // The position is the start of the text.
+const LOOK_WORD: u8 = 2; // The previous char is a word char.
+const LOOK_LF: u8 = 4; // The previous char is '\n'.
+const LOOK_CR: u8 = 8; // The previous char is '\r'.
+
+/// Returns whether the expression can be matched by the lazy DFA, i.e.
+/// it has no lookaround assertions, no back references and no byte transitions.
+pub fn is_supported(object_file: &ObjectFile) -> bool {
+    object_file.routes.len() == 1
+        && !object_file.has_back_reference()
+        && !object_file.has_byte_transition()
+}
+
+// The state of a thread, see `pikevm::Thread`.
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+struct ThreadKey {
+    node_index: usize,
+    transition_index: usize,
+    char_offset: usize,
+    repetition_count: usize,
+    counter_stack: Vec<usize>,
+}
+
+impl ThreadKey {
+    fn new(node_index: usize, counter_stack: Vec<usize>) -> Self {
+        ThreadKey {
+            node_index,
+            transition_index: NODE_ENTRY,
+            char_offset: 0,
+            repetition_count: 0,
+            counter_stack,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+struct State {
+    // The threads in priority order, the non-consuming transitions
+    // of the threads at the entry of nodes are not followed yet.
+    threads: Vec<ThreadKey>,
+
+    // The information of the previous char, see `LOOK_*`.
+    look_behind: u8,
+
+    // Whether a new thread is started at the position (with the lowest priority).
+    has_start: bool,
+
+    // Whether a match ends at the previous position.
+    is_match: bool,
+}
+
+/// The states and transitions of the lazy DFA, it is kept in the `Cache`
+/// and built for one expression (and anchoring) at a time.
+#[derive(Default)]
+pub struct DfaCache {
+    owner: Option<(usize, bool, bool)>, // The id of the expression and the anchoring.
+    states: Vec<State>,
+    state_indices: HashMap<State, u32>,
+    start_states: HashMap<u8, u32>,
+    ascii_transitions: Vec<u32>, // 128 transitions for each state.
+    other_transitions: HashMap<(u32, u32), u32>,
+    clear_count: usize,
+}
+
+impl DfaCache {
+    fn reset(&mut self) {
+        self.states.clear();
+        self.state_indices.clear();
+        self.start_states.clear();
+        self.ascii_transitions.clear();
+        self.other_transitions.clear();
+    }
+
+    fn add_state(&mut self, state: State) -> u32 {
+        if let Some(index) = self.state_indices.get(&state) {
+            return *index;
+        }
+
+        let index = self.states.len() as u32;
+        self.states.push(state.clone());
+        self.state_indices.insert(state, index);
+        self.ascii_transitions
+            .extend(std::iter::repeat_n(UNKNOWN_STATE, 128));
+        index
+    }
+
+    fn get_transition(&self, state_index: u32, codepoint: u32) -> u32 {
+        if codepoint < 128 {
+            self.ascii_transitions[state_index as usize * 128 + codepoint as usize]
+        } else {
+            *self
+                .other_transitions
+                .get(&(state_index, codepoint))
+                .unwrap_or(&UNKNOWN_STATE)
+        }
+    }
+
+    fn set_transition(&mut self, state_index: u32, codepoint: u32, next_index: u32) {
+        if codepoint < 128 {
+            self.ascii_transitions[state_index as usize * 128 + codepoint as usize] = next_index;
+        } else {
+            self.other_transitions
+                .insert((state_index, codepoint), next_index);
+        }
+    }
+}
+
+/// Searches the end of the leftmost-first match within the range
+/// `start_position..end_position`, with the DFA cache of the context.
+///
+/// The `id` identifies the expression of the cache. If `earliest` is `true`,
+/// the search stops at the first match found, it is used by `is_match`,
+/// and the end of the returned match may not be the end of the leftmost-first match.
+pub fn search(
+    context: &mut Context,
+    object_file: &ObjectFile,
+    id: usize,
+    start_position: usize,
+    end_position: usize,
+    anchored: Anchored,
+    earliest: bool,
+) -> DfaResult {
+    let route = &object_file.routes[MAIN_ROUTE_INDEX];
+    let dfa = Dfa {
+        object_file,
+        route,
+        is_fixed_start_position: route.is_fixed_start_position
+            || anchored.is_fixed_start_position(),
+        is_fixed_end_position: route.is_fixed_end_position || anchored.is_fixed_end_position(),
+    };
+
+    let bytes = context.bytes;
+    let cache = &mut context.dfa_cache;
+
+    let owner = Some((id, dfa.is_fixed_start_position, dfa.is_fixed_end_position));
+    if cache.owner != owner {
+        cache.reset();
+        cache.owner = owner;
+    }
+    cache.clear_count = 0;
+
+    let prefilter = &object_file.prefilter;
+    let use_prefilter = !dfa.is_fixed_start_position;
+    if use_prefilter && !prefilter.may_match(bytes, start_position, end_position) {
+        return DfaResult::NoMatch;
+    }
+
+    let mut position = start_position;
+    let mut state_index = dfa.get_start_state(cache, get_look_behind(bytes, position));
+    let mut window_start = start_position;
+    let mut match_end = None;
+
+    loop {
+        let state = &cache.states[state_index as usize];
+        if state.threads.is_empty() {
+            if !state.has_start {
+                // No thread is running, and no new thread will be started.
+                break;
+            }
+
+            // No thread started before the position is running,
+            // so the match can only start at or after the position.
+            window_start = position;
+
+            // Skip the positions where a match can not start.
+            if use_prefilter {
+                match prefilter.next_candidate(bytes, position, end_position) {
+                    Some(candidate) if candidate != position => {
+                        position = candidate;
+                        window_start = candidate;
+                        state_index = dfa.get_start_state(cache, get_look_behind(bytes, position));
+                    }
+                    Some(_) => {}
+                    None => break,
+                }
+            }
+        }
+
+        if position >= end_position {
+            // The assertions at the end of the range can see the char after the range.
+            let state = &cache.states[state_index as usize];
+            let look_ahead = get_look_ahead(bytes, position);
+            if dfa.is_match_at_end(state, look_ahead) {
+                match_end = Some(position);
+            }
+            break;
+        }
+
+        let (codepoint, byte_length) = read_char(bytes, position);
+        let Some(next_index) = dfa.get_next_state(cache, state_index, codepoint) else {
+            return DfaResult::GaveUp;
+        };
+
+        if cache.states[next_index as usize].is_match {
+            match_end = Some(position);
+            if earliest {
+                break;
+            }
+        }
+
+        state_index = next_index;
+        position += byte_length;
+    }
+
+    match match_end {
+        Some(end) => DfaResult::Match { window_start, end },
+        None => DfaResult::NoMatch,
+    }
+}
+
+struct Dfa<'a> {
+    object_file: &'a ObjectFile,
+    route: &'a Route,
+    is_fixed_start_position: bool,
+    is_fixed_end_position: bool,
+}
+
+impl Dfa<'_> {
+    fn get_start_state(&self, cache: &mut DfaCache, look_behind: u8) -> u32 {
+        if let Some(index) = cache.start_states.get(&look_behind) {
+            return *index;
+        }
+
+        let state = State {
+            threads: vec![],
+            look_behind,
+            has_start: true,
+            is_match: false,
+        };
+        let index = cache.add_state(state);
+        cache.start_states.insert(look_behind, index);
+        index
+    }
+
+    // Returns `None` if the cache is cleared too many times.
+    fn get_next_state(
+        &self,
+        cache: &mut DfaCache,
+        state_index: u32,
+        codepoint: u32,
+    ) -> Option<u32> {
+        let next_index = cache.get_transition(state_index, codepoint);
+        if next_index != UNKNOWN_STATE {
+            return Some(next_index);
+        }
+
+        let state = &cache.states[state_index as usize];
+        let next_state = self.compute_next_state(state, codepoint);
+
+        let mut state_index = state_index;
+        if cache.states.len() >= MAX_STATES {
+            if cache.clear_count >= MAX_CACHE_CLEARS {
+                return None;
+            }
+            cache.clear_count += 1;
+
+            // Keep the current state only.
+            let state = cache.states[state_index as usize].clone();
+            cache.reset();
+            state_index = cache.add_state(state);
+        }
+
+        let next_index = cache.add_state(next_state);
+        cache.set_transition(state_index, codepoint, next_index);
+        Some(next_index)
+    }
+
+    fn compute_next_state(&self, state: &State, codepoint: u32) -> State {
+        let threads = self.follow_threads(state, Some(codepoint));
+
+        let mut next_threads = vec![];
+        let mut visited = HashSet::new();
+        let mut is_match = false;
+
+        for thread in threads {
+            if thread.node_index == self.route.end_node_index {
+                if self.is_fixed_end_position {
+                    // The match does not reach the end of the range.
+                    continue;
+                }
+
+                // The threads after this one have lower priority, discard them.
+                is_match = true;
+                break;
+            }
+
+            if let Some(next_thread) = self.step_thread(thread, codepoint) {
+                if visited.insert(next_thread.clone()) {
+                    next_threads.push(next_thread);
+                }
+            }
+        }
+
+        State {
+            threads: next_threads,
+            look_behind: get_look_behind_of_char(codepoint),
+            has_start: state.has_start && !is_match && !self.is_fixed_start_position,
+            is_match,
+        }
+    }
+
+    fn is_match_at_end(&self, state: &State, look_ahead: Option<u32>) -> bool {
+        self.follow_threads(state, look_ahead)
+            .iter()
+            .any(|thread| thread.node_index == self.route.end_node_index)
+    }
+
+    // Follows the non-consuming transitions of the threads of the state,
+    // and returns the threads that stop at a consuming transition
+    // (or at the exit node), in priority order, see `pikevm::add_thread`.
+    fn follow_threads(&self, state: &State, look_ahead: Option<u32>) -> Vec<ThreadKey> {
+        let mut threads = vec![];
+        let mut visited = HashSet::new();
+
+        // The counter transitions are executed without text.
+        let mut context = Context::from_bytes(&[], 0);
+
+        let start_thread = state
+            .has_start
+            .then(|| ThreadKey::new(self.route.start_node_index, vec![]));
+
+        for thread in state.threads.iter().cloned().chain(start_thread) {
+            let mut stack = vec![thread];
+
+            while let Some(thread) = stack.pop() {
+                if !visited.insert(thread.clone()) {
+                    continue;
+                }
+
+                let node = &self.route.nodes[thread.node_index];
+                if thread.transition_index != NODE_ENTRY
+                    || thread.node_index == self.route.end_node_index
+                    || node.transition_items.is_empty()
+                {
+                    threads.push(thread);
+                    continue;
+                }
+
+                let mut following_threads = vec![];
+
+                for (transition_index, transition_item) in node.transition_items.iter().enumerate()
+                {
+                    let mut next_thread = ThreadKey::new(
+                        transition_item.target_node_index,
+                        thread.counter_stack.clone(),
+                    );
+
+                    match &transition_item.transition {
+                        Transition::Char(_)
+                        | Transition::SpecialChar(_)
+                        | Transition::String(_)
+                        | Transition::CharSet(_)
+                        | Transition::Trie(_) => {
+                            // Consuming transitions are executed with the next char.
+                            next_thread.node_index = thread.node_index;
+                            next_thread.transition_index = transition_index;
+                            next_thread.repetition_count = thread.repetition_count;
+                        }
+                        Transition::CaptureStart(_) | Transition::CaptureEnd(_) => {}
+                        Transition::CounterSave(_) => {
+                            next_thread.counter_stack.push(thread.repetition_count);
+                        }
+                        Transition::CounterInc(_) => {
+                            let last_count = next_thread.counter_stack.pop().unwrap();
+                            next_thread.repetition_count = last_count + 1;
+                        }
+                        Transition::AnchorAssertion(_) | Transition::BoundaryAssertion(_) => {
+                            if !is_assertion_satisfied(
+                                &transition_item.transition,
+                                state.look_behind,
+                                look_ahead,
+                            ) {
+                                continue;
+                            }
+                        }
+                        Transition::BackReference(_)
+                        | Transition::LookAheadAssertion(_)
+                        | Transition::LookBehindAssertion(_) => {
+                            unreachable!("Unsupported by the lazy DFA.")
+                        }
+                        transition => {
+                            // Jumps and the transitions for checking the counter.
+                            match transition.execute(
+                                &mut context,
+                                self.object_file,
+                                0,
+                                thread.repetition_count,
+                            ) {
+                                ExecuteResult::Success(_, repetition_count) => {
+                                    next_thread.repetition_count =
+                                        saturate_repetition_count(transition, repetition_count);
+                                }
+                                ExecuteResult::Failure => continue,
+                            }
+                        }
+                    }
+
+                    following_threads.push(next_thread);
+                }
+
+                stack.extend(following_threads.into_iter().rev());
+            }
+        }
+
+        threads
+    }
+
+    // Executes the consuming transition the thread stops at, see `pikevm::step_thread`.
+    fn step_thread(&self, thread: ThreadKey, codepoint: u32) -> Option<ThreadKey> {
+        let transition_item =
+            &self.route.nodes[thread.node_index].transition_items[thread.transition_index];
+
+        let is_completed = match &transition_item.transition {
+            Transition::String(transition) => {
+                let expected_char = transition.codepoints[thread.char_offset];
+                let is_same = if transition.ignore_case {
+                    fold_case(expected_char) == fold_case(codepoint)
+                } else {
+                    expected_char == codepoint
+                };
+
+                if !is_same {
+                    return None;
+                }
+
+                if thread.char_offset + 1 < transition.codepoints.len() {
+                    return Some(ThreadKey {
+                        char_offset: thread.char_offset + 1,
+                        ..thread
+                    });
+                }
+
+                true
+            }
+            Transition::Trie(transition) => {
+                let node_index = transition.step(thread.char_offset, codepoint)?;
+
+                if !transition.is_terminal(node_index) {
+                    return Some(ThreadKey {
+                        char_offset: node_index,
+                        ..thread
+                    });
+                }
+
+                true
+            }
+            transition => {
+                // The transitions which match a single char are executed
+                // on the UTF-8 bytes of the char.
+                let mut buffer = [0u8; 4];
+                let bytes: &[u8] = match char::from_u32(codepoint) {
+                    Some(c) => c.encode_utf8(&mut buffer).as_bytes(),
+                    None => {
+                        // Any invalid byte is read as `INVALID_CODEPOINT`.
+                        debug_assert_eq!(codepoint, INVALID_CODEPOINT);
+                        &[0xFF]
+                    }
+                };
+
+                let mut context = Context::from_bytes(bytes, 0);
+                context.push_routine(0, bytes.len(), MAIN_ROUTE_INDEX);
+
+                matches!(
+                    transition.execute(&mut context, self.object_file, 0, thread.repetition_count),
+                    ExecuteResult::Success(_, _)
+                )
+            }
+        };
+
+        is_completed
+            .then(|| ThreadKey::new(transition_item.target_node_index, thread.counter_stack))
+    }
+}
+
+// Returns the information of the char before the position, see `LOOK_*`.
+fn get_look_behind(bytes: &[u8], position: usize) -> u8 {
+    if position == 0 {
+        LOOK_START
+    } else {
+        let (codepoint, _) = read_previous_char(bytes, position);
+        get_look_behind_of_char(codepoint)
+    }
+}
+
+fn get_look_behind_of_char(codepoint: u32) -> u8 {
+    let mut look_behind = 0;
+    if is_word_char(codepoint) {
+        look_behind |= LOOK_WORD;
+    }
+    if codepoint == '\n' as u32 {
+        look_behind |= LOOK_LF;
+    }
+    if codepoint == '\r' as u32 {
+        look_behind |= LOOK_CR;
+    }
+    look_behind
+}
+
+// Returns the char at the position, or `None` at the end of the text.
+fn get_look_ahead(bytes: &[u8], position: usize) -> Option<u32> {
+    (position < bytes.len()).then(|| read_char(bytes, position).0)
+}
+
+// Checks the assertions in the same way as `Transition::execute`,
+// with the chars on both sides of the position.
+fn is_assertion_satisfied(
+    transition: &Transition,
+    look_behind: u8,
+    look_ahead: Option<u32>,
+) -> bool {
+    let is_start = look_behind & LOOK_START != 0;
+    let is_end = look_ahead.is_none();
+
+    match transition {
+        Transition::AnchorAssertion(transition) if transition.multi_line => {
+            let previous_lf = look_behind & LOOK_LF != 0;
+            let previous_cr = look_behind & LOOK_CR != 0;
+            let current_lf = look_ahead == Some('\n' as u32);
+            let current_cr = look_ahead == Some('\r' as u32);
+
+            match transition.name {
+                AnchorAssertionName::Start if transition.crlf => {
+                    is_start || previous_lf || (previous_cr && !current_lf)
+                }
+                AnchorAssertionName::Start => is_start || previous_lf,
+                AnchorAssertionName::End if transition.crlf => {
+                    is_end || current_cr || (current_lf && !previous_cr)
+                }
+                AnchorAssertionName::End => is_end || current_lf,
+            }
+        }
+        Transition::AnchorAssertion(transition) => match transition.name {
+            AnchorAssertionName::Start => is_start,
+            AnchorAssertionName::End => is_end,
+        },
+        Transition::BoundaryAssertion(transition) => {
+            let is_bound = (look_behind & LOOK_WORD != 0) != look_ahead.is_some_and(is_word_char);
+            match transition.name {
+                BoundaryAssertionName::IsBound => is_bound,
+                BoundaryAssertionName::IsNotBound => !is_bound,
+            }
+        }
+        _ => unreachable!(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{compiler::compile_from_regex, context::Context, regex::Anchored};
+
+    use super::{is_supported, search, DfaResult};
+
+    // Returns `(window_start, end)` of the match, or `None` if there is no match.
+    fn find_end(pattern: &str, text: &str, earliest: bool) -> Option<(usize, usize)> {
+        let object_file = compile_from_regex(pattern).unwrap();
+        assert!(is_supported(&object_file));
+
+        let mut context = Context::new(text, object_file.capture_group_names.len());
+        match search(
+            &mut context,
+            &object_file,
+            0,
+            0,
+            text.len(),
+            Anchored::No,
+            earliest,
+        ) {
+            DfaResult::NoMatch => None,
+            DfaResult::Match { window_start, end } => Some((window_start, end)),
+            DfaResult::GaveUp => panic!("The lazy DFA gave up."),
+        }
+    }
+
+    #[test]
+    fn test_search() {
+        assert_eq!(find_end(r#"\d+"#, "ab 123 45", false), Some((3, 6)));
+        assert_eq!(find_end(r#"\d+"#, "ab cd", false), None);
+
+        // the threads which are still running widen the window
+        assert_eq!(find_end(r#"a+b"#, "xaaab", false), Some((1, 5)));
+        assert_eq!(find_end(r#"\w+c"#, "ab-abc", false), Some((3, 6)));
+
+        // leftmost-first
+        assert_eq!(find_end(r#"a|ab"#, "ab", false), Some((0, 1)));
+        assert_eq!(find_end(r#"ab|a"#, "ab", false), Some((0, 2)));
+        assert_eq!(find_end(r#"a+?"#, "aaa", false), Some((0, 1)));
+
+        // stop at the first match found
+        assert_eq!(find_end(r#"a+"#, "baaa", true), Some((1, 2)));
+
+        // the assertions
+        assert_eq!(find_end(r#"\bfoo\b"#, "foobar foo", false), Some((7, 10)));
+        assert_eq!(find_end(r#"foo$"#, "foo foo", false), Some((4, 7)));
+        assert_eq!(find_end(r#"^foo"#, "xfoo", false), None);
+        assert_eq!(find_end(r#"(?m)^b$"#, "a\nb\nc", false), Some((2, 3)));
+
+        // empty match
+        assert_eq!(find_end(r#"x*"#, "ab", false), Some((0, 0)));
+        assert_eq!(find_end(r#"$"#, "ab", false), Some((2, 2)));
+    }
+
+    #[test]
+    fn test_search_give_up() {
+        let object_file = compile_from_regex(r#"^a{50000}"#).unwrap();
+        let text = "a".repeat(50000);
+        let mut context = Context::new(&text, object_file.capture_group_names.len());
+
+        // each position has a new state, the cache is cleared too many times
+        assert!(matches!(
+            search(
+                &mut context,
+                &object_file,
+                0,
+                0,
+                text.len(),
+                Anchored::No,
+                false,
+            ),
+            DfaResult::GaveUp
+        ));
+
+        // the states are kept for the next search
+        let object_file = compile_from_regex(r#"a{3}"#).unwrap();
+        let mut context = Context::new("aaaa", object_file.capture_group_names.len());
+        for _ in 0..2 {
+            assert!(matches!(
+                search(&mut context, &object_file, 1, 0, 4, Anchored::No, false),
+                DfaResult::Match {
+                    window_start: 0,
+                    end: 3
+                }
+            ));
+        }
+        assert!(!context.dfa_cache.states.is_empty());
+    }
+}
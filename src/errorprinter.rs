@@ -156,6 +156,7 @@ impl AnreError {
                     generate_snippet_and_indented_detail(&mut chars, &snippet_range, detail);
                 format!("{}\n{}\n{}", msg, snippet, indented_detail)
             }
+            AnreError::InvalidObjectFile(msg) => msg.to_owned(),
         }
     }
 }
@@ -0,0 +1,540 @@
+// Copyright (c) 2025 Hemashushu <hippospark@gmail.com>, All rights reserved.
+//
+// This Source Code Form is subject to the terms of
+// the Mozilla Public License version 2.0 and additional exceptions.
+// For more details, see the LICENSE, LICENSE.additional, and CONTRIBUTING files.
+
+//! Explains a pattern in plain English.
+//!
+//! e.g.
+//!
+//! ```
+//! let text = regex_anre::explainer::from_regex(r"(?<year>\d+)-").unwrap();
+//! assert_eq!(
+//!     text,
+//!     "one or more digits, captured as group #1 'year'\nfollowed by '-'"
+//! );
+//! ```
+//!
+//! The items of a sequence are written line by line, and the parts which
+//! can not be written in one line (e.g. a quantifier of a sequence) are
+//! written as a header followed by the indented lines.
+
+use crate::{
+    ast::{
+        quote_char, AnchorAssertionName, BackReference, BoundaryAssertionName, CharSet,
+        CharSetElement, Expression, FunctionCall, FunctionName, Literal, PresetCharSetName,
+        Program, SpecialCharName,
+    },
+    rulechecker::check_program,
+    AnreError,
+};
+
+/// Explains the traditional regular expression.
+pub fn from_regex(s: &str) -> Result<String, AnreError> {
+    let program = crate::traditional::parse_from_str(s)?;
+    check_program(&program)?;
+    Ok(explain(&program))
+}
+
+/// Explains the ANRE regular expression.
+pub fn from_anre(s: &str) -> Result<String, AnreError> {
+    let program = crate::anre::parse_from_str(s)?;
+    check_program(&program)?;
+    Ok(explain(&program))
+}
+
+/// Explains the AST `Program`, the program should be checked
+/// by `check_program` first.
+pub(crate) fn explain(program: &Program) -> String {
+    let mut explainer = Explainer::default();
+    let description = explainer.describe_expressions(&program.expressions);
+
+    let mut lines = vec![];
+    description.write_lines(&mut lines);
+    lines.join("\n")
+}
+
+enum Description {
+    // A phrase which fits in one line, e.g. "a digit", with its plural
+    // form (e.g. "digits") if it can be counted by a quantifier.
+    Phrase(String, Option<String>),
+
+    // The items are written line by line, the lines except the first one
+    // start with "followed by".
+    Sequence(Vec<Description>),
+
+    // The alternatives are written as a list.
+    Choice(Vec<Description>),
+
+    // A header (e.g. "one or more times") and the indented description.
+    Block(String, Box<Description>),
+
+    // A lookaround condition (e.g. "only if followed by") of the previous item.
+    Condition(String, Box<Description>),
+}
+
+impl Description {
+    fn write_lines(&self, lines: &mut Vec<String>) {
+        match self {
+            Description::Phrase(text, _) => lines.push(text.to_owned()),
+            Description::Sequence(items) => {
+                for (index, item) in items.iter().enumerate() {
+                    let start = lines.len();
+                    item.write_lines(lines);
+                    if index > 0 && !matches!(item, Description::Condition(_, _)) {
+                        lines[start] = format!("followed by {}", lines[start]);
+                    }
+                }
+            }
+            Description::Choice(alternatives) => {
+                lines.push("one of:".to_owned());
+                for alternative in alternatives {
+                    let mut alternative_lines = vec![];
+                    alternative.write_lines(&mut alternative_lines);
+                    for (index, line) in alternative_lines.into_iter().enumerate() {
+                        let bullet = if index == 0 { "- " } else { "  " };
+                        lines.push(format!("  {}{}", bullet, line));
+                    }
+                }
+            }
+            Description::Block(header, description)
+            | Description::Condition(header, description) => {
+                if let (Description::Condition(_, _), Description::Phrase(text, _)) =
+                    (self, description.as_ref())
+                {
+                    lines.push(format!("{} {}", header, text));
+                } else {
+                    lines.push(format!("{}:", header));
+                    let mut body_lines = vec![];
+                    description.write_lines(&mut body_lines);
+                    lines.extend(body_lines.into_iter().map(|line| format!("  {}", line)));
+                }
+            }
+        }
+    }
+}
+
+#[derive(Default)]
+struct Explainer {
+    // The number of capture groups which have been described, it is
+    // used to show the index of the groups, the group 0 is the whole match.
+    capture_group_count: usize,
+
+    // Whether the anchors match the start and end of lines,
+    // it is changed by the modifier "multiline".
+    multi_line: bool,
+}
+
+impl Explainer {
+    fn describe_expressions(&mut self, expressions: &[Expression]) -> Description {
+        let mut items = vec![];
+        for expression in expressions {
+            match self.describe_expression(expression) {
+                Description::Sequence(sub_items) => items.extend(sub_items),
+                description => items.push(description),
+            }
+        }
+
+        match items.len() {
+            0 => Description::Phrase("nothing".to_owned(), None),
+            1 => items.remove(0),
+            _ => Description::Sequence(items),
+        }
+    }
+
+    fn describe_expression(&mut self, expression: &Expression) -> Description {
+        match expression {
+            Expression::Literal(literal) => describe_literal(literal),
+            Expression::BackReference(back_reference) => {
+                let text = match back_reference {
+                    BackReference::Index(index) => format!("the same text as group #{}", index),
+                    BackReference::Name(name) => format!("the same text as group '{}'", name),
+                };
+                Description::Phrase(text, None)
+            }
+            Expression::AnchorAssertion(name) => {
+                let text = match (name, self.multi_line) {
+                    (AnchorAssertionName::Start, false) => "the start of the text",
+                    (AnchorAssertionName::Start, true) => "the start of a line",
+                    (AnchorAssertionName::End, false) => "the end of the text",
+                    (AnchorAssertionName::End, true) => "the end of a line",
+                };
+                Description::Phrase(text.to_owned(), None)
+            }
+            Expression::BoundaryAssertion(name) => {
+                let text = match name {
+                    BoundaryAssertionName::IsBound => "a word boundary",
+                    BoundaryAssertionName::IsNotBound => "a position which is not a word boundary",
+                };
+                Description::Phrase(text.to_owned(), None)
+            }
+            Expression::Group(expressions) => self.describe_expressions(expressions),
+            Expression::FunctionCall(function_call) => self.describe_function_call(function_call),
+            Expression::Or(_, _) => {
+                // flatten `a || (b || c)` into one list.
+                Description::Choice(
+                    expression
+                        .get_alternatives()
+                        .into_iter()
+                        .map(|alternative| self.describe_expression(alternative))
+                        .collect(),
+                )
+            }
+        }
+    }
+
+    fn describe_function_call(&mut self, function_call: &FunctionCall) -> Description {
+        let name = function_call.name;
+        let args = &function_call.args;
+
+        match name {
+            FunctionName::Optional
+            | FunctionName::OptionalLazy
+            | FunctionName::OneOrMore
+            | FunctionName::OneOrMoreLazy
+            | FunctionName::ZeroOrMore
+            | FunctionName::ZeroOrMoreLazy
+            | FunctionName::Repeat
+            | FunctionName::RepeatRange
+            | FunctionName::RepeatRangeLazy
+            | FunctionName::AtLeast
+            | FunctionName::AtLeastLazy => {
+                let description = self.describe_expression(&args[0]);
+                describe_quantifier(name, description, &args[1..])
+            }
+            FunctionName::IsBefore
+            | FunctionName::IsNotBefore
+            | FunctionName::IsAfter
+            | FunctionName::IsNotAfter => {
+                let header = match name {
+                    FunctionName::IsBefore => "only if followed by",
+                    FunctionName::IsNotBefore => "only if not followed by",
+                    FunctionName::IsAfter => "only if preceded by",
+                    _ => "only if not preceded by",
+                };
+
+                // The capture groups in the preceding expression of `is_after`
+                // are numbered first, see `Compiler::emit_lookbehind_assertion`.
+                let (description, condition_description) =
+                    if matches!(name, FunctionName::IsBefore | FunctionName::IsNotBefore) {
+                        let description = self.describe_expression(&args[0]);
+                        (description, self.describe_expression(&args[1]))
+                    } else {
+                        let condition_description = self.describe_expression(&args[1]);
+                        (self.describe_expression(&args[0]), condition_description)
+                    };
+
+                match (description, condition_description) {
+                    (Description::Phrase(text, _), Description::Phrase(condition_text, _)) => {
+                        Description::Phrase(
+                            format!("{}, {} {}", text, header, condition_text),
+                            None,
+                        )
+                    }
+                    (description, condition_description) => Description::Sequence(vec![
+                        // make it clear that the condition applies to the whole sequence.
+                        if matches!(description, Description::Sequence(_)) {
+                            Description::Block("the following".to_owned(), Box::new(description))
+                        } else {
+                            description
+                        },
+                        Description::Condition(header.to_owned(), Box::new(condition_description)),
+                    ]),
+                }
+            }
+            FunctionName::Name | FunctionName::Index => {
+                self.capture_group_count += 1;
+                let header = match args.get(1) {
+                    Some(Expression::Literal(Literal::String(s)))
+                    | Some(Expression::BackReference(BackReference::Name(s))) => {
+                        format!("captured as group #{} '{}'", self.capture_group_count, s)
+                    }
+                    _ => format!("captured as group #{}", self.capture_group_count),
+                };
+                let description = self.describe_expression(&args[0]);
+                append_to_phrase(description, &header)
+            }
+            FunctionName::IgnoreCase
+            | FunctionName::MultiLine
+            | FunctionName::DotAll
+            | FunctionName::NotIgnoreCase
+            | FunctionName::NotMultiLine
+            | FunctionName::NotDotAll => {
+                let header = match name {
+                    FunctionName::IgnoreCase => "ignoring case",
+                    FunctionName::MultiLine => "in multiline mode",
+                    FunctionName::DotAll => "with any char also matching new lines",
+                    FunctionName::NotIgnoreCase => "matching case",
+                    FunctionName::NotMultiLine => "not in multiline mode",
+                    _ => "with any char not matching new lines",
+                };
+
+                let saved_multi_line = self.multi_line;
+                match name {
+                    FunctionName::MultiLine => self.multi_line = true,
+                    FunctionName::NotMultiLine => self.multi_line = false,
+                    _ => {}
+                }
+                let description = self.describe_expression(&args[0]);
+                self.multi_line = saved_multi_line;
+
+                append_to_phrase(description, header)
+            }
+        }
+    }
+}
+
+// Appends the text to the phrase, e.g. "a digit, ignoring case",
+// or makes a block (e.g. "the following, ignoring case:") if
+// the description is not a phrase.
+fn append_to_phrase(description: Description, text: &str) -> Description {
+    match description {
+        Description::Phrase(phrase, _) => {
+            Description::Phrase(format!("{}, {}", phrase, text), None)
+        }
+        _ => Description::Block(format!("the following, {}", text), Box::new(description)),
+    }
+}
+
+fn describe_quantifier(
+    name: FunctionName,
+    description: Description,
+    args: &[Expression],
+) -> Description {
+    // e.g. ("one or more", "one or more times")
+    let (count_text, times_text) = match name {
+        FunctionName::Optional | FunctionName::OptionalLazy => {
+            ("optionally".to_owned(), "optionally".to_owned())
+        }
+        FunctionName::OneOrMore | FunctionName::OneOrMoreLazy => {
+            ("one or more".to_owned(), "one or more times".to_owned())
+        }
+        FunctionName::ZeroOrMore | FunctionName::ZeroOrMoreLazy => {
+            ("zero or more".to_owned(), "zero or more times".to_owned())
+        }
+        FunctionName::Repeat => {
+            let times = args[0].get_number();
+            if times == 1 {
+                return description;
+            }
+            (times.to_string(), format!("{} times", times))
+        }
+        FunctionName::RepeatRange | FunctionName::RepeatRangeLazy => {
+            let from = args[0].get_number();
+            let to = args[1].get_number();
+            (
+                format!("{} to {}", from, to),
+                format!("{} to {} times", from, to),
+            )
+        }
+        FunctionName::AtLeast | FunctionName::AtLeastLazy => match args[0].get_number() {
+            0 => ("zero or more".to_owned(), "zero or more times".to_owned()),
+            1 => ("one or more".to_owned(), "one or more times".to_owned()),
+            from => (
+                format!("at least {}", from),
+                format!("at least {} times", from),
+            ),
+        },
+        _ => unreachable!(),
+    };
+
+    let lazy_text = match name {
+        FunctionName::OptionalLazy => " (preferably none)",
+        FunctionName::OneOrMoreLazy
+        | FunctionName::ZeroOrMoreLazy
+        | FunctionName::RepeatRangeLazy
+        | FunctionName::AtLeastLazy => " (as few as possible)",
+        _ => "",
+    };
+
+    let is_optional = matches!(name, FunctionName::Optional | FunctionName::OptionalLazy);
+
+    match description {
+        Description::Phrase(text, _) if is_optional => {
+            Description::Phrase(format!("{} {}{}", count_text, text, lazy_text), None)
+        }
+        Description::Phrase(_, Some(plural_text)) => {
+            Description::Phrase(format!("{} {}{}", count_text, plural_text, lazy_text), None)
+        }
+        _ => append_to_phrase(description, &format!("{}{}", times_text, lazy_text)),
+    }
+}
+
+fn describe_literal(literal: &Literal) -> Description {
+    match literal {
+        Literal::Number(n) => Description::Phrase(n.to_string(), None),
+        Literal::Char(c) => {
+            let text = quote_char(*c);
+            Description::Phrase(text.clone(), Some(text))
+        }
+        Literal::String(s) => {
+            let text = format!("\"{}\"", s.escape_debug());
+            Description::Phrase(text.clone(), Some(text))
+        }
+        Literal::Special(SpecialCharName::CharAny) => {
+            Description::Phrase("any char".to_owned(), Some("chars".to_owned()))
+        }
+        Literal::CharSet(charset) => {
+            let mut texts = vec![];
+            append_charset_element_texts(charset, &mut texts);
+            let list_text = join_with_or(&texts);
+
+            let (singular_text, plural_text) = if charset.negative {
+                (
+                    format!("a char other than {}", list_text),
+                    format!("chars other than {}", list_text),
+                )
+            } else {
+                (
+                    format!("a char from {}", list_text),
+                    format!("chars from {}", list_text),
+                )
+            };
+            Description::Phrase(singular_text, Some(plural_text))
+        }
+        Literal::PresetCharSet(name) => {
+            let (singular_text, plural_text) = get_preset_charset_texts(name);
+            Description::Phrase(singular_text.to_owned(), Some(plural_text.to_owned()))
+        }
+    }
+}
+
+// The nested char sets are positive, so their elements are merged
+// into the outer char set.
+fn append_charset_element_texts(charset: &CharSet, texts: &mut Vec<String>) {
+    for element in &charset.elements {
+        match element {
+            CharSetElement::Char(c) => texts.push(quote_char(*c)),
+            CharSetElement::CharRange(range) => texts.push(format!(
+                "{} to {}",
+                quote_char(range.start),
+                quote_char(range.end_included)
+            )),
+            CharSetElement::PresetCharSet(name) => {
+                texts.push(get_preset_charset_texts(name).0.to_owned())
+            }
+            CharSetElement::CharSet(charset) => append_charset_element_texts(charset, texts),
+        }
+    }
+}
+
+// e.g. "'a', 'b' or 'c'"
+fn join_with_or(texts: &[String]) -> String {
+    match texts {
+        [] => String::new(),
+        [text] => text.to_owned(),
+        [heads @ .., last] => format!("{} or {}", heads.join(", "), last),
+    }
+}
+
+fn get_preset_charset_texts(name: &PresetCharSetName) -> (&'static str, &'static str) {
+    match name {
+        PresetCharSetName::CharWord => ("a word char", "word chars"),
+        PresetCharSetName::CharNotWord => ("a non-word char", "non-word chars"),
+        PresetCharSetName::CharDigit => ("a digit", "digits"),
+        PresetCharSetName::CharNotDigit => ("a non-digit char", "non-digit chars"),
+        PresetCharSetName::CharSpace => ("a white space", "white spaces"),
+        PresetCharSetName::CharNotSpace => ("a non-white-space char", "non-white-space chars"),
+        PresetCharSetName::CharHex => ("a hex digit", "hex digits"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_str_eq;
+
+    use crate::AnreError;
+
+    use super::{from_anre, from_regex};
+
+    #[test]
+    fn test_explain() {
+        assert_str_eq!(
+            from_regex(r#"(?<year>\d{4})-(\d{2,3}?)[^a-f\s]x*?y+z{2,}(?=q)\b\k<year>\1."#).unwrap(),
+            "\
+4 digits, captured as group #1 'year'
+followed by '-'
+followed by 2 to 3 digits (as few as possible), captured as group #2
+followed by a char other than 'a' to 'f' or a white space
+followed by zero or more 'x' (as few as possible)
+followed by one or more 'y'
+followed by at least 2 'z', only if followed by 'q'
+followed by a word boundary
+followed by the same text as group 'year'
+followed by the same text as group #1
+followed by any char"
+        );
+
+        assert_str_eq!(
+            from_anre(r#"'a'.optional_lazy(), 'b'.repeat_range(0, 3), is_not_before('d', 'e'), is_not_after('f', 'g'), start, end, char_hex"#).unwrap(),
+            "\
+optionally 'a' (preferably none)
+followed by 0 to 3 'b'
+followed by 'd', only if not followed by 'e'
+followed by 'f', only if not preceded by 'g'
+followed by the start of the text
+followed by the end of the text
+followed by a hex digit"
+        );
+
+        assert!(from_regex(r#"(a"#).is_err());
+    }
+
+    #[test]
+    fn test_explain_blocks() {
+        assert_str_eq!(
+            from_regex(r#"^(?:ab|c(d|e))+$"#).unwrap(),
+            "\
+the start of the text
+followed by the following, one or more times:
+  one of:
+    - \"ab\"
+    - 'c'
+      followed by the following, captured as group #1:
+        one of:
+          - 'd'
+          - 'e'
+followed by the end of the text"
+        );
+
+        assert_str_eq!(
+            from_regex(r#"(?m)^a(?=b|c)"#).unwrap(),
+            "\
+the following, in multiline mode:
+  the start of a line
+  followed by 'a'
+  only if followed by:
+    one of:
+      - 'b'
+      - 'c'"
+        );
+
+        // the condition of the lookbehind applies to the whole sequence
+        assert_str_eq!(
+            from_regex(r#"x(?<!w)(?i:k)\b"#).unwrap(),
+            "\
+'x'
+followed by the following:
+  'k', ignoring case
+  followed by a word boundary
+only if not preceded by 'w'"
+        );
+    }
+    #[test]
+    fn test_explain_invalid_arguments() {
+        for expression in [
+            r#"repeat('a')"#,
+            r#"'a'.repeat('b')"#,
+            r#"is_before('a')"#,
+            r#"at_least('a')"#,
+            r#"one_or_more()"#,
+        ] {
+            assert!(
+                matches!(from_anre(expression), Err(AnreError::SyntaxIncorrect(_))),
+                "{}",
+                expression
+            );
+        }
+    }
+}
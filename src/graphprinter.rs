@@ -0,0 +1,251 @@
+// Copyright (c) 2025 Hemashushu <hippospark@gmail.com>, All rights reserved.
+//
+// This Source Code Form is subject to the terms of
+// the Mozilla Public License version 2.0 and additional exceptions.
+// For more details, see the LICENSE, LICENSE.additional, and CONTRIBUTING files.
+
+// Renders the routes of the object file as graphs.
+//
+// Each route is drawn as a cluster (subgraph), the nodes are the vertices and
+// the transition items are the edges labeled with the transitions (the same
+// text as `get_debug_text`). The entry node is drawn in bold and the exit node
+// is drawn as a double circle.
+//
+// The lookaround assertions are linked to the clusters of their sub-routes
+// by dashed edges.
+
+use crate::{object_file::ObjectFile, transition::Transition};
+
+// The sub-route which is evaluated by the transition.
+fn get_sub_route_index(transition: &Transition) -> Option<usize> {
+    match transition {
+        Transition::LookAheadAssertion(transition) => Some(transition.route_index),
+        Transition::LookBehindAssertion(transition) => Some(transition.route_index),
+        _ => None,
+    }
+}
+
+/// Generates the Graphviz DOT text of the object file.
+pub fn to_dot(object_file: &ObjectFile) -> String {
+    let mut ss = vec![
+        "digraph automaton {".to_owned(),
+        "    rankdir=LR;".to_owned(),
+        "    compound=true;".to_owned(),
+        "    node [shape=circle];".to_owned(),
+    ];
+
+    let mut links = vec![];
+
+    for (route_index, route) in object_file.routes.iter().enumerate() {
+        ss.push(format!("    subgraph cluster_{} {{", route_index));
+        ss.push(format!("        label=\"${}\";", route_index));
+
+        for node_index in 0..route.nodes.len() {
+            let mut attributes = vec![format!("label=\"{}\"", node_index)];
+            if node_index == route.start_node_index {
+                attributes.push("penwidth=2".to_owned());
+            }
+            if node_index == route.end_node_index {
+                attributes.push("shape=doublecircle".to_owned());
+            }
+            ss.push(format!(
+                "        r{}_n{} [{}];",
+                route_index,
+                node_index,
+                attributes.join(", ")
+            ));
+        }
+
+        for (node_index, node) in route.nodes.iter().enumerate() {
+            for item in &node.transition_items {
+                ss.push(format!(
+                    "        r{}_n{} -> r{}_n{} [label=\"{}\"];",
+                    route_index,
+                    node_index,
+                    route_index,
+                    item.target_node_index,
+                    escape_dot(&item.transition.to_string())
+                ));
+
+                if let Some(sub_route_index) = get_sub_route_index(&item.transition) {
+                    let sub_route = &object_file.routes[sub_route_index];
+                    links.push(format!(
+                        "    r{}_n{} -> r{}_n{} [style=dashed, lhead=cluster_{}];",
+                        route_index,
+                        node_index,
+                        sub_route_index,
+                        sub_route.start_node_index,
+                        sub_route_index
+                    ));
+                }
+            }
+        }
+
+        ss.push("    }".to_owned());
+    }
+
+    ss.extend(links);
+    ss.push("}".to_owned());
+    ss.join("\n")
+}
+
+/// Generates the Mermaid flowchart text of the object file.
+pub fn to_mermaid(object_file: &ObjectFile) -> String {
+    let mut ss = vec!["flowchart LR".to_owned()];
+
+    let mut links = vec![];
+    let mut entry_nodes = vec![];
+
+    for (route_index, route) in object_file.routes.iter().enumerate() {
+        ss.push(format!(
+            "    subgraph route{} [\"${}\"]",
+            route_index, route_index
+        ));
+
+        for node_index in 0..route.nodes.len() {
+            let shape = if node_index == route.end_node_index {
+                format!("((({})))", node_index)
+            } else {
+                format!("(({}))", node_index)
+            };
+            ss.push(format!("        r{}_n{}{}", route_index, node_index, shape));
+        }
+
+        for (node_index, node) in route.nodes.iter().enumerate() {
+            for item in &node.transition_items {
+                ss.push(format!(
+                    "        r{}_n{} -->|\"{}\"| r{}_n{}",
+                    route_index,
+                    node_index,
+                    escape_mermaid(&item.transition.to_string()),
+                    route_index,
+                    item.target_node_index
+                ));
+
+                if let Some(sub_route_index) = get_sub_route_index(&item.transition) {
+                    links.push(format!(
+                        "    r{}_n{} -.-> route{}",
+                        route_index, node_index, sub_route_index
+                    ));
+                }
+            }
+        }
+
+        ss.push("    end".to_owned());
+
+        if !route.nodes.is_empty() {
+            entry_nodes.push(format!("r{}_n{}", route_index, route.start_node_index));
+        }
+    }
+
+    ss.extend(links);
+
+    if !entry_nodes.is_empty() {
+        ss.push("    classDef entry stroke-width:3px".to_owned());
+        ss.push(format!("    class {} entry", entry_nodes.join(",")));
+    }
+
+    ss.join("\n")
+}
+
+fn escape_dot(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+// Mermaid labels are HTML, so the special chars are written as entities.
+fn escape_mermaid(s: &str) -> String {
+    s.replace('&', "#amp;")
+        .replace('"', "#quot;")
+        .replace('<', "#lt;")
+        .replace('>', "#gt;")
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_str_eq;
+
+    use crate::compiler::compile_from_regex;
+
+    use super::{to_dot, to_mermaid};
+
+    #[test]
+    fn test_to_dot() {
+        let object_file = compile_from_regex(r#"a(?=b)"#).unwrap();
+        assert_str_eq!(
+            to_dot(&object_file),
+            r#"digraph automaton {
+    rankdir=LR;
+    compound=true;
+    node [shape=circle];
+    subgraph cluster_0 {
+        label="$0";
+        r0_n0 [label="0"];
+        r0_n1 [label="1"];
+        r0_n2 [label="2"];
+        r0_n3 [label="3"];
+        r0_n4 [label="4", penwidth=2];
+        r0_n5 [label="5", shape=doublecircle];
+        r0_n0 -> r0_n1 [label="Char 'a'"];
+        r0_n1 -> r0_n3 [label="Look ahead $1"];
+        r0_n2 -> r0_n0 [label="Jump"];
+        r0_n3 -> r0_n5 [label="Capture end {0}"];
+        r0_n4 -> r0_n2 [label="Capture start {0}"];
+    }
+    subgraph cluster_1 {
+        label="$1";
+        r1_n0 [label="0", penwidth=2];
+        r1_n1 [label="1", shape=doublecircle];
+        r1_n0 -> r1_n1 [label="Char 'b'"];
+    }
+    r0_n1 -> r1_n0 [style=dashed, lhead=cluster_1];
+}"#
+        );
+
+        // the quotes and backslashes are escaped
+        let object_file = compile_from_regex(r#""\\"#).unwrap();
+        assert!(to_dot(&object_file).contains(r#"[label="String \"\\\"\\\\\""];"#));
+    }
+
+    #[test]
+    fn test_to_mermaid() {
+        let object_file = compile_from_regex(r#"(?<!x)[a-c]{2}"#).unwrap();
+        assert_str_eq!(
+            to_mermaid(&object_file),
+            r#"flowchart LR
+    subgraph route0 ["$0"]
+        r0_n0((0))
+        r0_n1((1))
+        r0_n2((2))
+        r0_n3((3))
+        r0_n4((4))
+        r0_n5((5))
+        r0_n6((6))
+        r0_n7((7))
+        r0_n8((8))
+        r0_n9(((9)))
+        r0_n0 -->|"Charset ['a'..'c']"| r0_n1
+        r0_n1 -->|"Counter inc"| r0_n4
+        r0_n2 -->|"Counter reset"| r0_n3
+        r0_n3 -->|"Counter save"| r0_n0
+        r0_n4 -->|"Counter check times 2"| r0_n5
+        r0_n4 -->|"Repetition times 2"| r0_n3
+        r0_n5 -->|"Jump"| r0_n7
+        r0_n6 -->|"Look behind negative $1, match length 1"| r0_n2
+        r0_n7 -->|"Capture end {0}"| r0_n9
+        r0_n8 -->|"Capture start {0}"| r0_n6
+    end
+    subgraph route1 ["$1"]
+        r1_n0((0))
+        r1_n1(((1)))
+        r1_n0 -->|"Char 'x'"| r1_n1
+    end
+    r0_n6 -.-> route1
+    classDef entry stroke-width:3px
+    class r0_n8,r1_n0 entry"#
+        );
+
+        // the quotes are written as entities
+        let object_file = compile_from_regex(r#"""#).unwrap();
+        assert!(to_mermaid(&object_file).contains(r#"-->|"Char '#quot;'"|"#));
+    }
+}
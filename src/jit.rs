@@ -0,0 +1,854 @@
+// Copyright (c) 2025 Hemashushu <hippospark@gmail.com>, All rights reserved.
+//
+// This Source Code Form is subject to the terms of
+// the Mozilla Public License version 2.0 and additional exceptions.
+// For more details, see the LICENSE, LICENSE.additional, and CONTRIBUTING files.
+
+mod assembler;
+mod memory;
+
+use std::mem::{offset_of, size_of};
+
+use crate::{
+    context::{Context, MatchRange, Routine},
+    object_file::{ObjectFile, MAIN_ROUTE_INDEX},
+    regex::Anchored,
+    transition::{
+        get_position_by_chars_backward, CharSetItem, ExecuteResult, RepetitionType, Transition,
+    },
+    utf8reader::read_char,
+};
+
+use assembler::{Assembler, Cond, Label, Mem, Reg};
+use memory::ExecutableMemory;
+
+// The JIT compiler translates each route of an object file into a native function,
+// which does the same thing as `process::execute_transitions`, i.e. it tries
+// to match the route at the specified position by backtracking.
+//
+// Each node becomes a block of code which pushes the alternative transitions
+// (all the transitions except the first one) onto the backtracking stack,
+// and each transition becomes a block of code which jumps to the block of
+// the target node on success, or pops the stack and jumps to the popped
+// transition on failure.
+//
+// ```diagram
+//  node 0 block:      push (trans 0.2, position, count)
+//                     push (trans 0.1, position, count)
+//  trans 0.0 block:   check ... --- failure ---> pop & jump
+//                      |
+//                      | success
+//                      v
+//  node 1 block:      ...
+// ```
+//
+// The transitions which consume characters (e.g. chars, strings and ASCII chars of
+// char sets), the counter checks and the capture groups are translated into
+// native instructions, and the others (e.g. back references, the case-insensitive
+// transitions and the non-ASCII chars of char sets) call back into
+// `Transition::execute`. The lookaround assertions call back into
+// the `start_routine` of this module, so the sub-routes also run in native code.
+//
+// The register allocation of the native function:
+//
+// - rbx: the pointer of `Environment`.
+// - rbp: the current position.
+// - r8:  the current repetition count (it is not preserved by calls).
+// - r12: the pointer of the text.
+// - r13: the end position of the routine.
+// - r14: the top of the backtracking stack.
+// - r15: the remaining steps of the budget.
+// - [rsp]: the pointer of the `BacktrackStack`.
+
+/// The signature of the native function of a route.
+///
+/// Returns 1 if the route matches, otherwise returns 0.
+type RouteFunction = unsafe extern "C" fn(
+    environment: *mut Environment,
+    position: usize,
+    end_position: usize,
+    stack: *mut BacktrackStack,
+) -> u64;
+
+/// The native code of an object file.
+pub struct JitCode {
+    memory: ExecutableMemory,
+
+    // The offset of the native function of each route.
+    route_offsets: Vec<usize>,
+}
+
+impl JitCode {
+    /// Returns `None` if the executable memory can not be allocated.
+    pub fn compile(object_file: &ObjectFile) -> Option<JitCode> {
+        let mut assembler = Assembler::new();
+        let route_offsets = (0..object_file.routes.len())
+            .map(|route_index| {
+                let offset = assembler.offset();
+                emit_route(&mut assembler, object_file, route_index);
+                offset
+            })
+            .collect();
+
+        let code = assembler.finish();
+        let memory = ExecutableMemory::new(&code)?;
+
+        Some(JitCode {
+            memory,
+            route_offsets,
+        })
+    }
+
+    fn get_route_function(&self, route_index: usize) -> RouteFunction {
+        unsafe {
+            let pointer = self.memory.as_ptr().add(self.route_offsets[route_index]);
+            std::mem::transmute::<*const u8, RouteFunction>(pointer)
+        }
+    }
+}
+
+// The state shared by the native functions and the helper functions.
+struct Environment<'a, 'b> {
+    bytes: *const u8,
+    match_ranges: *mut MatchRange,
+    remaining_steps: usize,
+
+    // The repetition count returned by the helper functions.
+    repetition_count: usize,
+
+    // Whether the match of the main route must end at the end of the text range.
+    is_fixed_end_position: bool,
+
+    context: *mut Context<'b>,
+    object_file: &'a ObjectFile,
+    jit_code: &'a JitCode,
+
+    // One backtracking stack for each level of routines, since
+    // the lookaround assertions start new routines in the middle of a routine.
+    //
+    // The stacks are boxed because the native code holds their addresses
+    // while the vector grows.
+    #[allow(clippy::vec_box)]
+    stacks: Vec<Box<BacktrackStack>>,
+}
+
+// The backtracking stack, each item consists of three words:
+// the address of the transition block, the position and the repetition count.
+struct BacktrackStack {
+    base: *mut u64,
+    limit: *mut u64,
+    buffer: Vec<u64>,
+}
+
+const STACK_ITEM_WORDS: usize = 3;
+const STACK_ITEM_SIZE: i32 = (STACK_ITEM_WORDS * size_of::<u64>()) as i32;
+const INITIAL_STACK_ITEMS: usize = 64;
+
+impl BacktrackStack {
+    fn new() -> Self {
+        let mut buffer = vec![0; INITIAL_STACK_ITEMS * STACK_ITEM_WORDS];
+        let base = buffer.as_mut_ptr();
+        let limit = unsafe { base.add(buffer.len()) };
+        BacktrackStack {
+            base,
+            limit,
+            buffer,
+        }
+    }
+}
+
+/// Start a new process with the native code.
+///
+/// The result is written to `context.match_ranges`, the same as
+/// the backtracking process (`process::start_process`).
+pub fn start_process(
+    context: &mut Context,
+    object_file: &ObjectFile,
+    jit_code: &JitCode,
+    start_position: usize,
+    end_position: usize,
+    anchored: Anchored,
+) -> bool {
+    let route = &object_file.routes[MAIN_ROUTE_INDEX];
+    let is_fixed_start_position =
+        route.is_fixed_start_position || anchored.is_fixed_start_position();
+    let is_fixed_end_position = route.is_fixed_end_position || anchored.is_fixed_end_position();
+
+    let mut environment = Environment {
+        bytes: context.bytes.as_ptr(),
+        match_ranges: context.match_ranges.as_mut_ptr(),
+        remaining_steps: 0,
+        repetition_count: 0,
+        is_fixed_end_position,
+        context,
+        object_file,
+        jit_code,
+        stacks: vec![],
+    };
+
+    let result = start_routine_with_fixed_start(
+        &mut environment,
+        MAIN_ROUTE_INDEX,
+        start_position,
+        end_position,
+        is_fixed_start_position,
+    );
+    let remaining_steps = environment.remaining_steps;
+
+    // Give back the steps which are taken but unused.
+    context.budget.return_steps(remaining_steps);
+
+    // The result is discarded if the budget is exceeded.
+    result && !context.budget.is_exceeded()
+}
+
+// Start a routine, it is the same as `process::start_routine`
+// except that the transitions are executed by the native code.
+fn start_routine(
+    environment: &mut Environment,
+    route_index: usize,
+    start_position: usize, // Start position of the text range (inclusive).
+    end_position: usize,   // End position of the text range (exclusive).
+) -> bool {
+    let is_fixed_start_position =
+        environment.object_file.routes[route_index].is_fixed_start_position;
+    start_routine_with_fixed_start(
+        environment,
+        route_index,
+        start_position,
+        end_position,
+        is_fixed_start_position,
+    )
+}
+
+fn start_routine_with_fixed_start(
+    environment: &mut Environment,
+    route_index: usize,
+    start_position: usize,
+    end_position: usize,
+    is_fixed_start_position: bool,
+) -> bool {
+    // The main route skips the positions where a match can not start,
+    // the same as `process::start_routine`.
+    let prefilter = &environment.object_file.prefilter;
+    let use_prefilter = route_index == MAIN_ROUTE_INDEX && !is_fixed_start_position;
+    if use_prefilter {
+        let context = unsafe { &*environment.context };
+        if !prefilter.may_match(context.bytes, start_position, end_position) {
+            return false;
+        }
+    }
+
+    // The helper functions (which call `Transition::execute`) read
+    // the end position of the text range from the current routine.
+    let depth = {
+        let context = unsafe { &mut *environment.context };
+        context
+            .routines
+            .push(Routine::new(start_position, end_position, route_index));
+        context.routines.len() - 1
+    };
+
+    if environment.stacks.len() <= depth {
+        environment.stacks.push(Box::new(BacktrackStack::new()));
+    }
+
+    let stack: *mut BacktrackStack = &mut *environment.stacks[depth];
+    let function = environment.jit_code.get_route_function(route_index);
+
+    let mut result = false;
+    let mut position = start_position;
+
+    // The end position is also tried, since the expression
+    // may match an empty string.
+    loop {
+        if use_prefilter {
+            let context = unsafe { &*environment.context };
+            match prefilter.next_candidate(context.bytes, position, end_position) {
+                Some(candidate) => position = candidate,
+                None => break,
+            }
+        }
+
+        if unsafe { function(environment, position, end_position, stack) } != 0 {
+            result = true;
+            break;
+        }
+
+        let context = unsafe { &mut *environment.context };
+
+        // If the expression starts with "^...", there is no need to try remaining characters.
+        // Also stop trying if the budget is exceeded.
+        if is_fixed_start_position || context.budget.is_exceeded() || position >= end_position {
+            break;
+        }
+
+        // Move forward by one character and try again.
+        let (_, byte_length) = read_char(context.bytes, position);
+        position += byte_length;
+    }
+
+    unsafe { &mut *environment.context }.routines.pop();
+    result
+}
+
+// Helper functions which are called by the native code.
+//
+// The helpers of transitions return the new position on success,
+// or -1 on failure, and the new repetition count is written to
+// `Environment::repetition_count`.
+type HelperFunction = extern "C" fn(
+    environment: *mut Environment,
+    route_index: usize,
+    node_index: usize,
+    transition_index: usize,
+    position: usize,
+    repetition_count: usize,
+) -> isize;
+
+extern "C" fn helper_take_steps(environment: *mut Environment) -> usize {
+    let environment = unsafe { &mut *environment };
+    let context = unsafe { &mut *environment.context };
+    context.budget.take_steps()
+}
+
+extern "C" fn helper_grow_stack(
+    stack: *mut BacktrackStack,
+    top: *mut u64,
+    additional_words: usize,
+) -> *mut u64 {
+    let stack = unsafe { &mut *stack };
+    let used_words = unsafe { top.offset_from(stack.base) } as usize;
+    let length = (stack.buffer.len() * 2).max(used_words + additional_words);
+
+    stack.buffer.resize(length, 0);
+    stack.base = stack.buffer.as_mut_ptr();
+    stack.limit = unsafe { stack.base.add(length) };
+    unsafe { stack.base.add(used_words) }
+}
+
+extern "C" fn helper_execute(
+    environment: *mut Environment,
+    route_index: usize,
+    node_index: usize,
+    transition_index: usize,
+    position: usize,
+    repetition_count: usize,
+) -> isize {
+    let environment = unsafe { &mut *environment };
+    let context = unsafe { &mut *environment.context };
+    let object_file = environment.object_file;
+    let transition = &object_file.routes[route_index].nodes[node_index].transition_items
+        [transition_index]
+        .transition;
+
+    match transition.execute(context, object_file, position, repetition_count) {
+        ExecuteResult::Success(move_forward, current_repetition_count) => {
+            environment.repetition_count = current_repetition_count;
+            (position + move_forward) as isize
+        }
+        ExecuteResult::Failure => -1,
+    }
+}
+
+extern "C" fn helper_look_around(
+    environment: *mut Environment,
+    route_index: usize,
+    node_index: usize,
+    transition_index: usize,
+    position: usize,
+    _repetition_count: usize,
+) -> isize {
+    let environment = unsafe { &mut *environment };
+    let object_file = environment.object_file;
+    let transition = &object_file.routes[route_index].nodes[node_index].transition_items
+        [transition_index]
+        .transition;
+    let bytes = unsafe { &*environment.context }.bytes;
+
+    let result = match transition {
+        Transition::LookAheadAssertion(transition) => {
+            start_routine(environment, transition.route_index, position, bytes.len())
+                ^ transition.negative
+        }
+        Transition::LookBehindAssertion(transition) => {
+            let thread_result = if let Ok(start) =
+                get_position_by_chars_backward(bytes, position, transition.match_length_in_char)
+            {
+                start_routine(environment, transition.route_index, start, bytes.len())
+            } else {
+                false
+            };
+
+            thread_result ^ transition.negative
+        }
+        _ => unreachable!(),
+    };
+
+    if result {
+        // Assertion should not move the position of parent thread.
+        environment.repetition_count = 0;
+        position as isize
+    } else {
+        -1
+    }
+}
+
+// Code generation
+
+const ENVIRONMENT_BYTES: i32 = offset_of!(Environment, bytes) as i32;
+const ENVIRONMENT_MATCH_RANGES: i32 = offset_of!(Environment, match_ranges) as i32;
+const ENVIRONMENT_REMAINING_STEPS: i32 = offset_of!(Environment, remaining_steps) as i32;
+const ENVIRONMENT_REPETITION_COUNT: i32 = offset_of!(Environment, repetition_count) as i32;
+const ENVIRONMENT_IS_FIXED_END_POSITION: i32 =
+    offset_of!(Environment, is_fixed_end_position) as i32;
+const STACK_BASE: i32 = offset_of!(BacktrackStack, base) as i32;
+const STACK_LIMIT: i32 = offset_of!(BacktrackStack, limit) as i32;
+
+// The registers, see the register allocation at the top of this file.
+const ENVIRONMENT: Reg = Reg::Rbx;
+const POSITION: Reg = Reg::Rbp;
+const REPETITION_COUNT: Reg = Reg::R8;
+const BYTES: Reg = Reg::R12;
+const END_POSITION: Reg = Reg::R13;
+const STACK_TOP: Reg = Reg::R14;
+const REMAINING_STEPS: Reg = Reg::R15;
+
+const CALLEE_SAVED_REGISTERS: [Reg; 6] =
+    [Reg::Rbx, Reg::Rbp, Reg::R12, Reg::R13, Reg::R14, Reg::R15];
+
+// The labels shared by all blocks of a route.
+struct RouteLabels {
+    backtrack: Label,
+    success: Label,
+    take_steps: Label,
+}
+
+fn emit_route(a: &mut Assembler, object_file: &ObjectFile, route_index: usize) {
+    let route = &object_file.routes[route_index];
+
+    let labels = RouteLabels {
+        backtrack: a.create_label(),
+        success: a.create_label(),
+        take_steps: a.create_label(),
+    };
+    let failure = a.create_label();
+    let epilogue = a.create_label();
+
+    let node_labels: Vec<Label> = route.nodes.iter().map(|_| a.create_label()).collect();
+
+    // Prologue
+    //
+    // The stack is 16-byte aligned after pushing 6 registers and
+    // the slot of the backtracking stack pointer.
+    for reg in CALLEE_SAVED_REGISTERS {
+        a.push(reg);
+    }
+    a.sub_ri(Reg::Rsp, 8);
+
+    a.mov_mr(Mem::base(Reg::Rsp, 0), Reg::Rcx);
+    a.mov_rr(ENVIRONMENT, Reg::Rdi);
+    a.mov_rr(POSITION, Reg::Rsi);
+    a.mov_rr(END_POSITION, Reg::Rdx);
+    a.mov_rm(BYTES, Mem::base(ENVIRONMENT, ENVIRONMENT_BYTES));
+    a.mov_rm(
+        REMAINING_STEPS,
+        Mem::base(ENVIRONMENT, ENVIRONMENT_REMAINING_STEPS),
+    );
+    a.mov_rm(STACK_TOP, Mem::base(Reg::Rcx, STACK_BASE));
+    a.zero(REPETITION_COUNT);
+    a.jmp(node_labels[route.start_node_index]);
+
+    // Pop the backtracking stack and jump to the popped transition,
+    // the route matching fails if the stack is empty.
+    a.bind(labels.backtrack);
+    a.mov_rm(Reg::Rax, Mem::base(Reg::Rsp, 0));
+    a.cmp_rm(STACK_TOP, Mem::base(Reg::Rax, STACK_BASE));
+    a.jcc(Cond::BelowOrEqual, failure);
+    a.sub_ri(STACK_TOP, STACK_ITEM_SIZE);
+    a.mov_rm(POSITION, Mem::base(STACK_TOP, 8));
+    a.mov_rm(REPETITION_COUNT, Mem::base(STACK_TOP, 16));
+    a.mov_rm(Reg::Rax, Mem::base(STACK_TOP, 0));
+    a.jmp_r(Reg::Rax);
+
+    a.bind(labels.success);
+    if route_index == MAIN_ROUTE_INDEX {
+        // The match must reach the end of the text range if
+        // the main route is anchored at the end, otherwise backtrack.
+        let matched = a.create_label();
+        a.cmp_m8i(Mem::base(ENVIRONMENT, ENVIRONMENT_IS_FIXED_END_POSITION), 0);
+        a.jcc(Cond::Equal, matched);
+        a.cmp_rr(POSITION, END_POSITION);
+        a.jcc(Cond::NotEqual, labels.backtrack);
+        a.bind(matched);
+    }
+    a.mov_ri(Reg::Rax, 1);
+    a.jmp(epilogue);
+
+    a.bind(failure);
+    a.zero(Reg::Rax);
+
+    // Epilogue
+    a.bind(epilogue);
+    a.mov_mr(
+        Mem::base(ENVIRONMENT, ENVIRONMENT_REMAINING_STEPS),
+        REMAINING_STEPS,
+    );
+    a.add_ri(Reg::Rsp, 8);
+    for reg in CALLEE_SAVED_REGISTERS.iter().rev() {
+        a.pop(*reg);
+    }
+    a.ret();
+
+    // The sub-routine for taking a batch of steps of the budget, the route matching
+    // fails if the budget is exceeded.
+    //
+    // The stack is 16-byte aligned again after reserving 8 bytes,
+    // since the `call` instruction pushes the return address.
+    let exceeded = a.create_label();
+    a.bind(labels.take_steps);
+    a.sub_ri(Reg::Rsp, 8);
+    a.mov_mr(
+        Mem::base(ENVIRONMENT, ENVIRONMENT_REPETITION_COUNT),
+        REPETITION_COUNT,
+    );
+    a.mov_rr(Reg::Rdi, ENVIRONMENT);
+    a.mov_ri(Reg::Rax, helper_take_steps as *const () as u64);
+    a.call_r(Reg::Rax);
+    a.mov_rm(
+        REPETITION_COUNT,
+        Mem::base(ENVIRONMENT, ENVIRONMENT_REPETITION_COUNT),
+    );
+    a.add_ri(Reg::Rsp, 8);
+    a.mov_rr(REMAINING_STEPS, Reg::Rax);
+    a.test_rr(Reg::Rax, Reg::Rax);
+    a.jcc(Cond::Equal, exceeded);
+    a.ret();
+
+    a.bind(exceeded);
+    // Discard the return address.
+    a.add_ri(Reg::Rsp, 8);
+    a.jmp(failure);
+
+    // Nodes and transitions
+    for (node_index, node) in route.nodes.iter().enumerate() {
+        a.bind(node_labels[node_index]);
+
+        let count = node.transition_items.len();
+        if count == 0 {
+            a.jmp(labels.backtrack);
+            continue;
+        }
+
+        let transition_labels: Vec<Label> = (0..count).map(|_| a.create_label()).collect();
+
+        if count > 1 {
+            emit_reserve_stack(a, count - 1);
+
+            // Push the alternative transitions in reverse order,
+            // since the stack pops the last item first.
+            for label in transition_labels[1..].iter().rev() {
+                a.lea_label(Reg::Rax, *label);
+                a.mov_mr(Mem::base(STACK_TOP, 0), Reg::Rax);
+                a.mov_mr(Mem::base(STACK_TOP, 8), POSITION);
+                a.mov_mr(Mem::base(STACK_TOP, 16), REPETITION_COUNT);
+                a.add_ri(STACK_TOP, STACK_ITEM_SIZE);
+            }
+        }
+
+        // The first transition follows the node block directly.
+        for (transition_index, transition_item) in node.transition_items.iter().enumerate() {
+            a.bind(transition_labels[transition_index]);
+
+            let target = if transition_item.target_node_index == route.end_node_index {
+                // Reached the last node of the route, meaning the route matching succeeded.
+                labels.success
+            } else {
+                node_labels[transition_item.target_node_index]
+            };
+
+            let location = (route_index, node_index, transition_index);
+            emit_transition(a, &labels, &transition_item.transition, location, target);
+        }
+    }
+}
+
+// Ensure that the backtracking stack has room for the specified number of items.
+fn emit_reserve_stack(a: &mut Assembler, items: usize) {
+    let enough = a.create_label();
+    let words = items * STACK_ITEM_WORDS;
+
+    a.lea_rm(
+        Reg::Rax,
+        Mem::base(STACK_TOP, (words * size_of::<u64>()) as i32),
+    );
+    a.mov_rm(Reg::Rcx, Mem::base(Reg::Rsp, 0));
+    a.cmp_rm(Reg::Rax, Mem::base(Reg::Rcx, STACK_LIMIT));
+    a.jcc(Cond::BelowOrEqual, enough);
+
+    a.mov_mr(
+        Mem::base(ENVIRONMENT, ENVIRONMENT_REPETITION_COUNT),
+        REPETITION_COUNT,
+    );
+    a.mov_rr(Reg::Rdi, Reg::Rcx);
+    a.mov_rr(Reg::Rsi, STACK_TOP);
+    a.mov_ri(Reg::Rdx, words as u64);
+    a.mov_ri(Reg::Rax, helper_grow_stack as *const () as u64);
+    a.call_r(Reg::Rax);
+    a.mov_rr(STACK_TOP, Reg::Rax);
+    a.mov_rm(
+        REPETITION_COUNT,
+        Mem::base(ENVIRONMENT, ENVIRONMENT_REPETITION_COUNT),
+    );
+
+    a.bind(enough);
+}
+
+fn emit_transition(
+    a: &mut Assembler,
+    labels: &RouteLabels,
+    transition: &Transition,
+    location: (usize, usize, usize), // (route index, node index, transition index)
+    target: Label,
+) {
+    // Each executed transition takes a step of the budget.
+    let has_steps = a.create_label();
+    a.test_rr(REMAINING_STEPS, REMAINING_STEPS);
+    a.jcc(Cond::NotEqual, has_steps);
+    a.call(labels.take_steps);
+    a.bind(has_steps);
+    a.dec(REMAINING_STEPS);
+
+    match transition {
+        Transition::Jump(_) | Transition::CounterReset(_) => {
+            a.zero(REPETITION_COUNT);
+            a.jmp(target);
+        }
+        Transition::Char(transition) if !transition.ignore_case => {
+            let c = char::from_u32(transition.codepoint).unwrap();
+            let mut buffer = [0u8; 4];
+            emit_bytes(a, labels, c.encode_utf8(&mut buffer).as_bytes(), target);
+        }
+        Transition::String(transition) if !transition.ignore_case => {
+            let s: String = transition
+                .codepoints
+                .iter()
+                .map(|codepoint| char::from_u32(*codepoint).unwrap())
+                .collect();
+            emit_bytes(a, labels, s.as_bytes(), target);
+        }
+        Transition::SpecialChar(transition) => {
+            let slow = a.create_label();
+
+            a.cmp_rr(POSITION, END_POSITION);
+            a.jcc(Cond::AboveOrEqual, labels.backtrack);
+            a.movzx_rm8(Reg::Rax, Mem::indexed(BYTES, POSITION, 0));
+
+            // Any byte is a single "char" in the byte mode.
+            if !transition.matches_any_byte {
+                a.cmp_ri(Reg::Rax, 0x80);
+                a.jcc(Cond::AboveOrEqual, slow);
+            }
+
+            if !transition.dot_matches_new_line {
+                a.cmp_ri(Reg::Rax, '\n' as i32);
+                a.jcc(Cond::Equal, labels.backtrack);
+                a.cmp_ri(Reg::Rax, '\r' as i32);
+                a.jcc(Cond::Equal, labels.backtrack);
+            }
+
+            a.inc(POSITION);
+            a.zero(REPETITION_COUNT);
+            a.jmp(target);
+
+            // The non-ASCII chars
+            a.bind(slow);
+            emit_helper_call(a, labels, helper_execute, location, target);
+        }
+        Transition::CharSet(transition) if !transition.ignore_case => {
+            let slow = a.create_label();
+            let found = a.create_label();
+            let matched = a.create_label();
+
+            a.cmp_rr(POSITION, END_POSITION);
+            a.jcc(Cond::AboveOrEqual, labels.backtrack);
+            a.movzx_rm8(Reg::Rax, Mem::indexed(BYTES, POSITION, 0));
+            a.cmp_ri(Reg::Rax, 0x80);
+            a.jcc(Cond::AboveOrEqual, slow);
+
+            // Check the ASCII part of the items.
+            for item in &transition.items {
+                let (start, end_included) = match item {
+                    CharSetItem::Char(c) => (*c, *c),
+                    CharSetItem::Range(r) => (r.start, r.end_included),
+                };
+
+                if start >= 0x80 {
+                    continue;
+                }
+
+                let end_included = end_included.min(0x7f);
+                if start == end_included {
+                    a.cmp_ri(Reg::Rax, start as i32);
+                    a.jcc(Cond::Equal, found);
+                } else if start < end_included {
+                    a.lea_rm(Reg::Rcx, Mem::base(Reg::Rax, -(start as i32)));
+                    a.cmp_ri(Reg::Rcx, (end_included - start) as i32);
+                    a.jcc(Cond::BelowOrEqual, found);
+                }
+            }
+
+            if transition.negative {
+                a.jmp(matched);
+                a.bind(found);
+                a.jmp(labels.backtrack);
+            } else {
+                a.jmp(labels.backtrack);
+                a.bind(found);
+            }
+
+            a.bind(matched);
+            a.inc(POSITION);
+            a.zero(REPETITION_COUNT);
+            a.jmp(target);
+
+            // The non-ASCII chars
+            a.bind(slow);
+            emit_helper_call(a, labels, helper_execute, location, target);
+        }
+        Transition::CaptureStart(transition) => {
+            emit_capture(
+                a,
+                transition.capture_group_index,
+                offset_of!(MatchRange, start),
+            );
+            a.zero(REPETITION_COUNT);
+            a.jmp(target);
+        }
+        Transition::CaptureEnd(transition) => {
+            emit_capture(
+                a,
+                transition.capture_group_index,
+                offset_of!(MatchRange, end),
+            );
+            a.zero(REPETITION_COUNT);
+            a.jmp(target);
+        }
+        Transition::CounterCheck(transition) => {
+            // The repetition count is unchanged.
+            match transition.repetition_type {
+                RepetitionType::Specified(m) => {
+                    emit_compare_repetition_count(a, m);
+                    a.jcc(Cond::NotEqual, labels.backtrack);
+                }
+                RepetitionType::Range(from, to) => {
+                    emit_compare_repetition_count(a, from);
+                    a.jcc(Cond::Below, labels.backtrack);
+
+                    if to != usize::MAX {
+                        emit_compare_repetition_count(a, to);
+                        a.jcc(Cond::Above, labels.backtrack);
+                    }
+                }
+            }
+            a.jmp(target);
+        }
+        Transition::Repetition(transition) => {
+            // The repetition count is unchanged.
+            let (RepetitionType::Specified(to) | RepetitionType::Range(_, to)) =
+                transition.repetition_type;
+            emit_compare_repetition_count(a, to);
+            a.jcc(Cond::AboveOrEqual, labels.backtrack);
+            a.jmp(target);
+        }
+        Transition::LookAheadAssertion(_) | Transition::LookBehindAssertion(_) => {
+            emit_helper_call(a, labels, helper_look_around, location, target);
+        }
+        _ => {
+            emit_helper_call(a, labels, helper_execute, location, target);
+        }
+    }
+}
+
+// Match the specified bytes at the current position.
+fn emit_bytes(a: &mut Assembler, labels: &RouteLabels, bytes: &[u8], target: Label) {
+    a.lea_rm(Reg::Rax, Mem::base(POSITION, bytes.len() as i32));
+    a.cmp_rr(Reg::Rax, END_POSITION);
+    a.jcc(Cond::Above, labels.backtrack);
+
+    // Compare 8 bytes at a time, and then the remaining bytes one by one.
+    let mut offset = 0;
+    for chunk in bytes.chunks(8) {
+        if chunk.len() == 8 {
+            a.mov_ri(Reg::Rcx, u64::from_le_bytes(chunk.try_into().unwrap()));
+            a.cmp_mr(Mem::indexed(BYTES, POSITION, offset), Reg::Rcx);
+            a.jcc(Cond::NotEqual, labels.backtrack);
+        } else {
+            for (idx, byte) in chunk.iter().enumerate() {
+                a.cmp_m8i(Mem::indexed(BYTES, POSITION, offset + idx as i32), *byte);
+                a.jcc(Cond::NotEqual, labels.backtrack);
+            }
+        }
+        offset += chunk.len() as i32;
+    }
+
+    a.mov_rr(POSITION, Reg::Rax);
+    a.zero(REPETITION_COUNT);
+    a.jmp(target);
+}
+
+fn emit_capture(a: &mut Assembler, capture_group_index: usize, field_offset: usize) {
+    let disp = capture_group_index * size_of::<MatchRange>() + field_offset;
+    a.mov_rm(Reg::Rax, Mem::base(ENVIRONMENT, ENVIRONMENT_MATCH_RANGES));
+    a.mov_mr(Mem::base(Reg::Rax, disp as i32), POSITION);
+}
+
+// Compare the repetition count with the specified value (unsigned).
+fn emit_compare_repetition_count(a: &mut Assembler, value: usize) {
+    if let Ok(imm) = i32::try_from(value) {
+        a.cmp_ri(REPETITION_COUNT, imm);
+    } else {
+        a.mov_ri(Reg::Rax, value as u64);
+        a.cmp_rr(REPETITION_COUNT, Reg::Rax);
+    }
+}
+
+// Call the helper function of a transition, i.e. `helper_execute` or `helper_look_around`.
+fn emit_helper_call(
+    a: &mut Assembler,
+    labels: &RouteLabels,
+    helper: HelperFunction,
+    location: (usize, usize, usize),
+    target: Label,
+) {
+    let (route_index, node_index, transition_index) = location;
+
+    // The helper function may start new routines which use the budget.
+    a.mov_mr(
+        Mem::base(ENVIRONMENT, ENVIRONMENT_REMAINING_STEPS),
+        REMAINING_STEPS,
+    );
+
+    a.mov_rr(Reg::R9, REPETITION_COUNT);
+    a.mov_rr(Reg::R8, POSITION);
+    a.mov_ri(Reg::Rcx, transition_index as u64);
+    a.mov_ri(Reg::Rdx, node_index as u64);
+    a.mov_ri(Reg::Rsi, route_index as u64);
+    a.mov_rr(Reg::Rdi, ENVIRONMENT);
+    a.mov_ri(Reg::Rax, helper as *const () as u64);
+    a.call_r(Reg::Rax);
+
+    a.mov_rm(
+        REMAINING_STEPS,
+        Mem::base(ENVIRONMENT, ENVIRONMENT_REMAINING_STEPS),
+    );
+    a.test_rr(Reg::Rax, Reg::Rax);
+    a.jcc(Cond::Sign, labels.backtrack);
+
+    a.mov_rr(POSITION, Reg::Rax);
+    a.mov_rm(
+        REPETITION_COUNT,
+        Mem::base(ENVIRONMENT, ENVIRONMENT_REPETITION_COUNT),
+    );
+    a.jmp(target);
+}
@@ -0,0 +1,411 @@
+// Copyright (c) 2025 Hemashushu <hippospark@gmail.com>, All rights reserved.
+//
+// This Source Code Form is subject to the terms of
+// the Mozilla Public License version 2.0 and additional exceptions.
+// For more details, see the LICENSE, LICENSE.additional, and CONTRIBUTING files.
+
+// A minimal x86-64 assembler, it supports only the instructions
+// required by the JIT code generator.
+//
+// All memory operands are encoded with a 32-bit displacement,
+// and all jumps (and calls) to labels are encoded with a 32-bit relative offset,
+// which makes the encoding simple at the cost of a few bytes.
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Reg {
+    Rax = 0,
+    Rcx = 1,
+    Rdx = 2,
+    Rbx = 3,
+    Rsp = 4,
+    Rbp = 5,
+    Rsi = 6,
+    Rdi = 7,
+    R8 = 8,
+    R9 = 9,
+    R12 = 12,
+    R13 = 13,
+    R14 = 14,
+    R15 = 15,
+}
+
+impl Reg {
+    fn low_bits(self) -> u8 {
+        (self as u8) & 0b111
+    }
+}
+
+// The condition codes of the conditional jump instructions.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Cond {
+    Below = 0x2,
+    AboveOrEqual = 0x3,
+    Equal = 0x4,
+    NotEqual = 0x5,
+    BelowOrEqual = 0x6,
+    Above = 0x7,
+    Sign = 0x8,
+}
+
+// A memory operand in the form of `[base + index + disp]`.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Mem {
+    base: Reg,
+    index: Option<Reg>,
+    disp: i32,
+}
+
+impl Mem {
+    pub fn base(base: Reg, disp: i32) -> Self {
+        Mem {
+            base,
+            index: None,
+            disp,
+        }
+    }
+
+    // Note that `Reg::Rsp` can not be used as an index.
+    pub fn indexed(base: Reg, index: Reg, disp: i32) -> Self {
+        Mem {
+            base,
+            index: Some(index),
+            disp,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Label(usize);
+
+pub struct Assembler {
+    code: Vec<u8>,
+
+    // The offsets of the bound labels.
+    labels: Vec<Option<usize>>,
+
+    // The places to be filled with the relative offsets of labels,
+    // i.e. (the offset of the 32-bit field, the target label).
+    fixups: Vec<(usize, Label)>,
+}
+
+impl Assembler {
+    pub fn new() -> Self {
+        Assembler {
+            code: vec![],
+            labels: vec![],
+            fixups: vec![],
+        }
+    }
+
+    pub fn offset(&self) -> usize {
+        self.code.len()
+    }
+
+    pub fn create_label(&mut self) -> Label {
+        let idx = self.labels.len();
+        self.labels.push(None);
+        Label(idx)
+    }
+
+    pub fn bind(&mut self, label: Label) {
+        self.labels[label.0] = Some(self.code.len());
+    }
+
+    // Fills the relative offsets of the labels and returns the machine code.
+    pub fn finish(mut self) -> Vec<u8> {
+        for (field_offset, label) in &self.fixups {
+            let target = self.labels[label.0].expect("Label is not bound.");
+
+            // The relative offset is based on the end of the 32-bit field,
+            // which is also the end of the instruction.
+            let relative = target as i64 - (*field_offset as i64 + 4);
+            self.code[*field_offset..*field_offset + 4]
+                .copy_from_slice(&(relative as i32).to_le_bytes());
+        }
+
+        self.code
+    }
+
+    // Instructions
+
+    pub fn push(&mut self, reg: Reg) {
+        self.emit_rex(false, 0, 0, reg as u8);
+        self.code.push(0x50 + reg.low_bits());
+    }
+
+    pub fn pop(&mut self, reg: Reg) {
+        self.emit_rex(false, 0, 0, reg as u8);
+        self.code.push(0x58 + reg.low_bits());
+    }
+
+    pub fn ret(&mut self) {
+        self.code.push(0xc3);
+    }
+
+    // `mov dst, src`
+    pub fn mov_rr(&mut self, dst: Reg, src: Reg) {
+        self.emit_rr(&[0x89], src, dst);
+    }
+
+    // `mov dst, [mem]`
+    pub fn mov_rm(&mut self, dst: Reg, mem: Mem) {
+        self.emit_rm(true, &[0x8b], dst as u8, mem);
+    }
+
+    // `mov [mem], src`
+    pub fn mov_mr(&mut self, mem: Mem, src: Reg) {
+        self.emit_rm(true, &[0x89], src as u8, mem);
+    }
+
+    // `mov dst, imm`
+    pub fn mov_ri(&mut self, dst: Reg, imm: u64) {
+        if let Ok(imm32) = u32::try_from(imm) {
+            // The 32-bit form zero-extends the value.
+            self.emit_rex(false, 0, 0, dst as u8);
+            self.code.push(0xb8 + dst.low_bits());
+            self.code.extend_from_slice(&imm32.to_le_bytes());
+        } else {
+            self.emit_rex(true, 0, 0, dst as u8);
+            self.code.push(0xb8 + dst.low_bits());
+            self.code.extend_from_slice(&imm.to_le_bytes());
+        }
+    }
+
+    // `xor dst, dst`
+    pub fn zero(&mut self, dst: Reg) {
+        self.emit_rex(false, dst as u8, 0, dst as u8);
+        self.code.push(0x31);
+        self.emit_modrm(0b11, dst as u8, dst as u8);
+    }
+
+    // `movzx dst, byte [mem]`
+    pub fn movzx_rm8(&mut self, dst: Reg, mem: Mem) {
+        self.emit_rm(true, &[0x0f, 0xb6], dst as u8, mem);
+    }
+
+    // `lea dst, [mem]`
+    pub fn lea_rm(&mut self, dst: Reg, mem: Mem) {
+        self.emit_rm(true, &[0x8d], dst as u8, mem);
+    }
+
+    // `lea dst, [rip + label]`
+    pub fn lea_label(&mut self, dst: Reg, label: Label) {
+        self.emit_rex(true, dst as u8, 0, 0);
+        self.code.push(0x8d);
+        self.emit_modrm(0b00, dst as u8, 0b101);
+        self.emit_fixup(label);
+    }
+
+    // `add dst, imm`
+    pub fn add_ri(&mut self, dst: Reg, imm: i32) {
+        self.emit_ri(0, dst, imm);
+    }
+
+    // `sub dst, imm`
+    pub fn sub_ri(&mut self, dst: Reg, imm: i32) {
+        self.emit_ri(5, dst, imm);
+    }
+
+    // `cmp left, imm`
+    pub fn cmp_ri(&mut self, left: Reg, imm: i32) {
+        self.emit_ri(7, left, imm);
+    }
+
+    // `cmp left, right`
+    pub fn cmp_rr(&mut self, left: Reg, right: Reg) {
+        self.emit_rr(&[0x39], right, left);
+    }
+
+    // `cmp left, [mem]`
+    pub fn cmp_rm(&mut self, left: Reg, mem: Mem) {
+        self.emit_rm(true, &[0x3b], left as u8, mem);
+    }
+
+    // `cmp [mem], right`
+    pub fn cmp_mr(&mut self, mem: Mem, right: Reg) {
+        self.emit_rm(true, &[0x39], right as u8, mem);
+    }
+
+    // `cmp byte [mem], imm`
+    pub fn cmp_m8i(&mut self, mem: Mem, imm: u8) {
+        self.emit_rm(false, &[0x80], 7, mem);
+        self.code.push(imm);
+    }
+
+    // `test left, right`
+    pub fn test_rr(&mut self, left: Reg, right: Reg) {
+        self.emit_rr(&[0x85], right, left);
+    }
+
+    // `inc dst`
+    pub fn inc(&mut self, dst: Reg) {
+        self.emit_rex(true, 0, 0, dst as u8);
+        self.code.push(0xff);
+        self.emit_modrm(0b11, 0, dst as u8);
+    }
+
+    // `dec dst`
+    pub fn dec(&mut self, dst: Reg) {
+        self.emit_rex(true, 0, 0, dst as u8);
+        self.code.push(0xff);
+        self.emit_modrm(0b11, 1, dst as u8);
+    }
+
+    pub fn jmp(&mut self, label: Label) {
+        self.code.push(0xe9);
+        self.emit_fixup(label);
+    }
+
+    pub fn jcc(&mut self, cond: Cond, label: Label) {
+        self.code.extend_from_slice(&[0x0f, 0x80 + cond as u8]);
+        self.emit_fixup(label);
+    }
+
+    // `jmp reg`
+    pub fn jmp_r(&mut self, reg: Reg) {
+        self.emit_rex(false, 0, 0, reg as u8);
+        self.code.push(0xff);
+        self.emit_modrm(0b11, 4, reg as u8);
+    }
+
+    pub fn call(&mut self, label: Label) {
+        self.code.push(0xe8);
+        self.emit_fixup(label);
+    }
+
+    // `call reg`
+    pub fn call_r(&mut self, reg: Reg) {
+        self.emit_rex(false, 0, 0, reg as u8);
+        self.code.push(0xff);
+        self.emit_modrm(0b11, 2, reg as u8);
+    }
+
+    // Encoding
+
+    // The REX prefix: `0100WRXB`, it is omitted if all the bits are 0.
+    fn emit_rex(&mut self, w: bool, reg: u8, index: u8, base: u8) {
+        let rex = 0x40
+            | ((w as u8) << 3)
+            | (((reg >> 3) & 1) << 2)
+            | (((index >> 3) & 1) << 1)
+            | ((base >> 3) & 1);
+        if rex != 0x40 {
+            self.code.push(rex);
+        }
+    }
+
+    fn emit_modrm(&mut self, mod_: u8, reg: u8, rm: u8) {
+        self.code
+            .push((mod_ << 6) | ((reg & 0b111) << 3) | (rm & 0b111));
+    }
+
+    fn emit_fixup(&mut self, label: Label) {
+        self.fixups.push((self.code.len(), label));
+        self.code.extend_from_slice(&[0; 4]);
+    }
+
+    // An instruction with 64-bit register operands.
+    fn emit_rr(&mut self, opcode: &[u8], reg: Reg, rm: Reg) {
+        self.emit_rex(true, reg as u8, 0, rm as u8);
+        self.code.extend_from_slice(opcode);
+        self.emit_modrm(0b11, reg as u8, rm as u8);
+    }
+
+    // An instruction with the opcode `81 /ext id`.
+    fn emit_ri(&mut self, ext: u8, dst: Reg, imm: i32) {
+        self.emit_rex(true, 0, 0, dst as u8);
+        self.code.push(0x81);
+        self.emit_modrm(0b11, ext, dst as u8);
+        self.code.extend_from_slice(&imm.to_le_bytes());
+    }
+
+    // An instruction with a memory operand, the `reg` is either
+    // a register number or an opcode extension.
+    fn emit_rm(&mut self, w: bool, opcode: &[u8], reg: u8, mem: Mem) {
+        let index = mem.index.map_or(0, |r| r as u8);
+        self.emit_rex(w, reg, index, mem.base as u8);
+        self.code.extend_from_slice(opcode);
+
+        // Always use the 32-bit displacement form (mod = 10).
+        if mem.index.is_some() || mem.base.low_bits() == 0b100 {
+            // The SIB byte is required, "index = 100" means no index.
+            let sib_index = mem.index.map_or(0b100, |r| r.low_bits());
+            self.emit_modrm(0b10, reg, 0b100);
+            self.code.push((sib_index << 3) | mem.base.low_bits());
+        } else {
+            self.emit_modrm(0b10, reg, mem.base.low_bits());
+        }
+
+        self.code.extend_from_slice(&mem.disp.to_le_bytes());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::{Assembler, Cond, Mem, Reg};
+
+    #[test]
+    fn test_assembler_encoding() {
+        let mut a = Assembler::new();
+        a.push(Reg::Rbx);
+        a.push(Reg::R15);
+        a.mov_rr(Reg::Rbx, Reg::Rdi);
+        a.mov_rm(Reg::R12, Mem::base(Reg::Rbx, 8));
+        a.mov_mr(Mem::base(Reg::Rsp, 0), Reg::Rcx);
+        a.movzx_rm8(Reg::Rax, Mem::indexed(Reg::R12, Reg::Rbp, 1));
+        a.cmp_m8i(Mem::indexed(Reg::R12, Reg::Rbp, 0), 0x61);
+        a.mov_ri(Reg::R8, 1);
+        a.mov_ri(Reg::Rax, 0x1_0000_0000);
+        a.zero(Reg::R8);
+        a.cmp_ri(Reg::R8, 3);
+        a.dec(Reg::R15);
+        a.call_r(Reg::Rax);
+        a.ret();
+
+        assert_eq!(
+            a.finish(),
+            vec![
+                0x53, // push rbx
+                0x41, 0x57, // push r15
+                0x48, 0x89, 0xfb, // mov rbx, rdi
+                0x4c, 0x8b, 0xa3, 0x08, 0x00, 0x00, 0x00, // mov r12, [rbx+8]
+                0x48, 0x89, 0x8c, 0x24, 0x00, 0x00, 0x00, 0x00, // mov [rsp+0], rcx
+                0x49, 0x0f, 0xb6, 0x84, 0x2c, 0x01, 0x00, 0x00,
+                0x00, // movzx rax, byte [r12+rbp+1]
+                0x41, 0x80, 0xbc, 0x2c, 0x00, 0x00, 0x00, 0x00,
+                0x61, // cmp byte [r12+rbp+0], 0x61
+                0x41, 0xb8, 0x01, 0x00, 0x00, 0x00, // mov r8d, 1
+                0x48, 0xb8, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00,
+                0x00, // mov rax, 0x100000000
+                0x45, 0x31, 0xc0, // xor r8d, r8d
+                0x49, 0x81, 0xf8, 0x03, 0x00, 0x00, 0x00, // cmp r8, 3
+                0x49, 0xff, 0xcf, // dec r15
+                0xff, 0xd0, // call rax
+                0xc3, // ret
+            ]
+        );
+    }
+
+    #[test]
+    fn test_assembler_label() {
+        let mut a = Assembler::new();
+        let start = a.create_label();
+        let end = a.create_label();
+        a.bind(start);
+        a.jcc(Cond::Equal, end); // 6 bytes
+        a.jmp(start); // 5 bytes
+        a.bind(end);
+        a.ret();
+
+        assert_eq!(
+            a.finish(),
+            vec![
+                0x0f, 0x84, 0x05, 0x00, 0x00, 0x00, // je end
+                0xe9, 0xf5, 0xff, 0xff, 0xff, // jmp start
+                0xc3, // ret
+            ]
+        );
+    }
+}
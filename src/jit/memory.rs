@@ -0,0 +1,91 @@
+// Copyright (c) 2025 Hemashushu <hippospark@gmail.com>, All rights reserved.
+//
+// This Source Code Form is subject to the terms of
+// the Mozilla Public License version 2.0 and additional exceptions.
+// For more details, see the LICENSE, LICENSE.additional, and CONTRIBUTING files.
+
+use std::ffi::{c_int, c_long, c_void};
+
+// The functions and constants of the libc on Linux.
+extern "C" {
+    fn mmap(
+        addr: *mut c_void,
+        length: usize,
+        prot: c_int,
+        flags: c_int,
+        fd: c_int,
+        offset: c_long,
+    ) -> *mut c_void;
+    fn mprotect(addr: *mut c_void, length: usize, prot: c_int) -> c_int;
+    fn munmap(addr: *mut c_void, length: usize) -> c_int;
+}
+
+const PROT_READ: c_int = 0x1;
+const PROT_WRITE: c_int = 0x2;
+const PROT_EXEC: c_int = 0x4;
+const MAP_PRIVATE: c_int = 0x02;
+const MAP_ANONYMOUS: c_int = 0x20;
+const MAP_FAILED: *mut c_void = !0 as *mut c_void;
+
+/// A block of executable memory which holds the machine code.
+///
+/// The memory is writable only while the code is being copied in,
+/// and it becomes read-only and executable after that.
+pub struct ExecutableMemory {
+    pointer: *mut u8,
+    length: usize,
+}
+
+// The memory is never modified after creation.
+unsafe impl Send for ExecutableMemory {}
+unsafe impl Sync for ExecutableMemory {}
+
+impl ExecutableMemory {
+    /// Returns `None` if the memory can not be allocated,
+    /// e.g. the executable memory is forbidden by the system.
+    pub fn new(code: &[u8]) -> Option<Self> {
+        // Allocate at least one byte, since zero-length mapping is not allowed.
+        let length = code.len().max(1);
+
+        unsafe {
+            let pointer = mmap(
+                std::ptr::null_mut(),
+                length,
+                PROT_READ | PROT_WRITE,
+                MAP_PRIVATE | MAP_ANONYMOUS,
+                -1,
+                0,
+            );
+
+            if pointer == MAP_FAILED {
+                return None;
+            }
+
+            let memory = ExecutableMemory {
+                pointer: pointer as *mut u8,
+                length,
+            };
+
+            std::ptr::copy_nonoverlapping(code.as_ptr(), memory.pointer, code.len());
+
+            if mprotect(pointer, length, PROT_READ | PROT_EXEC) != 0 {
+                // The memory is released by `drop`.
+                return None;
+            }
+
+            Some(memory)
+        }
+    }
+
+    pub fn as_ptr(&self) -> *const u8 {
+        self.pointer
+    }
+}
+
+impl Drop for ExecutableMemory {
+    fn drop(&mut self) {
+        unsafe {
+            munmap(self.pointer as *mut c_void, self.length);
+        }
+    }
+}
@@ -5,29 +5,47 @@
 // For more details, see the LICENSE, LICENSE.additional, and CONTRIBUTING files.
 
 mod anre;
+mod assembler;
 mod ast;
 mod charwithposition;
 mod compiler;
 mod context;
+mod converter;
+mod dfa;
 mod errorprinter;
+mod graphprinter;
+#[cfg(all(feature = "jit", target_arch = "x86_64", target_os = "linux"))]
+mod jit;
 mod location;
+mod optimizer;
 mod peekableiter;
+mod pikevm;
+mod prefilter;
 mod printer;
 mod rulechecker;
+mod serializer;
 mod traditional;
 mod transition;
 mod utf8reader;
 
+pub mod bytes;
+pub mod explainer;
 pub mod object_file;
 pub mod process;
+pub mod railroad;
 pub mod regex;
+pub mod regexset;
+pub mod tokenizer;
 
-pub use regex::Regex;
+pub use context::Cache;
+pub use converter::{anre_to_regex, regex_to_anre};
+pub use location::Location;
+pub use regex::{Anchored, CaptureLocations, Engine, NoExpand, Regex, RegexBuilder, Replacer};
+pub use regexset::{RegexSet, SetMatches};
+pub use tokenizer::{Token, Tokenizer, TokenizerBuilder};
 
 use std::fmt::{self, Display};
 
-use crate::location::Location;
-
 #[derive(Debug, PartialEq, Clone)]
 pub enum AnreError {
     SyntaxIncorrect(String),
@@ -37,6 +55,10 @@ pub enum AnreError {
     // the last index of the string. For example, the "char incomplete" error
     // raised by a string `'a` has an index of 2.
     MessageWithLocation(String, Location),
+
+    // The data of the object file is corrupted or its version
+    // does not match, see `ObjectFile::from_bytes`.
+    InvalidObjectFile(String),
 }
 
 impl Display for AnreError {
@@ -56,8 +78,31 @@ impl Display for AnreError {
                 )?;
                 write!(f, "{}", detail)
             }
+            AnreError::InvalidObjectFile(msg) => f.write_str(msg),
         }
     }
 }
 
 impl std::error::Error for AnreError {}
+
+/// The error of a matching operation which is aborted by the limits
+/// set by `RegexBuilder::step_limit` and `RegexBuilder::time_limit`.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum MatchError {
+    // The number of executed transitions exceeds the step limit.
+    BudgetExceeded,
+
+    // The matching time exceeds the time limit.
+    Timeout,
+}
+
+impl Display for MatchError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MatchError::BudgetExceeded => f.write_str("The matching step limit is exceeded."),
+            MatchError::Timeout => f.write_str("The matching time limit is exceeded."),
+        }
+    }
+}
+
+impl std::error::Error for MatchError {}
@@ -4,7 +4,9 @@
 // the Mozilla Public License version 2.0 and additional exceptions.
 // For more details, see the LICENSE, LICENSE.additional, and CONTRIBUTING files.
 
-use crate::transition::Transition;
+use crate::{
+    assembler, graphprinter, prefilter::Prefilter, serializer, transition::Transition, AnreError,
+};
 
 pub const MAIN_ROUTE_INDEX: usize = 0;
 
@@ -38,6 +40,23 @@ pub struct ObjectFile {
 
     // The names of capture groups.
     pub capture_group_names: Vec<Option<String>>,
+
+    // The literals and the first bytes of the matches, which are used to
+    // skip the positions where a match can not start, see `prefilter.rs`.
+    pub(crate) prefilter: Prefilter,
+
+    // The numbers of nodes and transitions before and after the optimization,
+    // it is `None` if the object file is not optimized, see `optimizer.rs`.
+    pub optimization_stats: Option<OptimizationStats>,
+}
+
+// The numbers of nodes and transitions of all routes.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct OptimizationStats {
+    pub nodes_before: usize,
+    pub nodes_after: usize,
+    pub transitions_before: usize,
+    pub transitions_after: usize,
 }
 
 // A series of nodes.
@@ -58,6 +77,10 @@ pub struct Route {
     // the process will not move forward by one character to try matching again.
     // This is also true for "lookahead assertions" and "lookbehind assertions."
     pub is_fixed_start_position: bool,
+
+    // True if the match must end at the end of the text range,
+    // it is set by the option `RegexBuilder::anchored` instead of the expression.
+    pub is_fixed_end_position: bool,
 }
 
 // A route consists of multiple nodes.
@@ -83,6 +106,8 @@ impl ObjectFile {
         ObjectFile {
             routes: vec![],
             capture_group_names: vec![],
+            prefilter: Prefilter::default(),
+            optimization_stats: None,
         }
     }
 
@@ -93,6 +118,7 @@ impl ObjectFile {
             start_node_index: 0,
             end_node_index: 0,
             is_fixed_start_position: false,
+            is_fixed_end_position: false,
         };
 
         let idx = self.routes.len();
@@ -125,6 +151,124 @@ impl ObjectFile {
         }
     }
 
+    // Checks whether any route contains back references.
+    pub fn has_back_reference(&self) -> bool {
+        self.routes
+            .iter()
+            .flat_map(|route| &route.nodes)
+            .flat_map(|node| &node.transition_items)
+            .any(|item| matches!(item.transition, Transition::BackReference(_)))
+    }
+
+    // Checks whether any route contains transitions which match
+    // a single byte instead of a char, i.e. the "char_any" in the byte mode.
+    pub fn has_byte_transition(&self) -> bool {
+        self.routes
+            .iter()
+            .flat_map(|route| &route.nodes)
+            .flat_map(|node| &node.transition_items)
+            .any(|item| {
+                matches!(&item.transition, Transition::SpecialChar(transition) if transition.matches_any_byte)
+            })
+    }
+
+    // Finds a route which runs itself by lookaround assertions, directly
+    // or through other routes, the engines would recurse endlessly on it.
+    //
+    // The route indices of the lookaround assertions should be checked
+    // before calling this function.
+    pub fn find_recursive_route(&self) -> Option<usize> {
+        // The routes run by the lookaround assertions of each route.
+        let callees: Vec<Vec<usize>> = self
+            .routes
+            .iter()
+            .map(|route| {
+                route
+                    .nodes
+                    .iter()
+                    .flat_map(|node| &node.transition_items)
+                    .filter_map(|item| match &item.transition {
+                        Transition::LookAheadAssertion(transition) => Some(transition.route_index),
+                        Transition::LookBehindAssertion(transition) => Some(transition.route_index),
+                        _ => None,
+                    })
+                    .collect()
+            })
+            .collect();
+
+        // A depth-first search with an explicit stack, a route which is
+        // still on the stack when it is reached again is in a cycle.
+        let mut is_on_stack = vec![false; self.routes.len()];
+        let mut is_finished = vec![false; self.routes.len()];
+
+        for root_index in 0..self.routes.len() {
+            if is_finished[root_index] {
+                continue;
+            }
+
+            // (route index, index of the next callee)
+            let mut stack = vec![(root_index, 0)];
+            is_on_stack[root_index] = true;
+
+            while let Some(&(route_index, callee_offset)) = stack.last() {
+                let Some(&callee_index) = callees[route_index].get(callee_offset) else {
+                    is_on_stack[route_index] = false;
+                    is_finished[route_index] = true;
+                    stack.pop();
+                    continue;
+                };
+
+                stack.last_mut().unwrap().1 += 1;
+
+                if is_on_stack[callee_index] {
+                    return Some(callee_index);
+                }
+
+                if !is_finished[callee_index] {
+                    is_on_stack[callee_index] = true;
+                    stack.push((callee_index, 0));
+                }
+            }
+        }
+
+        None
+    }
+
+    /// Serializes the object file to bytes in a versioned binary format,
+    /// so that the expression can be compiled ahead of time.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        serializer::serialize(self)
+    }
+
+    /// Deserializes the object file from the bytes generated by `to_bytes`,
+    /// the result can be used by `Regex::from_object_file`.
+    ///
+    /// Returns `AnreError::InvalidObjectFile` if the data is corrupted or
+    /// is generated by another version of the format.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, AnreError> {
+        serializer::deserialize(bytes)
+    }
+
+    /// Parses the listing generated by `get_debug_text` and rebuilds the object file,
+    /// it is used to write or patch small automata by hand, e.g. for testing engines.
+    ///
+    /// Returns `AnreError::MessageWithLocation` if the listing is malformed.
+    pub fn from_debug_text(text: &str) -> Result<Self, AnreError> {
+        assembler::assemble(text)
+    }
+
+    /// Generates the Graphviz DOT text of the routes, e.g. for rendering
+    /// with `dot -Tsvg`. Each route is drawn as a cluster, and the lookaround
+    /// assertions are linked to the clusters of their sub-routes.
+    pub fn to_dot(&self) -> String {
+        graphprinter::to_dot(self)
+    }
+
+    /// Generates the Mermaid flowchart text of the routes, see `to_dot`.
+    pub fn to_mermaid(&self) -> String {
+        graphprinter::to_mermaid(self)
+    }
+
     // For debugging: generates a textual representation of the object file.
     pub fn get_debug_text(&self) -> String {
         let mut ss = vec![];
@@ -151,6 +295,17 @@ impl ObjectFile {
             ss.push(s);
         }
 
+        // Optimization stats
+        if let Some(stats) = &self.optimization_stats {
+            ss.push(format!(
+                "* optimized: nodes {} -> {}, transitions {} -> {}",
+                stats.nodes_before,
+                stats.nodes_after,
+                stats.transitions_before,
+                stats.transitions_after
+            ));
+        }
+
         ss.join("\n")
     }
 }
@@ -208,7 +363,14 @@ impl Route {
                 '-'
             };
 
-            let s = format!("{} {}", prefix, node_index);
+            // The anchoring of the route is shown after the entry or exit node.
+            let mut s = format!("{} {}", prefix, node_index);
+            if node_index == self.start_node_index && self.is_fixed_start_position {
+                s.push_str(", fixed start");
+            }
+            if node_index == self.end_node_index && self.is_fixed_end_position {
+                s.push_str(", fixed end");
+            }
             ss.push(s);
 
             // Transition items
@@ -0,0 +1,357 @@
+// Copyright (c) 2025 Hemashushu <hippospark@gmail.com>, All rights reserved.
+//
+// This Source Code Form is subject to the terms of
+// the Mozilla Public License version 2.0 and additional exceptions.
+// For more details, see the LICENSE, LICENSE.additional, and CONTRIBUTING files.
+
+use crate::{
+    object_file::{ObjectFile, OptimizationStats, Route},
+    prefilter::Prefilter,
+    transition::{CharRange, CharSetItem, CharSetTransition, StringTransition, Transition},
+};
+
+/// Optimizes the object file generated by the compiler.
+///
+/// The following passes are run on each route:
+///
+/// 1. The nodes which have only a jump transition are skipped, i.e.
+///    the transitions to them go to the targets of their jumps directly.
+/// 2. The adjacent char and string transitions are fused into one string transition.
+/// 3. The nodes which become unreachable after the above passes are removed.
+/// 4. The items of char sets are sorted and merged, and the bitmaps of
+///    the ASCII chars are built.
+///
+/// The prefilter is rebuilt since the transitions are changed.
+pub fn optimize(object_file: &mut ObjectFile) {
+    let (nodes_before, transitions_before) = count_nodes_and_transitions(object_file);
+
+    for route in &mut object_file.routes {
+        skip_jump_nodes(route);
+        remove_unreachable_nodes(route);
+        fuse_strings(route);
+        remove_unreachable_nodes(route);
+        compact_char_sets(route);
+    }
+
+    let (nodes_after, transitions_after) = count_nodes_and_transitions(object_file);
+    object_file.optimization_stats = Some(OptimizationStats {
+        nodes_before,
+        nodes_after,
+        transitions_before,
+        transitions_after,
+    });
+
+    object_file.prefilter = Prefilter::new(object_file);
+}
+
+fn count_nodes_and_transitions(object_file: &ObjectFile) -> (usize, usize) {
+    let nodes = object_file.routes.iter().flat_map(|route| &route.nodes);
+    let number_of_nodes = nodes.clone().count();
+    let number_of_transitions = nodes.map(|node| node.transition_items.len()).sum();
+    (number_of_nodes, number_of_transitions)
+}
+
+// Returns the target of the node if the node has only a jump transition.
+fn get_jump_target(route: &Route, node_index: usize) -> Option<usize> {
+    match route.nodes[node_index].transition_items.as_slice() {
+        [item] if matches!(item.transition, Transition::Jump(_)) => Some(item.target_node_index),
+        _ => None,
+    }
+}
+
+// Follows the jump-only nodes and returns the first node which is not jump-only,
+// the node itself is returned if the jumps form a loop.
+fn resolve_jumps(route: &Route, node_index: usize) -> usize {
+    let mut current_node_index = node_index;
+    for _ in 0..route.nodes.len() {
+        match get_jump_target(route, current_node_index) {
+            Some(target_node_index) => current_node_index = target_node_index,
+            None => return current_node_index,
+        }
+    }
+    node_index
+}
+
+fn skip_jump_nodes(route: &mut Route) {
+    let targets: Vec<usize> = (0..route.nodes.len())
+        .map(|node_index| resolve_jumps(route, node_index))
+        .collect();
+
+    for node in &mut route.nodes {
+        for item in &mut node.transition_items {
+            // The jump transition resets the repetition count, so the transitions
+            // which pass the count to the next node must still go through the jump.
+            let passes_repetition_count = matches!(
+                item.transition,
+                Transition::CounterInc(_) | Transition::CounterCheck(_) | Transition::Repetition(_)
+            );
+
+            if !passes_repetition_count {
+                item.target_node_index = targets[item.target_node_index];
+            }
+        }
+    }
+
+    // The backtracking engine succeeds only when a transition
+    // reaches the exit node, so the entry node can not be the exit node.
+    let start_node_index = targets[route.start_node_index];
+    if start_node_index != route.end_node_index {
+        route.start_node_index = start_node_index;
+    }
+}
+
+// Returns the codepoints of the char or string transition.
+fn get_literal(transition: &Transition) -> Option<(Vec<u32>, bool)> {
+    match transition {
+        Transition::Char(transition) => Some((vec![transition.codepoint], transition.ignore_case)),
+        Transition::String(transition) => {
+            Some((transition.codepoints.clone(), transition.ignore_case))
+        }
+        _ => None,
+    }
+}
+
+// Fuses `P --literal--> N --literal--> T` into `P --string--> T`
+// if `N` is reached only from `P` and has only one transition.
+fn fuse_strings(route: &mut Route) {
+    let mut in_degrees = vec![0; route.nodes.len()];
+    for node in &route.nodes {
+        for item in &node.transition_items {
+            in_degrees[item.target_node_index] += 1;
+        }
+    }
+
+    for node_index in 0..route.nodes.len() {
+        for item_index in 0..route.nodes[node_index].transition_items.len() {
+            loop {
+                let item = &route.nodes[node_index].transition_items[item_index];
+                let next_node_index = item.target_node_index;
+
+                if next_node_index == node_index
+                    || next_node_index == route.start_node_index
+                    || next_node_index == route.end_node_index
+                    || in_degrees[next_node_index] != 1
+                {
+                    break;
+                }
+
+                let next_items = &route.nodes[next_node_index].transition_items;
+                if next_items.len() != 1 {
+                    break;
+                }
+
+                let (
+                    Some((mut codepoints, ignore_case)),
+                    Some((next_codepoints, next_ignore_case)),
+                ) = (
+                    get_literal(&item.transition),
+                    get_literal(&next_items[0].transition),
+                )
+                else {
+                    break;
+                };
+
+                if ignore_case != next_ignore_case {
+                    break;
+                }
+
+                codepoints.extend(next_codepoints);
+                let s: String = codepoints
+                    .iter()
+                    .map(|codepoint| unsafe { char::from_u32_unchecked(*codepoint) })
+                    .collect();
+
+                let transition = if ignore_case {
+                    StringTransition::new_ignore_case(&s)
+                } else {
+                    StringTransition::new(&s)
+                };
+
+                // The next node becomes unreachable, it is removed later.
+                let next_item = route.nodes[next_node_index].transition_items.remove(0);
+                in_degrees[next_node_index] = 0;
+
+                let item = &mut route.nodes[node_index].transition_items[item_index];
+                item.transition = Transition::String(transition);
+                item.target_node_index = next_item.target_node_index;
+            }
+        }
+    }
+}
+
+fn remove_unreachable_nodes(route: &mut Route) {
+    let mut reachable = vec![false; route.nodes.len()];
+
+    let mut pending = vec![route.start_node_index];
+    while let Some(node_index) = pending.pop() {
+        if reachable[node_index] {
+            continue;
+        }
+        reachable[node_index] = true;
+
+        for item in &route.nodes[node_index].transition_items {
+            if !reachable[item.target_node_index] {
+                pending.push(item.target_node_index);
+            }
+        }
+    }
+
+    // The exit node is kept even if the route can never match.
+    reachable[route.end_node_index] = true;
+
+    // The new indices of the nodes, the order of the nodes is kept.
+    let mut new_indices = vec![0; route.nodes.len()];
+    let mut number_of_nodes = 0;
+    for (node_index, is_reachable) in reachable.iter().enumerate() {
+        if *is_reachable {
+            new_indices[node_index] = number_of_nodes;
+            number_of_nodes += 1;
+        }
+    }
+
+    let nodes = std::mem::take(&mut route.nodes);
+    route.nodes = nodes
+        .into_iter()
+        .zip(reachable)
+        .filter(|(_, is_reachable)| *is_reachable)
+        .map(|(mut node, _)| {
+            for item in &mut node.transition_items {
+                item.target_node_index = new_indices[item.target_node_index];
+            }
+            node
+        })
+        .collect();
+
+    route.start_node_index = new_indices[route.start_node_index];
+    route.end_node_index = new_indices[route.end_node_index];
+}
+
+// Sorts and merges the items of the char sets, and builds the bitmaps
+// of the ASCII chars.
+//
+// The char sets which ignore case are not changed, since the chars and
+// the ranges are compared with different case folding.
+fn compact_char_sets(route: &mut Route) {
+    for node in &mut route.nodes {
+        for item in &mut node.transition_items {
+            if let Transition::CharSet(transition) = &mut item.transition {
+                if !transition.ignore_case {
+                    compact_char_set(transition);
+                }
+            }
+        }
+    }
+}
+
+pub fn compact_char_set(transition: &mut CharSetTransition) {
+    let mut ranges: Vec<(u32, u32)> = transition
+        .items
+        .iter()
+        .map(|item| match item {
+            CharSetItem::Char(c) => (*c, *c),
+            CharSetItem::Range(r) => (r.start, r.end_included),
+        })
+        .collect();
+    ranges.sort_unstable();
+
+    // Merge the overlapping and adjacent ranges.
+    let mut merged_ranges: Vec<(u32, u32)> = vec![];
+    for (start, end_included) in ranges {
+        match merged_ranges.last_mut() {
+            Some(last) if start <= last.1.saturating_add(1) => {
+                last.1 = last.1.max(end_included);
+            }
+            _ => merged_ranges.push((start, end_included)),
+        }
+    }
+
+    let mut bitmap = [0u64; 2];
+    for (start, end_included) in &merged_ranges {
+        for c in *start..=(*end_included).min(127) {
+            bitmap[(c >> 6) as usize] |= 1 << (c & 63);
+        }
+    }
+
+    transition.items = merged_ranges
+        .into_iter()
+        .map(|(start, end_included)| {
+            if start == end_included {
+                CharSetItem::Char(start)
+            } else {
+                CharSetItem::Range(CharRange {
+                    start,
+                    end_included,
+                })
+            }
+        })
+        .collect();
+    transition.ascii_bitmap = Some(bitmap);
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_str_eq;
+
+    use crate::{compiler::compile_from_anre, object_file::ObjectFile};
+
+    use super::optimize;
+
+    fn optimize_anre(expression: &str) -> ObjectFile {
+        let mut object_file = compile_from_anre(expression).unwrap();
+        optimize(&mut object_file);
+        object_file
+    }
+
+    #[test]
+    fn test_optimize_jumps_and_strings() {
+        assert_str_eq!(
+            optimize_anre(r#"'a', 'b', 'c'"#).get_debug_text(),
+            "\
+- 0
+  -> 1, String \"abc\"
+- 1
+  -> 3, Capture end {0}
+> 2
+  -> 0, Capture start {0}
+< 3
+# {0}
+* optimized: nodes 8 -> 4, transitions 7 -> 3"
+        );
+
+        assert_str_eq!(
+            optimize_anre(r#"'a' || 'b'"#).get_debug_text(),
+            "\
+- 0
+  -> 3, Char 'a'
+- 1
+  -> 3, Char 'b'
+- 2
+  -> 0, Jump
+  -> 1, Jump
+- 3
+  -> 5, Capture end {0}
+> 4
+  -> 2, Capture start {0}
+< 5
+# {0}
+* optimized: nodes 8 -> 6, transitions 8 -> 6"
+        );
+    }
+
+    #[test]
+    fn test_optimize_char_set() {
+        assert_str_eq!(
+            optimize_anre(r#"['x', 'a'..'f', 'c'..'k', 'l', '0'..'9', '文']"#).get_debug_text(),
+            "\
+- 0
+  -> 1, Charset ['0'..'9', 'a'..'l', 'x', '文']
+- 1
+  -> 3, Capture end {0}
+> 2
+  -> 0, Capture start {0}
+< 3
+# {0}
+* optimized: nodes 4 -> 4, transitions 3 -> 3"
+        );
+    }
+}
@@ -0,0 +1,730 @@
+// Copyright (c) 2025 Hemashushu <hippospark@gmail.com>, All rights reserved.
+//
+// This Source Code Form is subject to the terms of
+// the Mozilla Public License version 2.0 and additional exceptions.
+// For more details, see the LICENSE, LICENSE.additional, and CONTRIBUTING files.
+
+use std::collections::HashSet;
+
+use crate::{
+    context::{Context, MatchRange, Routine},
+    object_file::{ObjectFile, Route, MAIN_ROUTE_INDEX},
+    regex::Anchored,
+    transition::{
+        fold_case, get_position_by_chars_backward, ExecuteResult, RepetitionType, Transition,
+    },
+    utf8reader::read_char,
+};
+
+// The Pike VM executes the same routes as the backtracking process (see `process.rs`),
+// but instead of trying the transitions one path after another, it moves all
+// possible paths (threads) forward one character at a time, in lockstep.
+//
+// ```diagram
+//  position:        0     1     2     3
+//                   |     |     |     |
+//  thread (high) ---o-----o-----o-----o---> ...
+//  thread        ---o-----o--x  |     |      (failed)
+//  thread (low)  ---------o-----o-----o---> ...
+// ```
+//
+// Threads that arrive at the same state (node, counters) at the same position
+// are merged, only the thread with the highest priority is kept, so
+// the number of threads at each position never exceeds the number of states,
+// and the matching time is O(n*m) (n is the length of the text and m is
+// the number of states) instead of exponential.
+//
+// A lookaround assertion runs its route as a sub-routine. The result is
+// cached for each position, so the route runs at most once at each position,
+// but it may scan the rest of the text each time (e.g. `(?=\w*x)`), so
+// the matching time of an expression with lookarounds is O(n^2*m) in the worst case.
+//
+// The threads are kept in priority order, which is the order in which
+// the backtracking process would try them, so the results are the same
+// as the backtracking process (i.e. the leftmost-first match).
+//
+// Back references are not supported, because the text matched by
+// a back reference depends on the capture groups of each thread,
+// which breaks the merging of threads.
+
+/// Start a new process with the Pike VM.
+///
+/// The result is written to `context.match_ranges`, the same as
+/// the backtracking process (`process::start_process`).
+pub fn start_process(
+    context: &mut Context,
+    object_file: &ObjectFile,
+    start_position: usize,
+    end_position: usize,
+    anchored: Anchored,
+) -> bool {
+    // Each thread has its own capture groups.
+    let match_ranges = vec![MatchRange::default(); context.match_ranges.len()];
+
+    if let Some(match_ranges) = start_routine(
+        context,
+        object_file,
+        MAIN_ROUTE_INDEX,
+        start_position,
+        end_position,
+        match_ranges,
+        anchored,
+    ) {
+        // The result is discarded if the budget is exceeded.
+        if context.budget.is_exceeded() {
+            return false;
+        }
+
+        context.match_ranges = match_ranges;
+        true
+    } else {
+        false
+    }
+}
+
+/// Start a new process for a set of expressions with the Pike VM.
+///
+/// The main route contains one branch for each expression, and
+/// `exit_node_indices` are the exit nodes of the branches (see `RegexSet`).
+/// Unlike `start_process`, the process does not stop at the first match,
+/// all the branches are moved forward in a single scan of the text,
+/// until every expression has matched or the end of the text is reached.
+///
+/// Returns whether each expression matches.
+pub fn start_set_process(
+    context: &mut Context,
+    object_file: &ObjectFile,
+    exit_node_indices: &[usize],
+) -> Vec<bool> {
+    let route = &object_file.routes[MAIN_ROUTE_INDEX];
+    let end_position = context.bytes.len();
+
+    // The index of the expression of each exit node.
+    let mut expression_indices = vec![None; route.nodes.len()];
+    for (expression_index, node_index) in exit_node_indices.iter().enumerate() {
+        expression_indices[*node_index] = Some(expression_index);
+    }
+
+    let mut matched = vec![false; exit_node_indices.len()];
+    let mut remaining = exit_node_indices.len();
+
+    // The capture groups are not reported.
+    let match_ranges = vec![MatchRange::default(); context.match_ranges.len()];
+
+    context
+        .routines
+        .push(Routine::new(0, end_position, MAIN_ROUTE_INDEX));
+
+    let mut current_threads = ThreadList::new();
+    let mut position = 0;
+
+    while remaining > 0 {
+        // Start a new thread at each position, unless all the expressions start with "^...".
+        if position == 0 || !route.is_fixed_start_position {
+            let thread = Thread::new(route.start_node_index, match_ranges.clone());
+            add_thread(
+                context,
+                object_file,
+                MAIN_ROUTE_INDEX,
+                &mut current_threads,
+                thread,
+                position,
+            );
+        }
+
+        let mut next_threads = ThreadList::new();
+        let next_position = if position < end_position {
+            let (_, byte_length) = read_char(context.bytes, position);
+            position + byte_length
+        } else {
+            position
+        };
+
+        for thread in current_threads.threads {
+            if !context.budget.take_step() {
+                break;
+            }
+
+            if thread.transition_index == NODE_ENTRY {
+                // The thread stops at an exit node.
+                if let Some(expression_index) = expression_indices[thread.node_index] {
+                    if !matched[expression_index] {
+                        matched[expression_index] = true;
+                        remaining -= 1;
+                    }
+                }
+                continue;
+            }
+
+            if position >= end_position {
+                continue;
+            }
+
+            step_thread(
+                context,
+                object_file,
+                MAIN_ROUTE_INDEX,
+                &mut next_threads,
+                thread,
+                position,
+                next_position,
+            );
+        }
+
+        if position >= end_position
+            || context.budget.is_exceeded()
+            || (next_threads.threads.is_empty() && route.is_fixed_start_position)
+        {
+            break;
+        }
+
+        current_threads = next_threads;
+        position = next_position;
+    }
+
+    context.routines.pop();
+    matched
+}
+
+/// Start a routine.
+///
+/// Returns the capture groups of the thread with the highest priority
+/// if the route matches.
+///
+/// The `anchored` parameter adds the anchoring to the anchoring of the route,
+/// it is `Anchored::No` for sub-routines.
+fn start_routine(
+    context: &mut Context,
+    object_file: &ObjectFile,
+    route_index: usize,
+    start_position: usize, // Start position of the text range (inclusive).
+    end_position: usize,   // End position of the text range (exclusive).
+    match_ranges: Vec<MatchRange>,
+    anchored: Anchored,
+) -> Option<Vec<MatchRange>> {
+    // The transitions (e.g. `Transition::Char`) read
+    // the end position of the text range from the current routine.
+    context
+        .routines
+        .push(Routine::new(start_position, end_position, route_index));
+
+    let result = execute_threads(
+        context,
+        object_file,
+        route_index,
+        start_position,
+        end_position,
+        match_ranges,
+        anchored,
+    );
+
+    context.routines.pop();
+    result
+}
+
+fn execute_threads(
+    context: &mut Context,
+    object_file: &ObjectFile,
+    route_index: usize,
+    start_position: usize,
+    end_position: usize,
+    match_ranges: Vec<MatchRange>,
+    anchored: Anchored,
+) -> Option<Vec<MatchRange>> {
+    let route = &object_file.routes[route_index];
+    let is_fixed_start_position =
+        route.is_fixed_start_position || anchored.is_fixed_start_position();
+    let is_fixed_end_position = route.is_fixed_end_position || anchored.is_fixed_end_position();
+
+    // The route of a lookaround assertion which sets no capture groups
+    // only needs to know whether it matches, not which thread matches.
+    let stops_at_first_match = route_index != MAIN_ROUTE_INDEX && !sets_capture_group(route);
+
+    let use_prefilter = route_index == MAIN_ROUTE_INDEX && !is_fixed_start_position;
+    if use_prefilter
+        && !object_file
+            .prefilter
+            .may_match(context.bytes, start_position, end_position)
+    {
+        return None;
+    }
+
+    let mut current_threads = ThreadList::new();
+    let mut matched: Option<Vec<MatchRange>> = None;
+    let mut position = start_position;
+
+    loop {
+        // The main route skips the positions where a match can not start,
+        // if there is no running thread.
+        if use_prefilter && matched.is_none() && current_threads.threads.is_empty() {
+            match object_file
+                .prefilter
+                .next_candidate(context.bytes, position, end_position)
+            {
+                Some(candidate) => {
+                    // The visited states belong to the old position.
+                    current_threads = ThreadList::new();
+                    position = candidate;
+                }
+                None => break,
+            }
+        }
+
+        // Start a new thread at the current position, it has the lowest priority.
+        //
+        // Like the backtracking process, the start position moves forward only if
+        // there is no match yet, and the expression does not start with "^...".
+        // The end position is also tried, since the expression may match an empty string.
+        if matched.is_none()
+            && position <= end_position
+            && (position == start_position || !is_fixed_start_position)
+        {
+            let thread = Thread::new(route.start_node_index, match_ranges.clone());
+            add_thread(
+                context,
+                object_file,
+                route_index,
+                &mut current_threads,
+                thread,
+                position,
+            );
+        }
+
+        if current_threads.threads.is_empty()
+            && (matched.is_some() || is_fixed_start_position || position >= end_position)
+        {
+            break;
+        }
+
+        let mut next_threads = ThreadList::new();
+        let next_position = if position < end_position {
+            let (_, byte_length) = read_char(context.bytes, position);
+            position + byte_length
+        } else {
+            position
+        };
+
+        for thread in current_threads.threads {
+            // Each stepped thread takes a step of the budget.
+            if !context.budget.take_step() {
+                return None;
+            }
+
+            if thread.node_index == route.end_node_index {
+                if is_fixed_end_position && position != end_position {
+                    // The match does not reach the end of the range.
+                    continue;
+                }
+
+                // The threads after this one have lower priority, discard them.
+                matched = Some(thread.match_ranges);
+                break;
+            }
+
+            if position >= end_position {
+                continue;
+            }
+
+            step_thread(
+                context,
+                object_file,
+                route_index,
+                &mut next_threads,
+                thread,
+                position,
+                next_position,
+            );
+        }
+
+        if position >= end_position || (matched.is_some() && stops_at_first_match) {
+            break;
+        }
+
+        current_threads = next_threads;
+        position = next_position;
+    }
+
+    matched
+}
+
+// Checks whether the route sets capture groups, including
+// the routes of the positive lookaround assertions inside it.
+fn sets_capture_group(route: &Route) -> bool {
+    route
+        .nodes
+        .iter()
+        .flat_map(|node| &node.transition_items)
+        .any(|item| match &item.transition {
+            Transition::CaptureStart(_) | Transition::CaptureEnd(_) => true,
+            Transition::LookAheadAssertion(transition) => !transition.negative,
+            Transition::LookBehindAssertion(transition) => !transition.negative,
+            _ => false,
+        })
+}
+
+// Executes the consuming transition the thread stops at, and adds
+// the following threads to the list of the next position.
+fn step_thread(
+    context: &mut Context,
+    object_file: &ObjectFile,
+    route_index: usize,
+    next_threads: &mut ThreadList,
+    thread: Thread,
+    position: usize,
+    next_position: usize,
+) {
+    let route = &object_file.routes[route_index];
+    let transition_item = &route.nodes[thread.node_index].transition_items[thread.transition_index];
+    let (current_char, _) = read_char(context.bytes, position);
+
+    let is_completed = match &transition_item.transition {
+        Transition::String(transition) => {
+            let expected_char = transition.codepoints[thread.char_offset];
+            let is_same = if transition.ignore_case {
+                fold_case(expected_char) == fold_case(current_char)
+            } else {
+                expected_char == current_char
+            };
+
+            if !is_same {
+                return;
+            }
+
+            if thread.char_offset + 1 < transition.codepoints.len() {
+                // Stay in the string transition and check the next char.
+                let next_thread = Thread {
+                    char_offset: thread.char_offset + 1,
+                    ..thread
+                };
+                if next_threads.visit(&next_thread) {
+                    next_threads.threads.push(next_thread);
+                }
+                return;
+            }
+
+            true
+        }
+        Transition::Trie(transition) => {
+            // The `char_offset` holds the index of the current trie node.
+            let Some(node_index) = transition.step(thread.char_offset, current_char) else {
+                return;
+            };
+
+            if !transition.is_terminal(node_index) {
+                // Stay in the trie transition and check the next char.
+                let next_thread = Thread {
+                    char_offset: node_index,
+                    ..thread
+                };
+                if next_threads.visit(&next_thread) {
+                    next_threads.threads.push(next_thread);
+                }
+                return;
+            }
+
+            true
+        }
+        transition => matches!(
+            transition.execute(context, object_file, position, thread.repetition_count),
+            ExecuteResult::Success(_, _)
+        ),
+    };
+
+    if is_completed {
+        let next_thread = Thread {
+            node_index: transition_item.target_node_index,
+            transition_index: NODE_ENTRY,
+            char_offset: 0,
+            repetition_count: 0,
+            ..thread
+        };
+        add_thread(
+            context,
+            object_file,
+            route_index,
+            next_threads,
+            next_thread,
+            next_position,
+        );
+    }
+}
+
+// Follows all the transitions which do not consume characters (e.g. jumps,
+// assertions and capture groups) from the node of the specified thread,
+// and adds the threads that stop at a consuming transition (or at the exit node)
+// to the list, in priority order.
+fn add_thread(
+    context: &mut Context,
+    object_file: &ObjectFile,
+    route_index: usize,
+    thread_list: &mut ThreadList,
+    thread: Thread,
+    position: usize,
+) {
+    let route = &object_file.routes[route_index];
+
+    // A stack is used instead of recursion, the following threads are
+    // pushed in reverse order, since the stack pops the last element first.
+    let mut stack = vec![thread];
+
+    while let Some(thread) = stack.pop() {
+        if !thread_list.visit(&thread) {
+            continue;
+        }
+
+        let node = &route.nodes[thread.node_index];
+
+        // The exit nodes of the branches of a set (see `start_set_process`)
+        // have no transitions either.
+        if thread.transition_index != NODE_ENTRY
+            || thread.node_index == route.end_node_index
+            || node.transition_items.is_empty()
+        {
+            // The thread stops at a consuming transition or at an exit node.
+            thread_list.threads.push(thread);
+            continue;
+        }
+
+        let mut following_threads = vec![];
+
+        for (transition_index, transition_item) in node.transition_items.iter().enumerate() {
+            let mut next_thread = Thread {
+                node_index: transition_item.target_node_index,
+                transition_index: NODE_ENTRY,
+                char_offset: 0,
+                repetition_count: 0,
+                counter_stack: thread.counter_stack.clone(),
+                match_ranges: thread.match_ranges.clone(),
+            };
+
+            match &transition_item.transition {
+                Transition::Char(_)
+                | Transition::SpecialChar(_)
+                | Transition::String(_)
+                | Transition::CharSet(_)
+                | Transition::Trie(_) => {
+                    // Consuming transitions are executed in the next step.
+                    next_thread.node_index = thread.node_index;
+                    next_thread.transition_index = transition_index;
+                    next_thread.repetition_count = thread.repetition_count;
+                }
+                Transition::BackReference(_) => {
+                    // Back references are not supported.
+                    continue;
+                }
+                Transition::CaptureStart(transition) => {
+                    next_thread.match_ranges[transition.capture_group_index].start = position;
+                }
+                Transition::CaptureEnd(transition) => {
+                    next_thread.match_ranges[transition.capture_group_index].end = position;
+                }
+                Transition::CounterSave(_) => {
+                    next_thread.counter_stack.push(thread.repetition_count);
+                }
+                Transition::CounterInc(_) => {
+                    let last_count = next_thread.counter_stack.pop().unwrap();
+                    next_thread.repetition_count = last_count + 1;
+                }
+                Transition::LookAheadAssertion(transition) => {
+                    let result = check_lookaround(
+                        context,
+                        object_file,
+                        transition.route_index,
+                        position,
+                        &next_thread.match_ranges,
+                    );
+
+                    match (result, transition.negative) {
+                        (Some(match_ranges), false) => next_thread.match_ranges = match_ranges,
+                        (None, true) => {}
+                        _ => continue,
+                    }
+                }
+                Transition::LookBehindAssertion(transition) => {
+                    let result = if let Ok(start) = get_position_by_chars_backward(
+                        context.bytes,
+                        position,
+                        transition.match_length_in_char,
+                    ) {
+                        check_lookaround(
+                            context,
+                            object_file,
+                            transition.route_index,
+                            start,
+                            &next_thread.match_ranges,
+                        )
+                    } else {
+                        None
+                    };
+
+                    match (result, transition.negative) {
+                        (Some(match_ranges), false) => next_thread.match_ranges = match_ranges,
+                        (None, true) => {}
+                        _ => continue,
+                    }
+                }
+                transition => {
+                    // Jumps, anchor assertions, boundary assertions and
+                    // the transitions for checking the counter.
+                    match transition.execute(
+                        context,
+                        object_file,
+                        position,
+                        thread.repetition_count,
+                    ) {
+                        ExecuteResult::Success(_, repetition_count) => {
+                            next_thread.repetition_count =
+                                saturate_repetition_count(transition, repetition_count);
+                        }
+                        ExecuteResult::Failure => continue,
+                    }
+                }
+            }
+
+            following_threads.push(next_thread);
+        }
+
+        stack.extend(following_threads.into_iter().rev());
+    }
+}
+
+// Runs the route of a lookaround assertion from the specified position
+// to the end of the text, and returns the capture groups of the thread
+// if the route matches.
+//
+// The result of a route depends only on the start position (there are
+// no back references), so it is computed once and cached in the context,
+// otherwise every thread arriving at the assertion would run the route again.
+// The cached result is computed with unset capture groups, only the
+// groups which are set by the route overwrite the groups of the thread.
+fn check_lookaround(
+    context: &mut Context,
+    object_file: &ObjectFile,
+    route_index: usize,
+    start_position: usize,
+    match_ranges: &[MatchRange],
+) -> Option<Vec<MatchRange>> {
+    let key = (route_index, start_position);
+
+    if !context.lookaround_results.contains_key(&key) {
+        let end = context.bytes.len();
+        let unset_match_ranges = vec![MatchRange::new(UNSET, UNSET); match_ranges.len()];
+        let result = start_routine(
+            context,
+            object_file,
+            route_index,
+            start_position,
+            end,
+            unset_match_ranges,
+            Anchored::No,
+        );
+
+        // The result is incomplete if the budget is exceeded.
+        if context.budget.is_exceeded() {
+            return None;
+        }
+
+        context.lookaround_results.insert(key, result);
+    }
+
+    context.lookaround_results[&key]
+        .as_ref()
+        .map(|route_match_ranges| {
+            match_ranges
+                .iter()
+                .zip(route_match_ranges)
+                .map(|(thread_range, route_range)| MatchRange {
+                    start: if route_range.start == UNSET {
+                        thread_range.start
+                    } else {
+                        route_range.start
+                    },
+                    end: if route_range.end == UNSET {
+                        thread_range.end
+                    } else {
+                        route_range.end
+                    },
+                })
+                .collect()
+        })
+}
+
+// The position of a capture group which is not set by the route of a lookaround.
+const UNSET: usize = usize::MAX;
+
+// For the repetition without upper limit (e.g. `{3,}`), all the counts
+// greater than the lower limit have the same behavior, so they are
+// treated as the same state, otherwise the threads of an expression
+// which matches empty string (e.g. `(a?){3,}`) could never be merged.
+pub(crate) fn saturate_repetition_count(transition: &Transition, repetition_count: usize) -> usize {
+    let repetition_type = match transition {
+        Transition::CounterCheck(transition) => &transition.repetition_type,
+        Transition::Repetition(transition) => &transition.repetition_type,
+        _ => return repetition_count,
+    };
+
+    match repetition_type {
+        RepetitionType::Range(from, usize::MAX) if repetition_count > *from => *from,
+        _ => repetition_count,
+    }
+}
+
+// The transition index of a thread which is at the entry of a node.
+const NODE_ENTRY: usize = usize::MAX;
+
+struct Thread {
+    node_index: usize,
+
+    // The index of the consuming transition the thread stops at,
+    // or `NODE_ENTRY` if the thread is at the entry of the node.
+    transition_index: usize,
+
+    // The index of the next char to check, for string transitions.
+    char_offset: usize,
+
+    repetition_count: usize,
+    counter_stack: Vec<usize>,
+    match_ranges: Vec<MatchRange>,
+}
+
+impl Thread {
+    fn new(node_index: usize, match_ranges: Vec<MatchRange>) -> Self {
+        Thread {
+            node_index,
+            transition_index: NODE_ENTRY,
+            char_offset: 0,
+            repetition_count: 0,
+            counter_stack: vec![],
+            match_ranges,
+        }
+    }
+}
+
+// The threads at the same position, in priority order.
+struct ThreadList {
+    threads: Vec<Thread>,
+
+    // The states of the threads that have been added, the capture groups
+    // are not part of the state, the thread with higher priority wins.
+    visited: HashSet<(usize, usize, usize, usize, Vec<usize>)>,
+}
+
+impl ThreadList {
+    fn new() -> Self {
+        ThreadList {
+            threads: vec![],
+            visited: HashSet::new(),
+        }
+    }
+
+    // Returns `false` if a thread with the same state has been added.
+    fn visit(&mut self, thread: &Thread) -> bool {
+        self.visited.insert((
+            thread.node_index,
+            thread.transition_index,
+            thread.char_offset,
+            thread.repetition_count,
+            thread.counter_stack.clone(),
+        ))
+    }
+}
@@ -0,0 +1,415 @@
+// Copyright (c) 2025 Hemashushu <hippospark@gmail.com>, All rights reserved.
+//
+// This Source Code Form is subject to the terms of
+// the Mozilla Public License version 2.0 and additional exceptions.
+// For more details, see the LICENSE, LICENSE.additional, and CONTRIBUTING files.
+
+use std::mem::size_of;
+
+use crate::{
+    object_file::{ObjectFile, Route, TransitionItem, MAIN_ROUTE_INDEX},
+    transition::{CharSetItem, Transition},
+};
+
+// The processes try to match the main route at every position of the text,
+// the prefilter finds the positions where a match can start (the candidates)
+// by scanning the bytes of the text, so the transitions are executed only
+// at the candidates instead of every position.
+//
+// The prefilter is extracted from the main route of the object file:
+//
+// - The prefix: the literal which every match starts with,
+//   e.g. "ERROR: " of `ERROR: \d+`.
+// - The first bytes: the set of the first bytes of every match,
+//   e.g. 'a', 'b' and 'c' of `[abc]\d`.
+// - The inner literal: the literal which every match contains,
+//   e.g. "@" of `\w+@\w+`, the text which does not contain it never matches.
+//
+// The transitions which do not consume chars (e.g. jumps, capture groups,
+// counters and assertions) are treated as always succeeding, this only
+// adds more candidates, so the prefilter never skips a real match.
+
+// The maximum length of the prefix.
+const MAX_PREFIX_LENGTH: usize = 64;
+
+// The maximum number of the literal transitions checked for the inner literal,
+// since each check walks through the whole route.
+const MAX_INNER_LITERAL_CHECKS: usize = 256;
+
+#[derive(Debug, Default)]
+pub struct Prefilter {
+    // The literal which every match starts with, it is empty if there is none.
+    pub prefix: Vec<u8>,
+
+    // The first byte of every match is in this set, it is `None` if any byte
+    // can start a match, or the match can be empty.
+    pub first_bytes: Option<Box<[bool; 256]>>,
+
+    // The literal which every match contains, it is empty if there is none
+    // or it is not longer than the prefix.
+    pub inner_literal: Vec<u8>,
+}
+
+impl Prefilter {
+    pub fn new(object_file: &ObjectFile) -> Self {
+        let route = &object_file.routes[MAIN_ROUTE_INDEX];
+
+        let prefix = extract_prefix(route);
+        let first_bytes = extract_first_bytes(route);
+        let inner_literal = match extract_inner_literal(route) {
+            Some(literal) if literal.len() > prefix.len() => literal,
+            _ => vec![],
+        };
+
+        Prefilter {
+            prefix,
+            first_bytes,
+            inner_literal,
+        }
+    }
+
+    /// Returns `false` if the text within the range never matches,
+    /// i.e. it does not contain the inner literal.
+    pub fn may_match(&self, bytes: &[u8], start_position: usize, end_position: usize) -> bool {
+        self.inner_literal.is_empty()
+            || find_bytes(&bytes[..end_position], start_position, &self.inner_literal).is_some()
+    }
+
+    /// Returns the first candidate position at or after the position,
+    /// or `None` if a match can not start in the rest of the range.
+    pub fn next_candidate(
+        &self,
+        bytes: &[u8],
+        position: usize,
+        end_position: usize,
+    ) -> Option<usize> {
+        if !self.prefix.is_empty() {
+            find_bytes(&bytes[..end_position], position, &self.prefix)
+        } else if let Some(first_bytes) = &self.first_bytes {
+            bytes[position..end_position]
+                .iter()
+                .position(|byte| first_bytes[*byte as usize])
+                .map(|offset| position + offset)
+        } else {
+            Some(position)
+        }
+    }
+}
+
+// Collects the first transitions which consume chars, starting from the node and
+// following the transitions which do not consume chars.
+//
+// Returns `None` if the exit node can be reached without consuming chars
+// (i.e. the match can be empty), or a back reference is met (it may match an empty string).
+fn collect_first_transitions(route: &Route, node_index: usize) -> Option<Vec<&TransitionItem>> {
+    let mut items = vec![];
+    let mut visited = vec![false; route.nodes.len()];
+    let mut stack = vec![node_index];
+
+    while let Some(node_index) = stack.pop() {
+        if node_index == route.end_node_index {
+            return None;
+        }
+
+        if visited[node_index] {
+            continue;
+        }
+        visited[node_index] = true;
+
+        for transition_item in &route.nodes[node_index].transition_items {
+            match &transition_item.transition {
+                Transition::Char(_)
+                | Transition::SpecialChar(_)
+                | Transition::String(_)
+                | Transition::CharSet(_)
+                | Transition::Trie(_) => items.push(transition_item),
+                Transition::BackReference(_) => return None,
+                _ => stack.push(transition_item.target_node_index),
+            }
+        }
+    }
+
+    Some(items)
+}
+
+// Returns the UTF-8 bytes of the transition which matches a literal.
+fn get_literal(transition: &Transition) -> Option<Vec<u8>> {
+    let codepoints = match transition {
+        Transition::Char(transition) if !transition.ignore_case => vec![transition.codepoint],
+        Transition::String(transition) if !transition.ignore_case => transition.codepoints.clone(),
+        _ => return None,
+    };
+
+    let s: String = codepoints
+        .iter()
+        .map(|codepoint| char::from_u32(*codepoint).unwrap())
+        .collect();
+    Some(s.into_bytes())
+}
+
+fn extract_prefix(route: &Route) -> Vec<u8> {
+    let mut prefix = vec![];
+    let mut node_index = route.start_node_index;
+
+    // Follow the chain of the literals until there is a branch.
+    while prefix.len() < MAX_PREFIX_LENGTH {
+        let Some(items) = collect_first_transitions(route, node_index) else {
+            break;
+        };
+
+        let [transition_item] = items.as_slice() else {
+            break;
+        };
+
+        let Some(literal) = get_literal(&transition_item.transition) else {
+            break;
+        };
+
+        prefix.extend(literal);
+        node_index = transition_item.target_node_index;
+    }
+
+    prefix.truncate(MAX_PREFIX_LENGTH);
+    prefix
+}
+
+// Returns the first byte of the UTF-8 encoding of the codepoint.
+fn get_first_byte(codepoint: u32) -> u8 {
+    match codepoint {
+        0..0x80 => codepoint as u8,
+        0x80..0x800 => 0xC0 | (codepoint >> 6) as u8,
+        0x800..0x1_0000 => 0xE0 | (codepoint >> 12) as u8,
+        _ => 0xF0 | (codepoint >> 18) as u8,
+    }
+}
+
+fn extract_first_bytes(route: &Route) -> Option<Box<[bool; 256]>> {
+    let items = collect_first_transitions(route, route.start_node_index)?;
+    let mut first_bytes = Box::new([false; 256]);
+
+    for transition_item in items {
+        match &transition_item.transition {
+            Transition::CharSet(transition) if !transition.negative && !transition.ignore_case => {
+                for item in &transition.items {
+                    // The first byte increases with the codepoint,
+                    // so a range of chars has a range of the first bytes.
+                    let (start, end_included) = match item {
+                        CharSetItem::Char(codepoint) => (*codepoint, *codepoint),
+                        CharSetItem::Range(range) => (range.start, range.end_included),
+                    };
+                    for byte in get_first_byte(start)..=get_first_byte(end_included) {
+                        first_bytes[byte as usize] = true;
+                    }
+                }
+            }
+            Transition::Trie(transition) if !transition.ignore_case => {
+                for (codepoint, _) in &transition.nodes[0].children {
+                    first_bytes[get_first_byte(*codepoint) as usize] = true;
+                }
+            }
+            transition => {
+                let literal = get_literal(transition)?;
+                first_bytes[literal[0] as usize] = true;
+            }
+        }
+    }
+
+    Some(first_bytes)
+}
+
+// Returns the longest literal which is passed through by every path
+// from the entry node to the exit node.
+fn extract_inner_literal(route: &Route) -> Option<Vec<u8>> {
+    let mut longest: Option<Vec<u8>> = None;
+
+    let literals = route
+        .nodes
+        .iter()
+        .enumerate()
+        .flat_map(|(node_index, node)| {
+            node.transition_items.iter().enumerate().filter_map(
+                move |(transition_index, transition_item)| {
+                    get_literal(&transition_item.transition)
+                        .map(|literal| ((node_index, transition_index), literal))
+                },
+            )
+        })
+        .take(MAX_INNER_LITERAL_CHECKS);
+
+    for (location, literal) in literals {
+        let is_longer = longest
+            .as_ref()
+            .is_none_or(|longest| literal.len() > longest.len());
+
+        if is_longer && !is_exit_reachable_without(route, location) {
+            longest = Some(literal);
+        }
+    }
+
+    longest
+}
+
+// Checks whether the exit node can be reached from the entry node
+// without passing through the specified transition.
+fn is_exit_reachable_without(route: &Route, location: (usize, usize)) -> bool {
+    let mut visited = vec![false; route.nodes.len()];
+    let mut stack = vec![route.start_node_index];
+
+    while let Some(node_index) = stack.pop() {
+        if node_index == route.end_node_index {
+            return true;
+        }
+
+        if visited[node_index] {
+            continue;
+        }
+        visited[node_index] = true;
+
+        for (transition_index, transition_item) in
+            route.nodes[node_index].transition_items.iter().enumerate()
+        {
+            if (node_index, transition_index) != location {
+                stack.push(transition_item.target_node_index);
+            }
+        }
+    }
+
+    false
+}
+
+// Finds the needle in the haystack starting from the position,
+// returns the position of the needle.
+fn find_bytes(haystack: &[u8], position: usize, needle: &[u8]) -> Option<usize> {
+    let mut position = position;
+
+    while position + needle.len() <= haystack.len() {
+        // Find the first byte of the needle, then compare the remaining bytes.
+        let offset = find_byte(&haystack[position..], needle[0])?;
+        let start = position + offset;
+
+        if haystack[start..].starts_with(needle) {
+            return Some(start);
+        }
+
+        position = start + 1;
+    }
+
+    None
+}
+
+const WORD_BYTES: usize = size_of::<usize>();
+const LOW_BITS: usize = usize::from_ne_bytes([0x01; WORD_BYTES]);
+const HIGH_BITS: usize = usize::from_ne_bytes([0x80; WORD_BYTES]);
+
+// Finds the byte in the haystack, it checks a word of bytes at a time.
+fn find_byte(haystack: &[u8], byte: u8) -> Option<usize> {
+    let repeated_byte = LOW_BITS * byte as usize;
+
+    let mut chunks = haystack.chunks_exact(WORD_BYTES);
+    for (chunk_index, chunk) in chunks.by_ref().enumerate() {
+        // A byte of the word is zero if it equals to the byte.
+        let word = usize::from_ne_bytes(chunk.try_into().unwrap()) ^ repeated_byte;
+        let has_zero_byte = word.wrapping_sub(LOW_BITS) & !word & HIGH_BITS != 0;
+
+        if has_zero_byte {
+            let offset = chunk.iter().position(|b| *b == byte).unwrap();
+            return Some(chunk_index * WORD_BYTES + offset);
+        }
+    }
+
+    let remainder = chunks.remainder();
+    remainder
+        .iter()
+        .position(|b| *b == byte)
+        .map(|offset| haystack.len() - remainder.len() + offset)
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use crate::compiler::{compile_from_regex, compile_from_regex_with_options, CompileOptions};
+
+    use super::{find_bytes, Prefilter};
+
+    fn build(pattern: &str) -> Prefilter {
+        Prefilter::new(&compile_from_regex(pattern).unwrap())
+    }
+
+    fn get_first_bytes(prefilter: &Prefilter) -> Option<Vec<u8>> {
+        prefilter.first_bytes.as_ref().map(|first_bytes| {
+            (0..=255u8)
+                .filter(|byte| first_bytes[*byte as usize])
+                .collect()
+        })
+    }
+
+    #[test]
+    fn test_extract() {
+        let prefilter = build(r#"ERROR: \d+"#);
+        assert_eq!(prefilter.prefix, b"ERROR: ");
+        assert_eq!(get_first_bytes(&prefilter), Some(b"E".to_vec()));
+
+        // the capture groups and assertions are skipped
+        let prefilter = build(r#"\b(?<name>foo)bar"#);
+        assert_eq!(prefilter.prefix, b"foobar");
+
+        // stop at the branch
+        let prefilter = build(r#"ab(c|d)"#);
+        assert_eq!(prefilter.prefix, b"ab");
+
+        let prefilter = build(r#"cat|car|dog"#);
+        assert_eq!(prefilter.prefix, b"");
+        assert_eq!(get_first_bytes(&prefilter), Some(b"cd".to_vec()));
+
+        let prefilter = build(r#"[a-c文]\d"#);
+        assert_eq!(get_first_bytes(&prefilter), Some(b"abc\xe6".to_vec()));
+
+        let prefilter = build(r#".+@\w+"#);
+        assert_eq!(prefilter.inner_literal, b"@");
+        assert_eq!(get_first_bytes(&prefilter), None);
+
+        // the inner literal is required by every path
+        let prefilter = build(r#"\d+(px|em)"#);
+        assert_eq!(prefilter.inner_literal, b"");
+
+        let prefilter = build(r#"(ab)+x"#);
+        assert_eq!(prefilter.prefix, b"ab");
+
+        // the match can be empty
+        let prefilter = build(r#"a*"#);
+        assert_eq!(prefilter.prefix, b"");
+        assert_eq!(get_first_bytes(&prefilter), None);
+
+        let prefilter = build(r#"(a)\1"#);
+        assert_eq!(prefilter.prefix, b"a");
+
+        // case insensitive
+        let options = CompileOptions {
+            case_insensitive: true,
+            ..CompileOptions::default()
+        };
+        let prefilter = Prefilter::new(&compile_from_regex_with_options("abc", &options).unwrap());
+        assert_eq!(prefilter.prefix, b"");
+        assert_eq!(get_first_bytes(&prefilter), None);
+    }
+
+    #[test]
+    fn test_next_candidate() {
+        let prefilter = build(r#"ERROR: \d+"#);
+        let text = b"INFO: 1\nERROR: 2\nERROR: 3";
+        assert_eq!(prefilter.next_candidate(text, 0, text.len()), Some(8));
+        assert_eq!(prefilter.next_candidate(text, 9, text.len()), Some(17));
+        assert_eq!(prefilter.next_candidate(text, 9, 20), None);
+
+        let prefilter = build(r#"\w+@\w+"#);
+        assert!(prefilter.may_match(b"a@b", 0, 3));
+        assert!(!prefilter.may_match(b"a@b", 2, 3));
+        assert!(!prefilter.may_match(b"a@b", 0, 1));
+
+        assert_eq!(find_bytes(b"aaaaaaaaaaaaaaaab", 0, b"ab"), Some(15));
+        assert_eq!(find_bytes(b"0123456789abcdefghij", 3, b"j"), Some(19));
+        assert_eq!(find_bytes(b"ab", 0, b"abc"), None);
+        assert_eq!(find_bytes(b"", 0, b"a"), None);
+    }
+}
@@ -30,20 +30,46 @@ impl Display for FunctionName {
             FunctionName::IsNotAfter => f.write_str("is_not_after"),
             FunctionName::Name => f.write_str("name"),
             FunctionName::Index => f.write_str("index"),
+            FunctionName::IgnoreCase => f.write_str("ignore_case"),
+            FunctionName::MultiLine => f.write_str("multiline"),
+            FunctionName::DotAll => f.write_str("dot_all"),
+            FunctionName::NotIgnoreCase => f.write_str("not_ignore_case"),
+            FunctionName::NotMultiLine => f.write_str("not_multiline"),
+            FunctionName::NotDotAll => f.write_str("not_dot_all"),
         }
     }
 }
 
+// Escapes the char in the char or string literal, the `quote` is
+// the quote of the literal, i.e. `'` or `"`.
+fn escape_char(c: char, quote: char) -> String {
+    match c {
+        '\\' => "\\\\".to_owned(),
+        '\t' => "\\t".to_owned(),
+        '\r' => "\\r".to_owned(),
+        '\n' => "\\n".to_owned(),
+        '\0' => "\\0".to_owned(),
+        _ if c == quote => format!("\\{}", c),
+        _ if c.is_control() => format!("\\u{{{:x}}}", c as u32),
+        _ => c.to_string(),
+    }
+}
+
 impl Display for CharRange {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "'{}'..'{}'", self.start, self.end_included)
+        write!(
+            f,
+            "'{}'..'{}'",
+            escape_char(self.start, '\''),
+            escape_char(self.end_included, '\'')
+        )
     }
 }
 
 impl Display for CharSetElement {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            CharSetElement::Char(c) => write!(f, "'{}'", c),
+            CharSetElement::Char(c) => write!(f, "'{}'", escape_char(*c, '\'')),
             CharSetElement::CharRange(c) => write!(f, "{}", c),
             CharSetElement::PresetCharSet(p) => write!(f, "{}", p),
             CharSetElement::CharSet(c) => write!(f, "{}", c),
@@ -66,8 +92,11 @@ impl Display for Literal {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Literal::Number(i) => write!(f, "{}", i),
-            Literal::Char(c) => write!(f, "'{}'", c),
-            Literal::String(s) => write!(f, "\"{}\"", s),
+            Literal::Char(c) => write!(f, "'{}'", escape_char(*c, '\'')),
+            Literal::String(s) => {
+                let escaped: String = s.chars().map(|c| escape_char(c, '"')).collect();
+                write!(f, "\"{}\"", escaped)
+            }
             Literal::CharSet(c) => write!(f, "{}", c),
             Literal::PresetCharSet(p) => write!(f, "{}", p),
             Literal::Special(s) => write!(f, "{}", s),
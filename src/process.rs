@@ -5,24 +5,43 @@
 // For more details, see the LICENSE, LICENSE.additional, and CONTRIBUTING files.
 
 use crate::{
-    context::{Context, Routine},
+    context::Context,
     object_file::{ObjectFile, MAIN_ROUTE_INDEX},
+    regex::Anchored,
     transition::ExecuteResult,
     utf8reader::read_char,
 };
 
 /// Start a new process.
 ///
-/// A process represents a matching operation.
+/// A process represents a matching operation, the match is searched
+/// within the range `start_position..end_position`, but the assertions
+/// (e.g. the lookbehind and `is_bound`) can still see the text outside the range.
+///
+/// The `anchored` parameter adds the anchoring to the anchoring
+/// of the main route (see `Route::is_fixed_start_position`).
 pub fn start_process(
     context: &mut Context,
     object_file: &ObjectFile,
     start_position: usize,
+    end_position: usize,
+    anchored: Anchored,
 ) -> bool {
-    let end = context.bytes.len();
+    let route = &object_file.routes[MAIN_ROUTE_INDEX];
 
     // Start the main routine for matching.
-    start_routine(context, object_file, MAIN_ROUTE_INDEX, start_position, end)
+    let result = start_routine_with_anchoring(
+        context,
+        object_file,
+        MAIN_ROUTE_INDEX,
+        start_position,
+        end_position,
+        route.is_fixed_start_position || anchored.is_fixed_start_position(),
+        route.is_fixed_end_position || anchored.is_fixed_end_position(),
+    );
+
+    // The result is discarded if the budget is exceeded.
+    result && !context.budget.is_exceeded()
 }
 
 /// Start a routine.
@@ -37,26 +56,63 @@ pub fn start_routine(
     start_position: usize, // Start position of the text range (inclusive).
     end_position: usize,   // End position of the text range (exclusive).
 ) -> bool {
+    let route = &object_file.routes[route_index];
+    start_routine_with_anchoring(
+        context,
+        object_file,
+        route_index,
+        start_position,
+        end_position,
+        route.is_fixed_start_position,
+        route.is_fixed_end_position,
+    )
+}
+
+fn start_routine_with_anchoring(
+    context: &mut Context,
+    object_file: &ObjectFile,
+    route_index: usize,
+    start_position: usize,
+    end_position: usize,
+    is_fixed_start_position: bool,
+    is_fixed_end_position: bool,
+) -> bool {
+    // The main route skips the positions where a match can not start.
+    let prefilter = &object_file.prefilter;
+    let use_prefilter = route_index == MAIN_ROUTE_INDEX && !is_fixed_start_position;
+    if use_prefilter && !prefilter.may_match(context.bytes, start_position, end_position) {
+        return false;
+    }
+
     let mut result = false;
     let mut position = start_position;
 
-    let routine = Routine::new(start_position, end_position, route_index);
-
     // A routine corresponds to a route.
     // Sub-routines (generated by lookaround assertions) also invoke this function.
     // The `context.routines` stack is used to record sub-routines.
-    context.routines.push(routine);
+    context.push_routine(start_position, end_position, route_index);
 
     // Continue moving the start position forward and retry matching
     // until a match is successful or the end of the range is reached.
-    while position < end_position {
-        if execute_transitions(context, object_file, position) {
+    //
+    // The end position is also tried, since the expression
+    // may match an empty string, e.g. `a*` and `$`.
+    loop {
+        if use_prefilter {
+            match prefilter.next_candidate(context.bytes, position, end_position) {
+                Some(candidate) => position = candidate,
+                None => break,
+            }
+        }
+
+        if execute_transitions(context, object_file, position, is_fixed_end_position) {
             result = true;
             break;
         }
 
         // If the expression starts with "^...", there is no need to try remaining characters.
-        if object_file.routes[route_index].is_fixed_start_position {
+        // Also stop trying if the budget is exceeded.
+        if is_fixed_start_position || context.budget.is_exceeded() || position >= end_position {
             break;
         }
 
@@ -65,18 +121,32 @@ pub fn start_routine(
         position += byte_length;
     }
 
-    context.routines.pop();
+    context.pop_routine();
     result
 }
 
 /// Execute transitions for a route starting from a specified position.
 /// Returns `true` if all transitions succeed, otherwise `false`.
-fn execute_transitions(context: &mut Context, object_file: &ObjectFile, position: usize) -> bool {
-    let (route_index, entry_node_index, exit_node_index) = {
+///
+/// If `is_fixed_end_position` is `true`, reaching the exit node before
+/// the end of the text range is treated as a failure, and the remaining
+/// transitions are tried.
+fn execute_transitions(
+    context: &mut Context,
+    object_file: &ObjectFile,
+    position: usize,
+    is_fixed_end_position: bool,
+) -> bool {
+    let (route_index, entry_node_index, exit_node_index, end_position) = {
         let thread = context.get_current_routine_ref();
         let route_index = thread.route_index;
         let route = &object_file.routes[route_index];
-        (route_index, route.start_node_index, route.end_node_index)
+        (
+            route_index,
+            route.start_node_index,
+            route.end_node_index,
+            thread.end_position,
+        )
     };
 
     // Add transitions for the first node (entry node).
@@ -91,6 +161,16 @@ fn execute_transitions(context: &mut Context, object_file: &ObjectFile, position
     // - On success: Move to the next node and push all its transitions onto the stack.
     //   If the node is the last node of the route, the route matching succeeds.
     while let Some(frame) = context.pop_transition_stack_item() {
+        // Each executed transition takes a step of the budget.
+        if !context.budget.take_step() {
+            // Discard the remaining transitions of this routine.
+            context
+                .get_current_routine_ref_mut()
+                .transition_stack
+                .clear();
+            return false;
+        }
+
         let route = &object_file.routes[route_index];
         let node = &route.nodes[frame.current_node_index];
         let transition_item = &node.transition_items[frame.transition_index];
@@ -106,6 +186,11 @@ fn execute_transitions(context: &mut Context, object_file: &ObjectFile, position
         match check_result {
             ExecuteResult::Success(move_forward, current_repetition_count) => {
                 if target_node_index == exit_node_index {
+                    if is_fixed_end_position && position + move_forward != end_position {
+                        // The match does not reach the end of the range, try the next transition.
+                        continue;
+                    }
+
                     // Reached the last node of the route, meaning the route matching succeeded.
                     return true;
                 }
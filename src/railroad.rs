@@ -0,0 +1,763 @@
+// Copyright (c) 2025 Hemashushu <hippospark@gmail.com>, All rights reserved.
+//
+// This Source Code Form is subject to the terms of
+// the Mozilla Public License version 2.0 and additional exceptions.
+// For more details, see the LICENSE, LICENSE.additional, and CONTRIBUTING files.
+
+//! Renders the railroad diagram (syntax diagram) of a pattern as a standalone SVG.
+//!
+//! e.g.
+//!
+//! ```
+//! let svg = regex_anre::railroad::from_regex(r"(?<year>\d{4})-(?<month>\d{2})").unwrap();
+//! assert!(svg.starts_with("<svg "));
+//! ```
+//!
+//! The literals are drawn as rounded boxes, the char classes, back references
+//! and assertions are drawn as square boxes. Captures, lookarounds, char sets
+//! and modifiers are drawn as dashed frames with a label.
+
+use crate::{
+    ast::{
+        quote_char, AnchorAssertionName, BackReference, BoundaryAssertionName, CharSet,
+        CharSetElement, Expression, FunctionCall, FunctionName, Literal, PresetCharSetName,
+        Program, SpecialCharName,
+    },
+    rulechecker::check_program,
+    AnreError,
+};
+
+// The layout constants, in pixels.
+const ARC_RADIUS: usize = 10;
+const HORIZONTAL_GAP: usize = 10;
+const VERTICAL_GAP: usize = 8;
+const BOX_HALF_HEIGHT: usize = 11;
+const TEXT_PADDING: usize = 10;
+const FRAME_PADDING: usize = 10;
+const LABEL_HEIGHT: usize = 14;
+const MARGIN: usize = 20;
+const TERMINATOR_LENGTH: usize = 20;
+
+// The width of the text in the boxes (13px monospace font),
+// and the width of the labels (11px sans-serif font).
+const CHAR_WIDTH: usize = 8;
+const LABEL_CHAR_WIDTH: usize = 7;
+
+const STYLE: &str = "\
+path{fill:none;stroke:#333;stroke-width:2}\
+rect.terminal{fill:#fff7d6;stroke:#333;stroke-width:2}\
+rect.nonterminal{fill:#dcecff;stroke:#333;stroke-width:2}\
+rect.frame{fill:none;stroke:#999;stroke-width:1;stroke-dasharray:4 3}\
+circle{fill:#333}\
+text{font:13px monospace;text-anchor:middle}\
+text.label{font:11px sans-serif;fill:#555}\
+text.frame{text-anchor:start}";
+
+/// Renders the railroad diagram of the traditional regular expression.
+pub fn from_regex(s: &str) -> Result<String, AnreError> {
+    let program = crate::traditional::parse_from_str(s)?;
+    check_program(&program)?;
+    Ok(render(&program))
+}
+
+/// Renders the railroad diagram of the ANRE regular expression.
+pub fn from_anre(s: &str) -> Result<String, AnreError> {
+    let program = crate::anre::parse_from_str(s)?;
+    check_program(&program)?;
+    Ok(render(&program))
+}
+
+/// Renders the railroad diagram of the AST `Program`,
+/// the program should be checked by `check_program` first.
+pub(crate) fn render(program: &Program) -> String {
+    let mut builder = DiagramBuilder::default();
+    let diagram = builder.build_expressions(&program.expressions);
+    let size = diagram.size();
+
+    let width = size.width + (MARGIN + TERMINATOR_LENGTH) * 2;
+    let up = size.up.max(BOX_HALF_HEIGHT);
+    let height = up + size.down.max(BOX_HALF_HEIGHT) + MARGIN * 2;
+    let x = MARGIN + TERMINATOR_LENGTH;
+    let y = MARGIN + up;
+
+    let mut ss = vec![
+        format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" viewBox=\"0 0 {} {}\">",
+            width, height, width, height
+        ),
+        format!("<style>{}</style>", STYLE),
+        format!("<circle cx=\"{}\" cy=\"{}\" r=\"5\"/>", MARGIN, y),
+        horizontal_line(MARGIN, y, TERMINATOR_LENGTH),
+    ];
+
+    diagram.render(x, y, &mut ss);
+
+    let end_x = x + size.width;
+    ss.push(horizontal_line(end_x, y, TERMINATOR_LENGTH));
+    ss.push(format!(
+        "<circle cx=\"{}\" cy=\"{}\" r=\"5\"/>",
+        end_x + TERMINATOR_LENGTH,
+        y
+    ));
+    ss.push("</svg>".to_owned());
+    ss.join("\n")
+}
+
+// The parts of the diagram, each part has an entry on the left and an exit
+// on the right, both are on the same horizontal line.
+enum Diagram {
+    // An empty path.
+    Skip,
+
+    // A rounded box, for the literals.
+    Terminal(String),
+
+    // A square box, for the char classes, back references and assertions.
+    NonTerminal(String),
+
+    Sequence(Vec<Diagram>),
+
+    // The first item is on the main line, the others are below it.
+    Choice(Vec<Diagram>),
+
+    // The item with a return path below it, and an optional label
+    // under the return path.
+    Loop(Box<Diagram>, Option<String>),
+
+    // A dashed frame around the item, with a label at the top.
+    Frame(Box<Diagram>, String),
+}
+
+// The width, and the heights above and below the entry line.
+struct Size {
+    width: usize,
+    up: usize,
+    down: usize,
+}
+
+impl Diagram {
+    fn size(&self) -> Size {
+        match self {
+            Diagram::Skip => Size {
+                width: 0,
+                up: 0,
+                down: 0,
+            },
+            Diagram::Terminal(text) | Diagram::NonTerminal(text) => Size {
+                width: text_width(text, CHAR_WIDTH) + TEXT_PADDING * 2,
+                up: BOX_HALF_HEIGHT,
+                down: BOX_HALF_HEIGHT,
+            },
+            Diagram::Sequence(items) => {
+                let sizes: Vec<Size> = items.iter().map(|item| item.size()).collect();
+                Size {
+                    width: sizes.iter().map(|size| size.width).sum::<usize>()
+                        + HORIZONTAL_GAP * sizes.len().saturating_sub(1),
+                    up: sizes.iter().map(|size| size.up).max().unwrap_or(0),
+                    down: sizes.iter().map(|size| size.down).max().unwrap_or(0),
+                }
+            }
+            Diagram::Choice(items) => {
+                let sizes: Vec<Size> = items.iter().map(|item| item.size()).collect();
+                let offsets = get_choice_offsets(&sizes);
+                Size {
+                    width: sizes.iter().map(|size| size.width).max().unwrap_or(0) + ARC_RADIUS * 4,
+                    up: sizes.first().map_or(0, |size| size.up),
+                    down: offsets
+                        .last()
+                        .zip(sizes.last())
+                        .map_or(0, |(offset, size)| offset + size.down),
+                }
+            }
+            Diagram::Loop(item, label) => {
+                let size = item.size();
+                let inner_width = get_loop_inner_width(&size, label);
+                let return_offset = get_loop_return_offset(&size);
+                Size {
+                    width: inner_width + ARC_RADIUS * 2,
+                    up: size.up,
+                    down: return_offset + if label.is_some() { LABEL_HEIGHT + 4 } else { 0 },
+                }
+            }
+            Diagram::Frame(item, label) => {
+                let size = item.size();
+                Size {
+                    width: size.width.max(text_width(label, LABEL_CHAR_WIDTH)) + FRAME_PADDING * 2,
+                    up: size.up + FRAME_PADDING + LABEL_HEIGHT,
+                    down: size.down + FRAME_PADDING,
+                }
+            }
+        }
+    }
+
+    // Renders the diagram with the entry at `(x, y)`.
+    fn render(&self, x: usize, y: usize, ss: &mut Vec<String>) {
+        match self {
+            Diagram::Skip => {}
+            Diagram::Terminal(text) | Diagram::NonTerminal(text) => {
+                let (class_name, radius) = if matches!(self, Diagram::Terminal(_)) {
+                    ("terminal", BOX_HALF_HEIGHT)
+                } else {
+                    ("nonterminal", 0)
+                };
+                let width = self.size().width;
+                ss.push(format!(
+                    "<rect class=\"{}\" x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" rx=\"{}\"/>",
+                    class_name,
+                    x,
+                    y - BOX_HALF_HEIGHT,
+                    width,
+                    BOX_HALF_HEIGHT * 2,
+                    radius
+                ));
+                ss.push(format!(
+                    "<text x=\"{}\" y=\"{}\">{}</text>",
+                    x + width / 2,
+                    y + 4,
+                    escape_xml(text)
+                ));
+            }
+            Diagram::Sequence(items) => {
+                let mut current_x = x;
+                for (index, item) in items.iter().enumerate() {
+                    if index > 0 {
+                        ss.push(horizontal_line(current_x, y, HORIZONTAL_GAP));
+                        current_x += HORIZONTAL_GAP;
+                    }
+                    item.render(current_x, y, ss);
+                    current_x += item.size().width;
+                }
+            }
+            Diagram::Choice(items) => {
+                let sizes: Vec<Size> = items.iter().map(|item| item.size()).collect();
+                let offsets = get_choice_offsets(&sizes);
+                let inner_width = sizes.iter().map(|size| size.width).max().unwrap_or(0);
+                let inner_x = x + ARC_RADIUS * 2;
+                let exit_x = inner_x + inner_width;
+
+                for ((item, size), offset) in items.iter().zip(&sizes).zip(offsets) {
+                    if offset == 0 {
+                        ss.push(horizontal_line(x, y, ARC_RADIUS * 2));
+                    } else {
+                        // curve down from the entry, and back up to the exit.
+                        ss.push(format!(
+                            "<path d=\"M{} {}a{r} {r} 0 0 1 {r} {r}v{}a{r} {r} 0 0 0 {r} {r}\"/>",
+                            x,
+                            y,
+                            offset - ARC_RADIUS * 2,
+                            r = ARC_RADIUS
+                        ));
+                    }
+
+                    let item_y = y + offset;
+                    item.render(inner_x, item_y, ss);
+
+                    let item_exit_x = inner_x + size.width;
+                    if offset == 0 {
+                        ss.push(horizontal_line(
+                            item_exit_x,
+                            item_y,
+                            exit_x - item_exit_x + ARC_RADIUS * 2,
+                        ));
+                    } else {
+                        ss.push(format!(
+                            "<path d=\"M{} {}h{}a{r} {r} 0 0 0 {r} -{r}v-{}a{r} {r} 0 0 1 {r} -{r}\"/>",
+                            item_exit_x,
+                            item_y,
+                            exit_x - item_exit_x,
+                            offset - ARC_RADIUS * 2,
+                            r = ARC_RADIUS
+                        ));
+                    }
+                }
+            }
+            Diagram::Loop(item, label) => {
+                let size = item.size();
+                let inner_width = get_loop_inner_width(&size, label);
+                let return_offset = get_loop_return_offset(&size);
+                let inner_x = x + ARC_RADIUS;
+
+                ss.push(horizontal_line(x, y, ARC_RADIUS));
+                item.render(inner_x, y, ss);
+                ss.push(horizontal_line(
+                    inner_x + size.width,
+                    y,
+                    inner_width - size.width + ARC_RADIUS,
+                ));
+
+                // the return path, from the exit of the item back to its entry.
+                ss.push(format!(
+                    "<path d=\"M{} {}a{r} {r} 0 0 1 {r} {r}v{}a{r} {r} 0 0 1 -{r} {r}h-{}a{r} {r} 0 0 1 -{r} -{r}v-{}a{r} {r} 0 0 1 {r} -{r}\"/>",
+                    inner_x + inner_width,
+                    y,
+                    return_offset - ARC_RADIUS * 2,
+                    inner_width,
+                    return_offset - ARC_RADIUS * 2,
+                    r = ARC_RADIUS
+                ));
+
+                if let Some(text) = label {
+                    ss.push(format!(
+                        "<text class=\"label\" x=\"{}\" y=\"{}\">{}</text>",
+                        inner_x + inner_width / 2,
+                        y + return_offset + LABEL_HEIGHT,
+                        escape_xml(text)
+                    ));
+                }
+            }
+            Diagram::Frame(item, label) => {
+                let size = self.size();
+                let item_size = item.size();
+                let inner_x = x + FRAME_PADDING;
+
+                ss.push(format!(
+                    "<rect class=\"frame\" x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" rx=\"4\"/>",
+                    x,
+                    y - size.up,
+                    size.width,
+                    size.up + size.down
+                ));
+                ss.push(format!(
+                    "<text class=\"label frame\" x=\"{}\" y=\"{}\">{}</text>",
+                    x + 4,
+                    y - size.up + LABEL_HEIGHT - 2,
+                    escape_xml(label)
+                ));
+
+                ss.push(horizontal_line(x, y, FRAME_PADDING));
+                item.render(inner_x, y, ss);
+                ss.push(horizontal_line(
+                    inner_x + item_size.width,
+                    y,
+                    size.width - FRAME_PADDING - item_size.width,
+                ));
+            }
+        }
+    }
+}
+
+// The vertical offsets of the items of a choice, relative to the entry line.
+// The items below the main line are at least two arcs away from it,
+// so that the curves fit.
+fn get_choice_offsets(sizes: &[Size]) -> Vec<usize> {
+    let mut offsets: Vec<usize> = Vec::with_capacity(sizes.len());
+    for (index, size) in sizes.iter().enumerate() {
+        let offset = if index == 0 {
+            0
+        } else {
+            let previous_offset = offsets[index - 1];
+            let previous_size = &sizes[index - 1];
+            (previous_offset + previous_size.down + VERTICAL_GAP + size.up).max(ARC_RADIUS * 2)
+        };
+        offsets.push(offset);
+    }
+    offsets
+}
+
+fn get_loop_inner_width(size: &Size, label: &Option<String>) -> usize {
+    label.as_ref().map_or(size.width, |text| {
+        size.width.max(text_width(text, LABEL_CHAR_WIDTH))
+    })
+}
+
+fn get_loop_return_offset(size: &Size) -> usize {
+    (size.down + VERTICAL_GAP).max(ARC_RADIUS * 2)
+}
+
+fn horizontal_line(x: usize, y: usize, length: usize) -> String {
+    format!("<path d=\"M{} {}h{}\"/>", x, y, length)
+}
+
+// The CJK chars are about twice as wide as the Latin chars.
+fn text_width(text: &str, char_width: usize) -> usize {
+    text.chars()
+        .map(|c| {
+            if c >= '\u{2e80}' {
+                char_width * 2
+            } else {
+                char_width
+            }
+        })
+        .sum()
+}
+
+fn escape_xml(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+// Converts the AST into the diagram.
+#[derive(Default)]
+struct DiagramBuilder {
+    // The number of capture groups which have been drawn, it is
+    // used to show the index of the groups, the group 0 is the whole match.
+    capture_group_count: usize,
+}
+
+impl DiagramBuilder {
+    fn build_expressions(&mut self, expressions: &[Expression]) -> Diagram {
+        let mut items: Vec<Diagram> = expressions
+            .iter()
+            .map(|expression| self.build_expression(expression))
+            .collect();
+
+        if items.len() == 1 {
+            items.remove(0)
+        } else {
+            Diagram::Sequence(items)
+        }
+    }
+
+    fn build_expression(&mut self, expression: &Expression) -> Diagram {
+        match expression {
+            Expression::Literal(literal) => self.build_literal(literal),
+            Expression::BackReference(back_reference) => {
+                let text = match back_reference {
+                    BackReference::Index(index) => format!("same as #{}", index),
+                    BackReference::Name(name) => format!("same as '{}'", name),
+                };
+                Diagram::NonTerminal(text)
+            }
+            Expression::AnchorAssertion(name) => {
+                let text = match name {
+                    AnchorAssertionName::Start => "start",
+                    AnchorAssertionName::End => "end",
+                };
+                Diagram::NonTerminal(text.to_owned())
+            }
+            Expression::BoundaryAssertion(name) => {
+                let text = match name {
+                    BoundaryAssertionName::IsBound => "word boundary",
+                    BoundaryAssertionName::IsNotBound => "not word boundary",
+                };
+                Diagram::NonTerminal(text.to_owned())
+            }
+            Expression::Group(expressions) => self.build_expressions(expressions),
+            Expression::FunctionCall(function_call) => self.build_function_call(function_call),
+            Expression::Or(_, _) => {
+                // flatten `a || (b || c)` into one choice.
+                Diagram::Choice(
+                    expression
+                        .get_alternatives()
+                        .into_iter()
+                        .map(|alternative| self.build_expression(alternative))
+                        .collect(),
+                )
+            }
+        }
+    }
+
+    fn build_literal(&mut self, literal: &Literal) -> Diagram {
+        match literal {
+            Literal::Number(n) => Diagram::Terminal(n.to_string()),
+            Literal::Char(c) => Diagram::Terminal(quote_char(*c)),
+            Literal::String(s) => Diagram::Terminal(format!("\"{}\"", s.escape_debug())),
+            Literal::Special(SpecialCharName::CharAny) => {
+                Diagram::NonTerminal("any char".to_owned())
+            }
+            Literal::CharSet(charset) => build_charset(charset),
+            Literal::PresetCharSet(name) => Diagram::NonTerminal(preset_charset_text(name)),
+        }
+    }
+
+    fn build_function_call(&mut self, function_call: &FunctionCall) -> Diagram {
+        let name = function_call.name;
+        let args = &function_call.args;
+
+        // the quantifiers and captures apply to the first argument, the
+        // lookarounds are drawn in the order of matching, so the
+        // preceding expression of `is_after` is built first.
+        match name {
+            FunctionName::Optional
+            | FunctionName::OptionalLazy
+            | FunctionName::OneOrMore
+            | FunctionName::OneOrMoreLazy
+            | FunctionName::ZeroOrMore
+            | FunctionName::ZeroOrMoreLazy
+            | FunctionName::Repeat
+            | FunctionName::RepeatRange
+            | FunctionName::RepeatRangeLazy
+            | FunctionName::AtLeast
+            | FunctionName::AtLeastLazy => {
+                let item = self.build_expression(&args[0]);
+                build_quantifier(name, item, &args[1..])
+            }
+            FunctionName::IsBefore | FunctionName::IsNotBefore => {
+                let label = if name == FunctionName::IsBefore {
+                    "followed by"
+                } else {
+                    "not followed by"
+                };
+                let item = self.build_expression(&args[0]);
+                let next_item = self.build_expression(&args[1]);
+                Diagram::Sequence(vec![
+                    item,
+                    Diagram::Frame(Box::new(next_item), label.to_owned()),
+                ])
+            }
+            FunctionName::IsAfter | FunctionName::IsNotAfter => {
+                let label = if name == FunctionName::IsAfter {
+                    "preceded by"
+                } else {
+                    "not preceded by"
+                };
+                let previous_item = self.build_expression(&args[1]);
+                let item = self.build_expression(&args[0]);
+                Diagram::Sequence(vec![
+                    Diagram::Frame(Box::new(previous_item), label.to_owned()),
+                    item,
+                ])
+            }
+            FunctionName::Name | FunctionName::Index => {
+                self.capture_group_count += 1;
+                let label = match args.get(1) {
+                    Some(Expression::Literal(Literal::String(s)))
+                    | Some(Expression::BackReference(BackReference::Name(s))) => {
+                        format!("capture #{} '{}'", self.capture_group_count, s)
+                    }
+                    _ => format!("capture #{}", self.capture_group_count),
+                };
+                let item = self.build_expression(&args[0]);
+                Diagram::Frame(Box::new(item), label)
+            }
+            FunctionName::IgnoreCase
+            | FunctionName::MultiLine
+            | FunctionName::DotAll
+            | FunctionName::NotIgnoreCase
+            | FunctionName::NotMultiLine
+            | FunctionName::NotDotAll => {
+                let label = match name {
+                    FunctionName::IgnoreCase => "ignore case",
+                    FunctionName::MultiLine => "multiline",
+                    FunctionName::DotAll => "dot matches new line",
+                    FunctionName::NotIgnoreCase => "case sensitive",
+                    FunctionName::NotMultiLine => "not multiline",
+                    _ => "dot does not match new line",
+                };
+                let item = self.build_expression(&args[0]);
+                Diagram::Frame(Box::new(item), label.to_owned())
+            }
+        }
+    }
+}
+
+// The greedy quantifiers prefer to match the item, so the item is on the
+// main line, and the lazy ones prefer to skip it, so the skip is on the main line.
+fn build_quantifier(name: FunctionName, item: Diagram, args: &[Expression]) -> Diagram {
+    let is_lazy = matches!(
+        name,
+        FunctionName::OptionalLazy
+            | FunctionName::OneOrMoreLazy
+            | FunctionName::ZeroOrMoreLazy
+            | FunctionName::RepeatRangeLazy
+            | FunctionName::AtLeastLazy
+    );
+
+    let with_lazy = |text: String| -> Option<String> {
+        if is_lazy {
+            Some(format!("{}, lazy", text))
+        } else {
+            Some(text)
+        }
+    };
+
+    let optional = |item: Diagram| -> Diagram {
+        if is_lazy {
+            Diagram::Choice(vec![Diagram::Skip, item])
+        } else {
+            Diagram::Choice(vec![item, Diagram::Skip])
+        }
+    };
+
+    let lazy_label = if is_lazy {
+        Some("lazy".to_owned())
+    } else {
+        None
+    };
+
+    match name {
+        FunctionName::Optional | FunctionName::OptionalLazy => optional(item),
+        FunctionName::OneOrMore | FunctionName::OneOrMoreLazy => {
+            Diagram::Loop(Box::new(item), lazy_label)
+        }
+        FunctionName::ZeroOrMore | FunctionName::ZeroOrMoreLazy => {
+            optional(Diagram::Loop(Box::new(item), lazy_label))
+        }
+        FunctionName::Repeat => {
+            let times = args[0].get_number();
+            Diagram::Loop(Box::new(item), Some(times_text(times)))
+        }
+        FunctionName::RepeatRange | FunctionName::RepeatRangeLazy => {
+            let from = args[0].get_number();
+            let to = args[1].get_number();
+            if from == 0 {
+                let label = with_lazy(format!("at most {}", times_text(to)));
+                optional(Diagram::Loop(Box::new(item), label))
+            } else {
+                let label = with_lazy(format!("{} to {} times", from, to));
+                Diagram::Loop(Box::new(item), label)
+            }
+        }
+        FunctionName::AtLeast | FunctionName::AtLeastLazy => {
+            let from = args[0].get_number();
+            if from == 0 {
+                optional(Diagram::Loop(Box::new(item), lazy_label))
+            } else {
+                let label = with_lazy(format!("at least {}", times_text(from)));
+                Diagram::Loop(Box::new(item), label)
+            }
+        }
+        _ => unreachable!(),
+    }
+}
+
+fn times_text(times: usize) -> String {
+    if times == 1 {
+        "1 time".to_owned()
+    } else {
+        format!("{} times", times)
+    }
+}
+
+fn build_charset(charset: &CharSet) -> Diagram {
+    let mut items = vec![];
+    append_charset_elements(charset, &mut items);
+
+    let label = if charset.negative {
+        "none of"
+    } else {
+        "one of"
+    };
+
+    Diagram::Frame(Box::new(Diagram::Choice(items)), label.to_owned())
+}
+
+// The nested char sets are positive, so their elements are merged
+// into the outer char set.
+fn append_charset_elements(charset: &CharSet, items: &mut Vec<Diagram>) {
+    for element in &charset.elements {
+        match element {
+            CharSetElement::Char(c) => items.push(Diagram::Terminal(quote_char(*c))),
+            CharSetElement::CharRange(range) => items.push(Diagram::Terminal(format!(
+                "{} to {}",
+                quote_char(range.start),
+                quote_char(range.end_included)
+            ))),
+            CharSetElement::PresetCharSet(name) => {
+                items.push(Diagram::NonTerminal(preset_charset_text(name)))
+            }
+            CharSetElement::CharSet(charset) => append_charset_elements(charset, items),
+        }
+    }
+}
+
+fn preset_charset_text(name: &PresetCharSetName) -> String {
+    let text = match name {
+        PresetCharSetName::CharWord => "word char",
+        PresetCharSetName::CharNotWord => "not word char",
+        PresetCharSetName::CharDigit => "digit",
+        PresetCharSetName::CharNotDigit => "not digit",
+        PresetCharSetName::CharSpace => "white space",
+        PresetCharSetName::CharNotSpace => "not white space",
+        PresetCharSetName::CharHex => "hex digit",
+    };
+    text.to_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_str_eq;
+
+    use crate::AnreError;
+
+    use super::{from_anre, from_regex};
+
+    #[test]
+    fn test_render() {
+        assert_str_eq!(
+            from_regex(r#"a|bc"#).unwrap(),
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="172" height="92" viewBox="0 0 172 92">
+<style>path{fill:none;stroke:#333;stroke-width:2}rect.terminal{fill:#fff7d6;stroke:#333;stroke-width:2}rect.nonterminal{fill:#dcecff;stroke:#333;stroke-width:2}rect.frame{fill:none;stroke:#999;stroke-width:1;stroke-dasharray:4 3}circle{fill:#333}text{font:13px monospace;text-anchor:middle}text.label{font:11px sans-serif;fill:#555}text.frame{text-anchor:start}</style>
+<circle cx="20" cy="31" r="5"/>
+<path d="M20 31h20"/>
+<path d="M40 31h20"/>
+<rect class="terminal" x="60" y="20" width="44" height="22" rx="11"/>
+<text x="82" y="35">'a'</text>
+<path d="M104 31h28"/>
+<path d="M40 31a10 10 0 0 1 10 10v10a10 10 0 0 0 10 10"/>
+<rect class="terminal" x="60" y="50" width="52" height="22" rx="11"/>
+<text x="86" y="65">&quot;bc&quot;</text>
+<path d="M112 61h0a10 10 0 0 0 10 -10v-10a10 10 0 0 1 10 -10"/>
+<path d="M132 31h20"/>
+<circle cx="152" cy="31" r="5"/>
+</svg>"#
+        );
+
+        assert!(from_regex(r#"(a"#).is_err());
+    }
+
+    #[test]
+    fn test_render_labels() {
+        let svg = from_regex(
+            r#"(?<year>\d{4})-(\d{2,3}?)[^a-f\s]x*?y+z{2,}(?=q)(?<!w)(?i:k)\b\k<year>\1."#,
+        )
+        .unwrap();
+
+        for text in [
+            r#"<text class="label frame" x="44" y="32">capture #1 'year'</text>"#,
+            r#"<text class="label" x="90" y="89">4 times</text>"#,
+            r#"<text class="label frame" x="247" y="32">capture #2</text>"#,
+            r#"<text class="label" x="326" y="89">2 to 3 times, lazy</text>"#,
+            r#"<text class="label frame" x="423" y="32">none of</text>"#,
+            r#"<text x="499" y="59">'a' to 'f'</text>"#,
+            r#"<text x="503" y="89">white space</text>"#,
+            r#"<text class="label" x="851" y="89">at least 2 times</text>"#,
+            r#"<text class="label frame" x="931" y="32">followed by</text>"#,
+            r#"<text class="label frame" x="1038" y="32">not preceded by</text>"#,
+            r#"<text class="label frame" x="1173" y="32">ignore case</text>"#,
+            r#"<text x="1338" y="59">word boundary</text>"#,
+            r#"<text x="1476" y="59">same as 'year'</text>"#,
+            r#"<text x="1602" y="59">same as #1</text>"#,
+            r#"<text x="1704" y="59">any char</text>"#,
+        ] {
+            assert!(svg.contains(text), "{}", text);
+        }
+
+        // the lazy quantifiers put the skip path on the main line (y=59),
+        // so the item is below it.
+        let svg = from_anre(
+            r#"'a'.optional_lazy(), 'b'.repeat_range(0, 3), 'c'.at_least_lazy(0), is_not_before('d', 'e'), is_not_after('f', 'g')"#,
+        )
+        .unwrap();
+
+        for text in [
+            r#"<text x="82" y="79">'a'</text>"#,
+            r#"<text x="186" y="59">'b'</text>"#,
+            r#"<text class="label" x="216" y="89">at most 3 times</text>"#,
+            r#"<text x="361" y="79">'c'</text>"#,
+            r#"<text class="label" x="361" y="109">lazy</text>"#,
+            r#"<text class="label frame" x="481" y="32">not followed by</text>"#,
+            r#"<text class="label frame" x="616" y="32">not preceded by</text>"#,
+        ] {
+            assert!(svg.contains(text), "{}", text);
+        }
+    }
+    #[test]
+    fn test_render_invalid_arguments() {
+        for expression in [
+            r#"repeat('a')"#,
+            r#"'a'.repeat('b')"#,
+            r#"is_after('a')"#,
+            r#"repeat_range('a', 3)"#,
+            r#"one_or_more()"#,
+        ] {
+            assert!(
+                matches!(from_anre(expression), Err(AnreError::SyntaxIncorrect(_))),
+                "{}",
+                expression
+            );
+        }
+    }
+}
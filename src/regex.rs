@@ -4,49 +4,336 @@
 // the Mozilla Public License version 2.0 and additional exceptions.
 // For more details, see the LICENSE, LICENSE.additional, and CONTRIBUTING files.
 
-use std::ops::{Index, Range};
+use std::{
+    borrow::Cow,
+    ops::{Index, Range},
+    sync::atomic::{AtomicUsize, Ordering},
+    time::Duration,
+};
 
 use crate::{
-    compiler::{compile_from_anre, compile_from_regex},
-    context::Context,
-    object_file::ObjectFile,
-    process::start_process,
-    AnreError,
+    compiler::{
+        compile_from_anre, compile_from_anre_with_options, compile_from_regex,
+        compile_from_regex_with_options, CompileOptions,
+    },
+    context::{Budget, Cache, CachePool, Context, MatchRange},
+    dfa::{self, DfaResult},
+    object_file::{ObjectFile, MAIN_ROUTE_INDEX},
+    optimizer, pikevm, process,
+    utf8reader::read_char,
+    AnreError, MatchError,
 };
 
+#[cfg(all(feature = "jit", target_arch = "x86_64", target_os = "linux"))]
+use crate::jit::{self, JitCode};
+
 pub struct Regex {
     pub object_file: ObjectFile,
+    engine: Engine,
+    limits: Limits,
+
+    // The unique id of the expression, it identifies the states
+    // of the lazy DFA in the caches.
+    id: usize,
+
+    // Whether the lazy DFA is used to find the matches
+    // before running the engine, see `RegexBuilder::lazy_dfa`.
+    use_lazy_dfa: bool,
+
+    // The caches for the methods which do not take a cache explicitly.
+    pool: CachePool,
+
+    #[cfg(all(feature = "jit", target_arch = "x86_64", target_os = "linux"))]
+    jit_code: Option<JitCode>,
+}
+
+// Whether the JIT engine is supported by the current build.
+const IS_JIT_AVAILABLE: bool = cfg!(all(
+    feature = "jit",
+    target_arch = "x86_64",
+    target_os = "linux"
+));
+
+// The next id of `Regex`.
+static NEXT_REGEX_ID: AtomicUsize = AtomicUsize::new(0);
+
+// The limits of each matching operation, see `RegexBuilder::step_limit`
+// and `RegexBuilder::time_limit`.
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub(crate) struct Limits {
+    pub(crate) step_limit: Option<usize>,
+    pub(crate) time_limit: Option<Duration>,
+}
+
+/// The matching engine.
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub enum Engine {
+    /// Selects the Pike VM if the expression has no back references
+    /// (and no "char_any" in the byte mode, see `bytes::RegexBuilder::unicode`),
+    /// otherwise selects the JIT engine if it is available,
+    /// or the backtracking engine.
+    #[default]
+    Auto,
+
+    /// The backtracking engine supports all features, but it may take
+    /// exponential time on some expressions, e.g. `(a+)+b`.
+    Backtracking,
+
+    /// The Pike VM engine takes linear time in the length of the text
+    /// (quadratic time if the expression has lookaround assertions),
+    /// but back references are not supported.
+    PikeVm,
+
+    /// The backtracking engine which runs the native x86-64 code compiled
+    /// from the expression. It requires the cargo feature `jit` and
+    /// the x86-64 Linux target, otherwise `Engine::Backtracking` is used instead.
+    Jit,
+}
+
+/// The anchoring of the matching, see `RegexBuilder::anchored`.
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub enum Anchored {
+    /// The match can start at any position of the text range.
+    #[default]
+    No,
+
+    /// The match must start at the start of the text range,
+    /// as if the expression starts with `^`.
+    Start,
+
+    /// The match must start at the start of the text range and
+    /// end at the end of the text range, i.e. the whole range matches.
+    Both,
+}
+
+impl Anchored {
+    pub(crate) fn is_fixed_start_position(&self) -> bool {
+        *self != Anchored::No
+    }
+
+    pub(crate) fn is_fixed_end_position(&self) -> bool {
+        *self == Anchored::Both
+    }
 }
 
 impl Regex {
     pub fn new(pattern: &str) -> Result<Self, AnreError> {
-        let object_file = compile_from_regex(pattern)?;
-        Ok(Regex { object_file })
+        let mut object_file = compile_from_regex(pattern)?;
+        optimizer::optimize(&mut object_file);
+        Regex::with_engine(object_file, Engine::Auto, Limits::default(), true)
     }
 
     pub fn from_anre(expression: &str) -> Result<Self, AnreError> {
-        let object_file = compile_from_anre(expression)?;
-        Ok(Regex { object_file })
+        let mut object_file = compile_from_anre(expression)?;
+        optimizer::optimize(&mut object_file);
+        Regex::with_engine(object_file, Engine::Auto, Limits::default(), true)
     }
 
+    /// Creates a regex from the object file, e.g. the one loaded by
+    /// `ObjectFile::from_bytes`.
+    ///
+    /// The object file is used as it is, i.e. it is not optimized again.
+    ///
+    /// Returns `AnreError::InvalidObjectFile` if the object file matches
+    /// a single byte (i.e. the "char_any" of `bytes::Regex` in the non-Unicode mode),
+    /// since the matches may split a char.
+    pub fn from_object_file(object_file: ObjectFile) -> Result<Self, AnreError> {
+        if object_file.has_byte_transition() {
+            return Err(AnreError::InvalidObjectFile(
+                "The object file matches bytes, which is not supported by the string regex."
+                    .to_owned(),
+            ));
+        }
+
+        Regex::with_engine(object_file, Engine::Auto, Limits::default(), true)
+    }
+
+    fn with_engine(
+        object_file: ObjectFile,
+        engine: Engine,
+        limits: Limits,
+        lazy_dfa: bool,
+    ) -> Result<Self, AnreError> {
+        // The Pike VM moves all threads forward one char at a time, so
+        // the "char_any" which matches a single byte is not supported either.
+        let has_back_reference = object_file.has_back_reference();
+        let has_byte_transition = object_file.has_byte_transition();
+        let requires_backtracking = has_back_reference || has_byte_transition;
+
+        let engine = match engine {
+            Engine::Auto if requires_backtracking && IS_JIT_AVAILABLE => Engine::Jit,
+            Engine::Auto if requires_backtracking => Engine::Backtracking,
+            Engine::Auto => Engine::PikeVm,
+            Engine::PikeVm if has_back_reference => {
+                return Err(AnreError::SyntaxIncorrect(
+                    "The Pike VM engine does not support back references.".to_owned(),
+                ));
+            }
+            Engine::PikeVm if has_byte_transition => {
+                return Err(AnreError::SyntaxIncorrect(
+                    "The Pike VM engine does not support matching any byte.".to_owned(),
+                ));
+            }
+            Engine::Jit if !IS_JIT_AVAILABLE => Engine::Backtracking,
+            _ => engine,
+        };
+
+        // The steps of the lazy DFA are not counted, so it is not used
+        // if the limits are set.
+        let id = NEXT_REGEX_ID.fetch_add(1, Ordering::Relaxed);
+        let use_lazy_dfa =
+            lazy_dfa && limits == Limits::default() && dfa::is_supported(&object_file);
+
+        #[cfg(all(feature = "jit", target_arch = "x86_64", target_os = "linux"))]
+        {
+            let jit_code = if engine == Engine::Jit {
+                JitCode::compile(&object_file)
+            } else {
+                None
+            };
+
+            // Falls back to the backtracking engine if
+            // the executable memory can not be allocated.
+            let engine = if engine == Engine::Jit && jit_code.is_none() {
+                Engine::Backtracking
+            } else {
+                engine
+            };
+
+            Ok(Regex {
+                object_file,
+                engine,
+                limits,
+                id,
+                use_lazy_dfa,
+                pool: CachePool::default(),
+                jit_code,
+            })
+        }
+
+        #[cfg(not(all(feature = "jit", target_arch = "x86_64", target_os = "linux")))]
+        Ok(Regex {
+            object_file,
+            engine,
+            limits,
+            id,
+            use_lazy_dfa,
+            pool: CachePool::default(),
+        })
+    }
+
+    /// Returns the engine selected for matching, it is
+    /// `Engine::Backtracking`, `Engine::PikeVm` or `Engine::Jit`.
+    pub fn engine(&self) -> Engine {
+        self.engine
+    }
+
+    /// Returns the leftmost-first match.
+    ///
+    /// If the limits (see `RegexBuilder::step_limit` and `RegexBuilder::time_limit`)
+    /// are exceeded, `None` is returned, use `try_find` to tell it from no match.
     pub fn find<'a, 'b>(&'a self, text: &'b str) -> Option<Match<'a, 'b>> {
+        self.try_find(text).unwrap_or(None)
+    }
+
+    /// Returns the leftmost-first match, or an error if the limits are exceeded.
+    pub fn try_find<'a, 'b>(&'a self, text: &'b str) -> Result<Option<Match<'a, 'b>>, MatchError> {
+        self.try_search(text, 0..text.len())
+    }
+
+    /// Returns the leftmost-first match which starts at or after the position `start`.
+    ///
+    /// Unlike `find(&text[start..])`, the assertions can still see the text
+    /// before `start`, e.g. `\bfoo` does not match "xfoo" at the position 1,
+    /// and the positions of the match are relative to the whole text.
+    ///
+    /// Panics if `start` is not at a char boundary of the text.
+    pub fn find_at<'a, 'b>(&'a self, text: &'b str, start: usize) -> Option<Match<'a, 'b>> {
+        self.search(text, start..text.len())
+    }
+
+    /// Returns the leftmost-first match within the range of the text.
+    ///
+    /// The match must be entirely inside the range, but the assertions
+    /// (e.g. the lookbehind, the lookahead and `is_bound`) can still see
+    /// the text outside the range, so a sub-slice of a large buffer can be
+    /// searched without losing the context.
+    ///
+    /// Panics if the range is not at char boundaries of the text.
+    pub fn search<'a, 'b>(&'a self, text: &'b str, range: Range<usize>) -> Option<Match<'a, 'b>> {
+        self.try_search(text, range).unwrap_or(None)
+    }
+
+    /// Returns the leftmost-first match within the range of the text,
+    /// or an error if the limits are exceeded.
+    pub fn try_search<'a, 'b>(
+        &'a self,
+        text: &'b str,
+        range: Range<usize>,
+    ) -> Result<Option<Match<'a, 'b>>, MatchError> {
+        self.try_search_anchored(text, range, Anchored::No)
+    }
+
+    fn try_search_anchored<'a, 'b>(
+        &'a self,
+        text: &'b str,
+        range: Range<usize>,
+        anchored: Anchored,
+    ) -> Result<Option<Match<'a, 'b>>, MatchError> {
+        check_range(text, &range);
+
         let bytes = text.as_bytes();
-        let number_of_capture_groups = self.object_file.capture_group_names.len();
-        let mut context = Context::from_bytes(bytes, number_of_capture_groups);
+        self.execute_pooled(bytes, range, anchored, |context| {
+            self.new_match(bytes, 0, &context.match_ranges[0])
+        })
+    }
 
-        if !start_process(&mut context, &self.object_file, 0) {
-            return None;
-        }
+    /// Returns the leftmost-first match within the range of the text,
+    /// or an error if the limits are exceeded.
+    ///
+    /// The buffers of the matching are kept in the cache for the next call,
+    /// instead of the internal pool of caches, it saves the locking of
+    /// the pool in a hot loop.
+    ///
+    /// e.g.
+    ///
+    /// ```
+    /// use regex_anre::{Cache, Regex};
+    ///
+    /// let re = Regex::new(r"\d+").unwrap();
+    /// let mut cache = Cache::new();
+    ///
+    /// let count = ["a1", "b", "c23"]
+    ///     .iter()
+    ///     .filter(|text| re.try_search_with(&mut cache, text, 0..text.len()).unwrap().is_some())
+    ///     .count();
+    /// assert_eq!(count, 2);
+    /// ```
+    pub fn try_search_with<'a, 'b>(
+        &'a self,
+        cache: &mut Cache,
+        text: &'b str,
+        range: Range<usize>,
+    ) -> Result<Option<Match<'a, 'b>>, MatchError> {
+        check_range(text, &range);
 
-        let match_range = &context.match_ranges[0];
-        let match_ = Match::new(
-            match_range.start,
-            match_range.end,
-            self.object_file.get_capture_group_name_by_index(0),
-            sub_string(bytes, match_range.start, match_range.end),
-        );
+        let bytes = text.as_bytes();
+        self.execute_with_cache(cache, bytes, range, Anchored::No, |context| {
+            self.new_match(bytes, 0, &context.match_ranges[0])
+        })
+    }
 
-        Some(match_)
+    /// Returns whether the text matches within the range with the cache,
+    /// see `try_search_with`.
+    pub fn try_is_match_with(
+        &self,
+        cache: &mut Cache,
+        text: &str,
+        range: Range<usize>,
+    ) -> Result<bool, MatchError> {
+        check_range(text, &range);
+
+        self.is_match_with_cache(cache, text.as_bytes(), range, Anchored::No)
     }
 
     pub fn find_iter<'a, 'b>(&'a self, text: &'b str) -> Matches<'a, 'b> {
@@ -54,33 +341,109 @@ impl Regex {
         let number_of_capture_groups = self.object_file.capture_group_names.len();
         let context = Context::from_bytes(bytes, number_of_capture_groups);
 
-        Matches::new(&self.object_file, context)
+        Matches::new(self, context)
     }
 
     pub fn captures<'a, 'b>(&'a self, text: &'b str) -> Option<Captures<'a, 'b>> {
+        self.try_captures(text).unwrap_or(None)
+    }
+
+    /// Returns the capture groups of the leftmost-first match,
+    /// or an error if the limits are exceeded.
+    pub fn try_captures<'a, 'b>(
+        &'a self,
+        text: &'b str,
+    ) -> Result<Option<Captures<'a, 'b>>, MatchError> {
+        self.try_search_captures(text, 0..text.len())
+    }
+
+    /// Returns the capture groups of the leftmost-first match which
+    /// starts at or after the position `start`, see `find_at`.
+    pub fn captures_at<'a, 'b>(&'a self, text: &'b str, start: usize) -> Option<Captures<'a, 'b>> {
+        self.search_captures(text, start..text.len())
+    }
+
+    /// Returns the capture groups of the leftmost-first match
+    /// within the range of the text, see `search`.
+    pub fn search_captures<'a, 'b>(
+        &'a self,
+        text: &'b str,
+        range: Range<usize>,
+    ) -> Option<Captures<'a, 'b>> {
+        self.try_search_captures(text, range).unwrap_or(None)
+    }
+
+    /// Returns the capture groups of the leftmost-first match within
+    /// the range of the text, or an error if the limits are exceeded.
+    pub fn try_search_captures<'a, 'b>(
+        &'a self,
+        text: &'b str,
+        range: Range<usize>,
+    ) -> Result<Option<Captures<'a, 'b>>, MatchError> {
+        check_range(text, &range);
+
         let bytes = text.as_bytes();
-        let number_of_capture_groups = self.object_file.capture_group_names.len();
-        let mut context = Context::from_bytes(bytes, number_of_capture_groups);
+        self.execute_pooled(bytes, range, Anchored::No, |context| {
+            let matches: Vec<Match> = context
+                .match_ranges
+                .iter()
+                .enumerate()
+                .map(|(idx, match_range)| self.new_match(bytes, idx, match_range))
+                .collect();
+
+            Captures { matches }
+        })
+    }
 
-        if !start_process(&mut context, &self.object_file, 0) {
-            return None;
+    /// Creates the reusable locations of the capture groups, see `captures_read`.
+    pub fn capture_locations(&self) -> CaptureLocations {
+        let number_of_capture_groups = self.object_file.capture_group_names.len();
+        CaptureLocations {
+            match_ranges: vec![MatchRange::default(); number_of_capture_groups],
         }
+    }
 
-        let matches: Vec<Match> = context
-            .match_ranges
-            .iter()
-            .enumerate()
-            .map(|(idx, match_range)| {
-                Match::new(
-                    match_range.start,
-                    match_range.end,
-                    self.object_file.get_capture_group_name_by_index(idx),
-                    sub_string(bytes, match_range.start, match_range.end),
-                )
-            })
-            .collect();
+    /// Writes the positions of the capture groups of the leftmost-first match
+    /// to `locations`, and returns the overall match.
+    ///
+    /// Unlike `captures`, no `Captures` is allocated for each call,
+    /// the locations can be reused between calls.
+    pub fn captures_read<'a, 'b>(
+        &'a self,
+        locations: &mut CaptureLocations,
+        text: &'b str,
+    ) -> Option<Match<'a, 'b>> {
+        self.captures_read_at(locations, text, 0)
+    }
 
-        Some(Captures { matches })
+    /// Writes the positions of the capture groups of the leftmost-first match
+    /// which starts at or after the position `start`, see `captures_read` and `find_at`.
+    pub fn captures_read_at<'a, 'b>(
+        &'a self,
+        locations: &mut CaptureLocations,
+        text: &'b str,
+        start: usize,
+    ) -> Option<Match<'a, 'b>> {
+        self.try_captures_read_at(locations, text, start)
+            .unwrap_or(None)
+    }
+
+    /// Writes the positions of the capture groups of the leftmost-first match,
+    /// or returns an error if the limits are exceeded.
+    pub fn try_captures_read_at<'a, 'b>(
+        &'a self,
+        locations: &mut CaptureLocations,
+        text: &'b str,
+        start: usize,
+    ) -> Result<Option<Match<'a, 'b>>, MatchError> {
+        let range = start..text.len();
+        check_range(text, &range);
+
+        let bytes = text.as_bytes();
+        self.execute_pooled(bytes, range, Anchored::No, |context| {
+            locations.match_ranges.clone_from(&context.match_ranges);
+            self.new_match(bytes, 0, &context.match_ranges[0])
+        })
     }
 
     pub fn captures_iter<'a, 'b>(&'a self, text: &'b str) -> CaptureMatches<'a, 'b> {
@@ -88,29 +451,585 @@ impl Regex {
         let number_of_capture_groups = self.object_file.capture_group_names.len();
         let context = Context::from_bytes(bytes, number_of_capture_groups);
 
-        CaptureMatches::new(&self.object_file, context)
+        CaptureMatches::new(self, context)
     }
 
+    /// Returns an iterator over the substrings of `text` delimited by the matches.
+    pub fn split<'a, 'b>(&'a self, text: &'b str) -> Split<'a, 'b> {
+        Split {
+            text,
+            matches: self.find_iter(text),
+            last_position: 0,
+        }
+    }
+
+    /// Returns an iterator of at most `limit` substrings of `text` delimited by the matches.
+    /// The last substring is the remaining text which is not split.
+    pub fn splitn<'a, 'b>(&'a self, text: &'b str, limit: usize) -> SplitN<'a, 'b> {
+        SplitN {
+            split: self.split(text),
+            limit,
+        }
+    }
+
+    /// Returns `false` if the limits are exceeded,
+    /// use `try_is_match` to tell it from no match.
     pub fn is_match(&self, text: &str) -> bool {
-        let bytes = text.as_bytes();
+        self.try_is_match(text).unwrap_or(false)
+    }
+
+    /// Returns whether the text matches, or an error if the limits are exceeded.
+    pub fn try_is_match(&self, text: &str) -> Result<bool, MatchError> {
+        self.try_is_match_in(text, 0..text.len())
+    }
+
+    /// Returns whether the text matches at or after the position `start`, see `find_at`.
+    pub fn is_match_at(&self, text: &str, start: usize) -> bool {
+        self.try_is_match_in(text, start..text.len())
+            .unwrap_or(false)
+    }
+
+    /// Returns whether the text matches within the range, see `search`.
+    pub fn is_match_in(&self, text: &str, range: Range<usize>) -> bool {
+        self.try_is_match_in(text, range).unwrap_or(false)
+    }
+
+    /// Returns whether the text matches within the range,
+    /// or an error if the limits are exceeded.
+    pub fn try_is_match_in(&self, text: &str, range: Range<usize>) -> Result<bool, MatchError> {
+        check_range(text, &range);
+
+        self.is_match_pooled(text.as_bytes(), range, Anchored::No)
+    }
+
+    /// Returns whether the whole text matches, as if the expression
+    /// is `^...$`, but without rewriting the expression.
+    ///
+    /// e.g. `a|ab` does not match "ab" by `find` as a whole, since
+    /// the leftmost-first match is "a", but "ab" is a full match.
+    pub fn is_full_match(&self, text: &str) -> bool {
+        self.try_is_full_match(text).unwrap_or(false)
+    }
+
+    /// Returns whether the whole text matches, or an error if the limits are exceeded.
+    pub fn try_is_full_match(&self, text: &str) -> Result<bool, MatchError> {
+        self.is_match_pooled(text.as_bytes(), 0..text.len(), Anchored::Both)
+    }
+
+    /// Returns the match which covers the whole text, see `is_full_match`.
+    pub fn full_match<'a, 'b>(&'a self, text: &'b str) -> Option<Match<'a, 'b>> {
+        self.try_full_match(text).unwrap_or(None)
+    }
+
+    /// Returns the match which covers the whole text,
+    /// or an error if the limits are exceeded.
+    pub fn try_full_match<'a, 'b>(
+        &'a self,
+        text: &'b str,
+    ) -> Result<Option<Match<'a, 'b>>, MatchError> {
+        self.try_search_anchored(text, 0..text.len(), Anchored::Both)
+    }
+
+    // Runs the engine within the range with the buffers of the cache,
+    // `on_match` reads the result from the context if the text matches.
+    pub(crate) fn execute_with_cache<'b, T>(
+        &self,
+        cache: &mut Cache,
+        bytes: &'b [u8],
+        range: Range<usize>,
+        anchored: Anchored,
+        on_match: impl FnOnce(&Context<'b>) -> T,
+    ) -> Result<Option<T>, MatchError> {
+        let number_of_capture_groups = self.object_file.capture_group_names.len();
+        let mut context =
+            Context::from_cache(bytes, number_of_capture_groups, std::mem::take(cache));
+
+        let result = execute_anchored(self, &mut context, range.start, range.end, anchored)
+            .map(|is_match| is_match.then(|| on_match(&context)));
+
+        *cache = context.into_cache();
+        result
+    }
+
+    // The same as `execute_with_cache`, but uses a cache of the internal pool,
+    // so the `Regex` can be shared by threads.
+    pub(crate) fn execute_pooled<'b, T>(
+        &self,
+        bytes: &'b [u8],
+        range: Range<usize>,
+        anchored: Anchored,
+        on_match: impl FnOnce(&Context<'b>) -> T,
+    ) -> Result<Option<T>, MatchError> {
+        let mut cache = self.pool.get();
+        let result = self.execute_with_cache(&mut cache, bytes, range, anchored, on_match);
+        self.pool.put(cache);
+        result
+    }
+
+    // Returns whether the text matches within the range, unlike
+    // `execute_with_cache`, the positions of the match are not resolved.
+    pub(crate) fn is_match_with_cache(
+        &self,
+        cache: &mut Cache,
+        bytes: &[u8],
+        range: Range<usize>,
+        anchored: Anchored,
+    ) -> Result<bool, MatchError> {
         let number_of_capture_groups = self.object_file.capture_group_names.len();
-        let mut context = Context::from_bytes(bytes, number_of_capture_groups);
-        start_process(&mut context, &self.object_file, 0)
+        let mut context =
+            Context::from_cache(bytes, number_of_capture_groups, std::mem::take(cache));
+
+        let result = is_match_anchored(self, &mut context, range.start, range.end, anchored);
+
+        *cache = context.into_cache();
+        result
+    }
+
+    // The same as `is_match_with_cache`, but uses a cache of the internal pool.
+    pub(crate) fn is_match_pooled(
+        &self,
+        bytes: &[u8],
+        range: Range<usize>,
+        anchored: Anchored,
+    ) -> Result<bool, MatchError> {
+        let mut cache = self.pool.get();
+        let result = self.is_match_with_cache(&mut cache, bytes, range, anchored);
+        self.pool.put(cache);
+        result
+    }
+
+    fn new_match<'a, 'b>(
+        &'a self,
+        bytes: &'b [u8],
+        capture_group_index: usize,
+        match_range: &MatchRange,
+    ) -> Match<'a, 'b> {
+        Match::new(
+            match_range.start,
+            match_range.end,
+            self.object_file
+                .get_capture_group_name_by_index(capture_group_index),
+            sub_string(bytes, match_range.start, match_range.end),
+        )
+    }
+
+    // the following methods are intended to
+    // be compatible with the replacement API of crate 'regex':
+    // https://docs.rs/regex/latest/regex/struct.Regex.html#method.replace
+
+    /// Replaces the leftmost-first match with the replacement provided.
+    ///
+    /// If no match is found, the text is returned unchanged (and borrowed).
+    pub fn replace<'b, R: Replacer>(&self, text: &'b str, rep: R) -> Cow<'b, str> {
+        self.replacen(text, 1, rep)
+    }
+
+    /// Replaces all non-overlapping matches with the replacement provided.
+    pub fn replace_all<'b, R: Replacer>(&self, text: &'b str, rep: R) -> Cow<'b, str> {
+        self.replacen(text, 0, rep)
+    }
+
+    /// Replaces at most `limit` non-overlapping matches with the replacement provided.
+    /// If `limit` is 0, then all non-overlapping matches are replaced.
+    pub fn replacen<'b, R: Replacer>(
+        &self,
+        text: &'b str,
+        limit: usize,
+        mut rep: R,
+    ) -> Cow<'b, str> {
+        // A literal replacement does not need the capture groups,
+        // so the cheaper `find_iter` is used.
+        if let Some(literal) = rep.no_expansion() {
+            let mut matches = self.find_iter(text).peekable();
+            if matches.peek().is_none() {
+                return Cow::Borrowed(text);
+            }
+
+            let mut new = String::with_capacity(text.len());
+            let mut last_end = 0;
+            for (idx, m) in matches.enumerate() {
+                if limit > 0 && idx >= limit {
+                    break;
+                }
+                new.push_str(&text[last_end..m.start]);
+                new.push_str(&literal);
+                last_end = m.end;
+            }
+            new.push_str(&text[last_end..]);
+            return Cow::Owned(new);
+        }
+
+        let mut captures_iter = self.captures_iter(text).peekable();
+        if captures_iter.peek().is_none() {
+            return Cow::Borrowed(text);
+        }
+
+        let mut new = String::with_capacity(text.len());
+        let mut last_end = 0;
+        for (idx, captures) in captures_iter.enumerate() {
+            if limit > 0 && idx >= limit {
+                break;
+            }
+            let m = &captures.matches[0];
+            new.push_str(&text[last_end..m.start]);
+            rep.replace_append(&captures, &mut new);
+            last_end = m.end;
+        }
+        new.push_str(&text[last_end..]);
+        Cow::Owned(new)
+    }
+}
+
+/// A builder for configuring the options of a `Regex`.
+pub struct RegexBuilder {
+    pattern: String,
+    is_anre: bool,
+    pub(crate) options: CompileOptions,
+    engine: Engine,
+    limits: Limits,
+    anchored: Anchored,
+    lazy_dfa: bool,
+    optimize: bool,
+}
+
+impl RegexBuilder {
+    /// Creates a builder for the traditional regular expression.
+    pub fn new(pattern: &str) -> Self {
+        RegexBuilder {
+            pattern: pattern.to_owned(),
+            is_anre: false,
+            options: CompileOptions::default(),
+            engine: Engine::Auto,
+            limits: Limits::default(),
+            anchored: Anchored::No,
+            lazy_dfa: true,
+            optimize: true,
+        }
+    }
+
+    /// Creates a builder for the ANRE expression.
+    pub fn from_anre(expression: &str) -> Self {
+        RegexBuilder {
+            pattern: expression.to_owned(),
+            is_anre: true,
+            options: CompileOptions::default(),
+            engine: Engine::Auto,
+            limits: Limits::default(),
+            anchored: Anchored::No,
+            lazy_dfa: true,
+            optimize: true,
+        }
+    }
+
+    /// Letters match both their uppercase and lowercase forms.
+    pub fn case_insensitive(&mut self, yes: bool) -> &mut Self {
+        self.options.case_insensitive = yes;
+        self
+    }
+
+    /// The assertions "start" (`^`) and "end" (`$`) also match at
+    /// the beginning and end of lines.
+    pub fn multi_line(&mut self, yes: bool) -> &mut Self {
+        self.options.multi_line = yes;
+        self
+    }
+
+    /// The "char_any" (`.`) also matches '\n' and '\r'.
+    pub fn dot_matches_new_line(&mut self, yes: bool) -> &mut Self {
+        self.options.dot_matches_new_line = yes;
+        self
+    }
+
+    /// Greedy quantifiers become lazy and lazy quantifiers become greedy.
+    pub fn swap_greed(&mut self, yes: bool) -> &mut Self {
+        self.options.swap_greed = yes;
+        self
+    }
+
+    /// In multi-line mode, '\r' is also treated as a line terminator,
+    /// and "\r\n" is treated as a single line terminator.
+    pub fn crlf(&mut self, yes: bool) -> &mut Self {
+        self.options.crlf = yes;
+        self
+    }
+
+    /// Anchors every match of the expression, the default is `Anchored::No`.
+    ///
+    /// With `Anchored::Start` the match must start at the start of
+    /// the text range (e.g. the `start` of `find_at`), and with `Anchored::Both`
+    /// it must also end at the end of the text range, the same as
+    /// `^...` and `^...$` but without rewriting the expression,
+    /// so the `^` and `$` in the multi-line mode are not affected.
+    pub fn anchored(&mut self, anchored: Anchored) -> &mut Self {
+        self.anchored = anchored;
+        self
+    }
+
+    /// Sets the matching engine, the default is `Engine::Auto`.
+    ///
+    /// Forcing `Engine::PikeVm` on an expression with back references
+    /// causes an error when building.
+    pub fn engine(&mut self, engine: Engine) -> &mut Self {
+        self.engine = engine;
+        self
+    }
+
+    /// Limits the number of executed transitions of each matching operation,
+    /// including the transitions of the lookaround assertions.
+    ///
+    /// Once the limit is exceeded, the matching is aborted, and the
+    /// `try_*` methods (e.g. `Regex::try_find`) return `MatchError::BudgetExceeded`.
+    pub fn step_limit(&mut self, limit: usize) -> &mut Self {
+        self.limits.step_limit = Some(limit);
+        self
+    }
+
+    /// Limits the time of each matching operation.
+    ///
+    /// Once the limit is exceeded, the matching is aborted, and the
+    /// `try_*` methods (e.g. `Regex::try_find`) return `MatchError::Timeout`.
+    pub fn time_limit(&mut self, limit: Duration) -> &mut Self {
+        self.limits.time_limit = Some(limit);
+        self
+    }
+
+    /// Uses the lazy DFA to find the matches before running the engine,
+    /// the default is `true`.
+    ///
+    /// The lazy DFA builds its states from the expression on demand while
+    /// scanning the text, it checks each char only once, so it is much faster
+    /// than the engines on a long text, e.g. for `is_match`. When a match is found,
+    /// the engine runs only on the range of the match to resolve the capture groups.
+    ///
+    /// It is not used for the expressions with lookaround assertions or back references,
+    /// and it is not used if the limits (see `step_limit` and `time_limit`) are set,
+    /// since its steps are not counted.
+    pub fn lazy_dfa(&mut self, yes: bool) -> &mut Self {
+        self.lazy_dfa = yes;
+        self
+    }
+
+    /// Optimizes the compiled expression, the default is `true`.
+    ///
+    /// The optimizer skips the jump-only nodes, fuses the adjacent chars into
+    /// strings, and sorts the char sets so they can be looked up quickly.
+    /// The numbers of nodes and transitions before and after the optimization
+    /// are shown in the debug text of the object file.
+    pub fn optimize(&mut self, yes: bool) -> &mut Self {
+        self.optimize = yes;
+        self
+    }
+
+    pub fn build(&self) -> Result<Regex, AnreError> {
+        let mut object_file = if self.is_anre {
+            compile_from_anre_with_options(&self.pattern, &self.options)?
+        } else {
+            compile_from_regex_with_options(&self.pattern, &self.options)?
+        };
+
+        let route = &mut object_file.routes[MAIN_ROUTE_INDEX];
+        route.is_fixed_start_position |= self.anchored.is_fixed_start_position();
+        route.is_fixed_end_position |= self.anchored.is_fixed_end_position();
+
+        if self.optimize {
+            optimizer::optimize(&mut object_file);
+        }
+
+        Regex::with_engine(object_file, self.engine, self.limits, self.lazy_dfa)
+    }
+}
+
+// Checks that the search range is inside the text and at the char boundaries.
+fn check_range(text: &str, range: &Range<usize>) {
+    assert!(
+        range.start <= range.end
+            && range.end <= text.len()
+            && text.is_char_boundary(range.start)
+            && text.is_char_boundary(range.end),
+        "The range {:?} is invalid for the text of length {}.",
+        range,
+        text.len()
+    );
+}
+
+// Runs the selected engine within the range `start_position..end_position`,
+// the result is written to `context.match_ranges`.
+//
+// Each call has its own budget, e.g. each `next()` of the iterators.
+pub(crate) fn execute(
+    regex: &Regex,
+    context: &mut Context,
+    start_position: usize,
+    end_position: usize,
+) -> Result<bool, MatchError> {
+    execute_anchored(regex, context, start_position, end_position, Anchored::No)
+}
+
+// Runs the selected engine with the additional anchoring,
+// e.g. `Anchored::Both` for the full match.
+pub(crate) fn execute_anchored(
+    regex: &Regex,
+    context: &mut Context,
+    start_position: usize,
+    end_position: usize,
+    anchored: Anchored,
+) -> Result<bool, MatchError> {
+    if !regex.use_lazy_dfa {
+        return run_engine(regex, context, start_position, end_position, anchored);
+    }
+
+    let object_file = &regex.object_file;
+    match dfa::search(
+        context,
+        object_file,
+        regex.id,
+        start_position,
+        end_position,
+        anchored,
+        false,
+    ) {
+        DfaResult::NoMatch => Ok(false),
+        DfaResult::Match { window_start, end } => {
+            // The engine runs only on the range of the match
+            // to resolve the capture groups.
+            run_engine(regex, context, window_start, end, anchored)
+        }
+        DfaResult::GaveUp => run_engine(regex, context, start_position, end_position, anchored),
+    }
+}
+
+// Checks whether the text matches with the additional anchoring,
+// the lazy DFA (if it is available) stops at the first match found.
+fn is_match_anchored(
+    regex: &Regex,
+    context: &mut Context,
+    start_position: usize,
+    end_position: usize,
+    anchored: Anchored,
+) -> Result<bool, MatchError> {
+    if regex.use_lazy_dfa {
+        match dfa::search(
+            context,
+            &regex.object_file,
+            regex.id,
+            start_position,
+            end_position,
+            anchored,
+            true,
+        ) {
+            DfaResult::NoMatch => return Ok(false),
+            DfaResult::Match { .. } => return Ok(true),
+            DfaResult::GaveUp => {}
+        }
+    }
+
+    run_engine(regex, context, start_position, end_position, anchored)
+}
+
+fn run_engine(
+    regex: &Regex,
+    context: &mut Context,
+    start_position: usize,
+    end_position: usize,
+    anchored: Anchored,
+) -> Result<bool, MatchError> {
+    let limits = regex.limits;
+    let object_file = &regex.object_file;
+    context.budget = Budget::new(limits.step_limit, limits.time_limit);
+
+    let result = match regex.engine {
+        Engine::PikeVm => {
+            pikevm::start_process(context, object_file, start_position, end_position, anchored)
+        }
+
+        #[cfg(all(feature = "jit", target_arch = "x86_64", target_os = "linux"))]
+        Engine::Jit => {
+            let jit_code = regex.jit_code.as_ref().unwrap();
+            jit::start_process(
+                context,
+                object_file,
+                jit_code,
+                start_position,
+                end_position,
+                anchored,
+            )
+        }
+
+        _ => process::start_process(context, object_file, start_position, end_position, anchored),
+    };
+
+    match context.budget.error.take() {
+        Some(error) => Err(error),
+        None => Ok(result),
+    }
+}
+
+// The position state of the iterators over the successive matches.
+//
+// The empty matches are handled in the same way as the crate 'regex':
+// - The search moves forward by one char after an empty match,
+//   otherwise the same empty match would be found again and again.
+// - An empty match right after the previous match is skipped, e.g.
+//   the matches of `a*` in "baaac" are `0..0`, `1..4` and `5..5`,
+//   the empty match at `4..4` is skipped.
+pub(crate) struct MatchCursor {
+    last_position: usize,          // The start position of the next search.
+    last_match_end: Option<usize>, // The end position of the previous match.
+}
+
+impl MatchCursor {
+    pub(crate) fn new() -> Self {
+        MatchCursor {
+            last_position: 0,
+            last_match_end: None,
+        }
+    }
+
+    // Finds the next match, the result is written to `context.match_ranges`.
+    //
+    // Returns `false` if there is no more match or the limits are exceeded.
+    pub(crate) fn next(&mut self, regex: &Regex, context: &mut Context) -> bool {
+        loop {
+            if self.last_position > context.bytes.len()
+                || !execute(regex, context, self.last_position, context.bytes.len())
+                    .unwrap_or(false)
+            {
+                return false;
+            }
+
+            let match_range = &context.match_ranges[0];
+            let (start, end) = (match_range.start, match_range.end);
+
+            if start == end && self.last_match_end == Some(end) {
+                // Skip the empty match, and search again from the next char.
+                self.last_position = if end < context.bytes.len() {
+                    let (_, byte_length) = read_char(context.bytes, end);
+                    end + byte_length
+                } else {
+                    end + 1
+                };
+                continue;
+            }
+
+            self.last_position = end;
+            self.last_match_end = Some(end);
+            return true;
+        }
     }
 }
 
 pub struct CaptureMatches<'a, 'b> {
-    object_file: &'a ObjectFile,
+    regex: &'a Regex,
     context: Context<'b>,
-    last_position: usize,
+    cursor: MatchCursor,
 }
 
 impl<'a, 'b> CaptureMatches<'a, 'b> {
-    fn new(object_file: &'a ObjectFile, context: Context<'b>) -> Self {
+    fn new(regex: &'a Regex, context: Context<'b>) -> Self {
         CaptureMatches {
-            object_file,
+            regex,
             context,
-            last_position: 0,
+            cursor: MatchCursor::new(),
         }
     }
 }
@@ -119,7 +1038,8 @@ impl<'a, 'b> Iterator for CaptureMatches<'a, 'b> {
     type Item = Captures<'a, 'b>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        if !start_process(&mut self.context, self.object_file, self.last_position) {
+        // The iteration stops if the limits are exceeded.
+        if !self.cursor.next(self.regex, &mut self.context) {
             return None;
         }
 
@@ -132,30 +1052,28 @@ impl<'a, 'b> Iterator for CaptureMatches<'a, 'b> {
                 Match::new(
                     match_range.start,
                     match_range.end,
-                    self.object_file.get_capture_group_name_by_index(idx),
+                    self.regex.object_file.get_capture_group_name_by_index(idx),
                     sub_string(self.context.bytes, match_range.start, match_range.end),
                 )
             })
             .collect();
 
-        self.last_position = matches[0].end;
-
         Some(Captures { matches })
     }
 }
 
 pub struct Matches<'a, 'b> {
-    object_file: &'a ObjectFile,
+    regex: &'a Regex,
     context: Context<'b>,
-    last_position: usize,
+    cursor: MatchCursor,
 }
 
 impl<'a, 'b> Matches<'a, 'b> {
-    fn new(object_file: &'a ObjectFile, context: Context<'b>) -> Self {
+    fn new(regex: &'a Regex, context: Context<'b>) -> Self {
         Matches {
-            object_file,
+            regex,
             context,
-            last_position: 0,
+            cursor: MatchCursor::new(),
         }
     }
 }
@@ -164,7 +1082,8 @@ impl<'a, 'b> Iterator for Matches<'a, 'b> {
     type Item = Match<'a, 'b>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        if !start_process(&mut self.context, self.object_file, self.last_position) {
+        // The iteration stops if the limits are exceeded.
+        if !self.cursor.next(self.regex, &mut self.context) {
             return None;
         }
 
@@ -172,31 +1091,119 @@ impl<'a, 'b> Iterator for Matches<'a, 'b> {
         let match_ = Match::new(
             match_range.start,
             match_range.end,
-            self.object_file.get_capture_group_name_by_index(0),
+            self.regex.object_file.get_capture_group_name_by_index(0),
             sub_string(self.context.bytes, match_range.start, match_range.end),
         );
 
-        self.last_position = match_.end;
-
         Some(match_)
     }
 }
 
+// The behavior of the following iterators (e.g. the handling of the
+// leading and trailing empty pieces) is intended to be compatible with the
+// 'Split' and 'SplitN' of crate 'regex':
+// https://docs.rs/regex/latest/regex/struct.Split.html
+
+pub struct Split<'a, 'b> {
+    text: &'b str,
+    matches: Matches<'a, 'b>,
+
+    // The start position of the next piece.
+    // It is set to `text.len() + 1` when the last piece has been yielded.
+    last_position: usize,
+}
+
+impl<'b> Split<'_, 'b> {
+    fn remaining(&mut self) -> Option<&'b str> {
+        let length = self.text.len();
+        if self.last_position > length {
+            None
+        } else {
+            let piece = &self.text[self.last_position..];
+            self.last_position = length + 1;
+            Some(piece)
+        }
+    }
+}
+
+impl<'b> Iterator for Split<'_, 'b> {
+    type Item = &'b str;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.matches.next() {
+            Some(m) => {
+                let piece = &self.text[self.last_position..m.start];
+                self.last_position = m.end;
+                Some(piece)
+            }
+            None => self.remaining(),
+        }
+    }
+}
+
+pub struct SplitN<'a, 'b> {
+    split: Split<'a, 'b>,
+    limit: usize,
+}
+
+impl<'b> Iterator for SplitN<'_, 'b> {
+    type Item = &'b str;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.limit == 0 {
+            return None;
+        }
+
+        self.limit -= 1;
+        if self.limit > 0 {
+            self.split.next()
+        } else {
+            self.split.remaining()
+        }
+    }
+}
+
+/// The positions of the capture groups written by `Regex::captures_read`.
+///
+/// It is compatible with the 'CaptureLocations' API of crate 'regex':
+/// https://docs.rs/regex/latest/regex/struct.CaptureLocations.html
+#[derive(Debug, PartialEq, Clone)]
+pub struct CaptureLocations {
+    match_ranges: Vec<MatchRange>,
+}
+
+impl CaptureLocations {
+    /// Returns the start and end positions of the capture group.
+    pub fn get(&self, index: usize) -> Option<(usize, usize)> {
+        self.match_ranges
+            .get(index)
+            .map(|match_range| (match_range.start, match_range.end))
+    }
+
+    pub fn len(&self) -> usize {
+        self.match_ranges.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
 #[derive(Debug, PartialEq, Clone)]
 pub struct Captures<'a, 'b> {
     pub matches: Vec<Match<'a, 'b>>,
 }
 
-impl Captures<'_, '_> {
+impl<'a, 'b> Captures<'a, 'b> {
     // the following methods are intended to
     // be compatible with the 'Captures' API of crate 'regex':
     // https://docs.rs/regex/latest/regex/struct.Captures.html
 
-    pub fn get(&self, index: usize) -> Option<&Match> {
+    pub fn get(&self, index: usize) -> Option<&Match<'a, 'b>> {
         self.matches.get(index)
     }
 
-    pub fn name(&self, name: &str) -> Option<&Match> {
+    pub fn name(&self, name: &str) -> Option<&Match<'a, 'b>> {
         // Option<Match> {
         self.matches.iter().find(|item| match item.name {
             Some(s) => s == name,
@@ -204,26 +1211,197 @@ impl Captures<'_, '_> {
         })
     }
 
-    // e.g.
-    //
-    // ```
-    //   let c = re.find("...").next().unwrap();
-    //   let (whole, [one, two, three]) = c.extract();
-    // ```
-    pub fn extract<const N: usize>(&self) -> (&str, [&str; N]) {
-        let mut items: [&str; N] = [""; N];
-        for (idx, item) in items.iter_mut().enumerate() {
-            *item = self.matches[idx + 1].value;
-        }
-        (self.matches[0].value, items)
+    // e.g.
+    //
+    // ```
+    //   let c = re.find("...").next().unwrap();
+    //   let (whole, [one, two, three]) = c.extract();
+    // ```
+    pub fn extract<const N: usize>(&self) -> (&str, [&str; N]) {
+        let mut items: [&str; N] = [""; N];
+        for (idx, item) in items.iter_mut().enumerate() {
+            *item = self.matches[idx + 1].value;
+        }
+        (self.matches[0].value, items)
+    }
+
+    pub fn len(&self) -> usize {
+        self.matches.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    // Expands all instances of `$ref` in `replacement` to the corresponding
+    // capture group, and writes them to the `dst` buffer.
+    //
+    // - `$1`, `${1}`: the capture group by index.
+    // - `$name`, `${name}`: the capture group by name.
+    // - `$$`: a literal `$`.
+    //
+    // The reference `$name` takes the longest possible name consisting of
+    // the chars `[a-zA-Z0-9_]`, e.g. `$1a` refers to the group named "1a",
+    // use the braces form `${1}a` to separate the reference from the trailing text.
+    // A non-existent capture group is replaced by the empty string.
+    pub fn expand(&self, replacement: &str, dst: &mut String) {
+        let mut remain = replacement;
+        while let Some(pos) = remain.find('$') {
+            dst.push_str(&remain[..pos]);
+            remain = &remain[pos..];
+
+            if remain[1..].starts_with('$') {
+                // `$$`
+                dst.push('$');
+                remain = &remain[2..];
+                continue;
+            }
+
+            match parse_capture_reference(remain) {
+                Some((reference, length)) => {
+                    let opt_match = match reference {
+                        CaptureReference::Index(index) => self.get(index),
+                        CaptureReference::Name(name) => self.name(name),
+                    };
+                    if let Some(m) = opt_match {
+                        dst.push_str(m.as_str());
+                    }
+                    remain = &remain[length..];
+                }
+                None => {
+                    // not a valid reference, treat the `$` as literal.
+                    dst.push('$');
+                    remain = &remain[1..];
+                }
+            }
+        }
+        dst.push_str(remain);
+    }
+}
+
+enum CaptureReference<'a> {
+    Index(usize),
+    Name(&'a str),
+}
+
+// Parses the capture reference at the beginning of `s` (which starts with `$`).
+// Returns the reference and the length (in bytes) of the reference text.
+fn parse_capture_reference(s: &str) -> Option<(CaptureReference<'_>, usize)> {
+    let body = &s[1..];
+
+    let (name, length) = if let Some(braced) = body.strip_prefix('{') {
+        // `${name}`
+        let end = braced.find('}')?;
+        (&braced[..end], end + 3)
+    } else {
+        // `$name`
+        let end = body
+            .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+            .unwrap_or(body.len());
+        (&body[..end], end + 1)
+    };
+
+    if name.is_empty() {
+        return None;
+    }
+
+    let reference = match name.parse::<usize>() {
+        Ok(index) => CaptureReference::Index(index),
+        Err(_) => CaptureReference::Name(name),
+    };
+
+    Some((reference, length))
+}
+
+/// A trait for types that can be used to replace matches in a text.
+///
+/// It is implemented for:
+///
+/// - `&str`, `String` and `Cow<str>`: a replacement template which expands
+///   the capture references, e.g. `$1`, `${name}` and `$$`, see `Captures::expand`.
+/// - `FnMut(&Captures) -> T` where `T: AsRef<str>`: the returned text is appended as is.
+/// - `NoExpand`: a literal replacement text.
+pub trait Replacer {
+    /// Appends the replacement text for the given captures to `dst`.
+    fn replace_append(&mut self, captures: &Captures<'_, '_>, dst: &mut String);
+
+    /// Returns the literal replacement text if the replacement
+    /// does not depend on the captures.
+    fn no_expansion(&mut self) -> Option<Cow<'_, str>> {
+        None
+    }
+}
+
+impl Replacer for &str {
+    fn replace_append(&mut self, captures: &Captures<'_, '_>, dst: &mut String) {
+        captures.expand(self, dst);
+    }
+
+    fn no_expansion(&mut self) -> Option<Cow<'_, str>> {
+        no_expansion(self)
+    }
+}
+
+impl Replacer for &String {
+    fn replace_append(&mut self, captures: &Captures<'_, '_>, dst: &mut String) {
+        self.as_str().replace_append(captures, dst);
+    }
+
+    fn no_expansion(&mut self) -> Option<Cow<'_, str>> {
+        no_expansion(self)
+    }
+}
+
+impl Replacer for String {
+    fn replace_append(&mut self, captures: &Captures<'_, '_>, dst: &mut String) {
+        self.as_str().replace_append(captures, dst);
+    }
+
+    fn no_expansion(&mut self) -> Option<Cow<'_, str>> {
+        no_expansion(self)
+    }
+}
+
+impl Replacer for Cow<'_, str> {
+    fn replace_append(&mut self, captures: &Captures<'_, '_>, dst: &mut String) {
+        self.as_ref().replace_append(captures, dst);
+    }
+
+    fn no_expansion(&mut self) -> Option<Cow<'_, str>> {
+        no_expansion(self)
+    }
+}
+
+impl<F, T> Replacer for F
+where
+    F: FnMut(&Captures<'_, '_>) -> T,
+    T: AsRef<str>,
+{
+    fn replace_append(&mut self, captures: &Captures<'_, '_>, dst: &mut String) {
+        dst.push_str((*self)(captures).as_ref());
+    }
+}
+
+/// A replacement text which is used literally, i.e. the capture
+/// references such as `$1` are NOT expanded.
+pub struct NoExpand<'a>(pub &'a str);
+
+impl Replacer for NoExpand<'_> {
+    fn replace_append(&mut self, _captures: &Captures<'_, '_>, dst: &mut String) {
+        dst.push_str(self.0);
     }
 
-    pub fn len(&self) -> usize {
-        self.matches.len()
+    fn no_expansion(&mut self) -> Option<Cow<'_, str>> {
+        Some(Cow::Borrowed(self.0))
     }
+}
 
-    pub fn is_empty(&self) -> bool {
-        self.len() == 0
+fn no_expansion<T: AsRef<str>>(template: &T) -> Option<Cow<'_, str>> {
+    let s = template.as_ref();
+    if s.contains('$') {
+        None
+    } else {
+        Some(Cow::Borrowed(s))
     }
 }
 
@@ -312,10 +1490,17 @@ fn sub_string(bytes: &[u8], start: usize, end_excluded: usize) -> &str {
 
 #[cfg(test)]
 mod tests {
-    use super::{Captures, Match, Regex};
+    use std::{borrow::Cow, time::Duration};
+
+    use super::{
+        Anchored, Cache, Captures, Engine, Match, NoExpand, Regex, RegexBuilder, IS_JIT_AVAILABLE,
+    };
+    use crate::object_file::ObjectFile;
+    use crate::traditional;
+    use crate::{AnreError, MatchError};
     use pretty_assertions::assert_eq;
 
-    fn new_match(start: usize, end: usize, value: &str) -> Match {
+    fn new_match(start: usize, end: usize, value: &str) -> Match<'_, '_> {
         Match::new(start, end, None, value)
     }
 
@@ -1472,6 +2657,11 @@ mod tests {
                 ]))
             );
         }
+
+        // the back reference inside the group it refers to, the range of
+        // the group is reversed since the group is not closed yet
+        let re = Regex::new(r#"(a\1)"#).unwrap();
+        assert_eq!(re.find("xaa"), None);
     }
 
     #[test]
@@ -1647,4 +2837,1141 @@ mod tests {
             assert_eq!(matches.next(), None);
         }
     }
+
+    #[test]
+    fn test_replace() {
+        // replace the first match
+        for re in generate_res(
+            r#"char_digit+"#, // ANRE
+            r#"\d+"#,         // traditional
+        ) {
+            assert_eq!(re.replace("abc123def456", "#"), "abc#def456");
+            assert_eq!(re.replace_all("abc123def456", "#"), "abc#def#");
+            assert_eq!(re.replacen("a1b2c3d4", 2, "#"), "a#b#c3d4");
+            assert_eq!(re.replacen("a1b2c3d4", 0, "#"), "a#b#c#d#");
+
+            // no match, the text is borrowed
+            assert!(matches!(re.replace_all("abc", "#"), Cow::Borrowed("abc")));
+        }
+
+        // expand the capture references
+        for re in generate_res(
+            r#"(char_digit+).name("year"), '-', (char_digit+).name("month")"#, // ANRE
+            r#"(?<year>\d+)-(?<month>\d+)"#,                                   // traditional
+        ) {
+            let text = "from 2025-04 to 2026-10.";
+
+            assert_eq!(
+                re.replace_all(text, "$month/$year"),
+                "from 04/2025 to 10/2026."
+            );
+            assert_eq!(
+                re.replace_all(text, "${2}_${1}"),
+                "from 04_2025 to 10_2026."
+            );
+            assert_eq!(re.replace(text, "$$$1"), "from $2025 to 2026-10.");
+
+            // `$1a` is the reference to the group named "1a", which does not exist.
+            assert_eq!(re.replace(text, "$1a"), "from  to 2026-10.");
+
+            // invalid references are kept literally
+            assert_eq!(re.replace(text, "$ ${}"), "from $ ${} to 2026-10.");
+
+            // no expansion
+            assert_eq!(
+                re.replace_all(text, NoExpand("$month/$year")),
+                "from $month/$year to $month/$year."
+            );
+
+            // closure
+            assert_eq!(
+                re.replace_all(text, |caps: &Captures| {
+                    format!("{}.{}", &caps["year"], &caps[2])
+                }),
+                "from 2025.04 to 2026.10."
+            );
+        }
+
+        // `String` replacement
+        {
+            let re = Regex::new(r#"o"#).unwrap();
+            let replacement = String::from("0");
+            assert_eq!(re.replace_all("foo boo", &replacement), "f00 b00");
+            assert_eq!(re.replace_all("foo boo", replacement), "f00 b00");
+        }
+    }
+
+    #[test]
+    fn test_split() {
+        for re in generate_res(
+            r#"[',', ';'], char_space*"#, // ANRE
+            r#"[,;]\s*"#,                 // traditional
+        ) {
+            let pieces: Vec<&str> = re.split("foo, bar;baz,  qux").collect();
+            assert_eq!(pieces, vec!["foo", "bar", "baz", "qux"]);
+
+            // leading, trailing and consecutive delimiters
+            let pieces: Vec<&str> = re.split(",a,,b;").collect();
+            assert_eq!(pieces, vec!["", "a", "", "b", ""]);
+
+            // no match
+            let pieces: Vec<&str> = re.split("abc").collect();
+            assert_eq!(pieces, vec!["abc"]);
+
+            // empty text
+            let pieces: Vec<&str> = re.split("").collect();
+            assert_eq!(pieces, vec![""]);
+        }
+    }
+
+    #[test]
+    fn test_splitn() {
+        for re in generate_res(
+            r#"char_digit+"#, // ANRE
+            r#"\d+"#,         // traditional
+        ) {
+            let text = "a1b22c333d";
+
+            let pieces: Vec<&str> = re.splitn(text, 0).collect();
+            assert!(pieces.is_empty());
+
+            let pieces: Vec<&str> = re.splitn(text, 1).collect();
+            assert_eq!(pieces, vec!["a1b22c333d"]);
+
+            let pieces: Vec<&str> = re.splitn(text, 2).collect();
+            assert_eq!(pieces, vec!["a", "b22c333d"]);
+
+            let pieces: Vec<&str> = re.splitn(text, 4).collect();
+            assert_eq!(pieces, vec!["a", "b", "c", "d"]);
+
+            let pieces: Vec<&str> = re.splitn(text, 10).collect();
+            assert_eq!(pieces, vec!["a", "b", "c", "d"]);
+
+            // the trailing piece is empty
+            let pieces: Vec<&str> = re.splitn("a1b2", 3).collect();
+            assert_eq!(pieces, vec!["a", "b", ""]);
+        }
+    }
+
+    #[test]
+    fn test_iterate_empty_matches() {
+        for engine in [Engine::Backtracking, Engine::PikeVm, Engine::Jit] {
+            for mut builder in generate_builders(
+                r#"'a'*"#, // ANRE
+                r#"a*"#,   // traditional
+            ) {
+                let re = builder.engine(engine).build().unwrap();
+
+                // the empty match at `4..4` right after the match `1..4` is skipped
+                let mut matches = re.find_iter("baaac");
+                assert_eq!(matches.next(), Some(new_match(0, 0, "")));
+                assert_eq!(matches.next(), Some(new_match(1, 4, "aaa")));
+                assert_eq!(matches.next(), Some(new_match(5, 5, "")));
+                assert_eq!(matches.next(), None);
+
+                // moves forward by one UTF-8 char after an empty match
+                let mut matches = re.find_iter("文b");
+                assert_eq!(matches.next(), Some(new_match(0, 0, "")));
+                assert_eq!(matches.next(), Some(new_match(3, 3, "")));
+                assert_eq!(matches.next(), Some(new_match(4, 4, "")));
+                assert_eq!(matches.next(), None);
+
+                // empty text
+                let mut matches = re.find_iter("");
+                assert_eq!(matches.next(), Some(new_match(0, 0, "")));
+                assert_eq!(matches.next(), None);
+
+                let mut captures = re.captures_iter("ba");
+                assert_eq!(captures.next(), Some(new_captures(&[(0, 0, None, "")])));
+                assert_eq!(captures.next(), Some(new_captures(&[(1, 2, None, "a")])));
+                assert_eq!(captures.next(), None);
+
+                assert_eq!(re.replace_all("baaac", "-"), "-b-c-");
+                assert_eq!(re.split("bac").collect::<Vec<_>>(), vec!["", "b", "c", ""]);
+            }
+
+            // assertions
+            let re = RegexBuilder::new(r#"\b"#).engine(engine).build().unwrap();
+            let positions: Vec<usize> = re.find_iter("ab cd").map(|m| m.start).collect();
+            assert_eq!(positions, vec![0, 2, 3, 5]);
+
+            let re = RegexBuilder::new(r#"$"#).engine(engine).build().unwrap();
+            assert_eq!(re.find("abc"), Some(new_match(3, 3, "")));
+
+            // the empty capture group
+            let re = RegexBuilder::new(r#"(b?)"#).engine(engine).build().unwrap();
+            let mut captures = re.captures_iter("ab");
+            assert_eq!(
+                captures.next(),
+                Some(new_captures(&[(0, 0, None, ""), (0, 0, None, "")]))
+            );
+            assert_eq!(
+                captures.next(),
+                Some(new_captures(&[(1, 2, None, "b"), (1, 2, None, "b")]))
+            );
+            assert_eq!(captures.next(), None);
+        }
+    }
+
+    #[test]
+    fn test_search_in_range() {
+        for engine in [Engine::Backtracking, Engine::PikeVm, Engine::Jit] {
+            let build = |pattern: &str| RegexBuilder::new(pattern).engine(engine).build().unwrap();
+
+            // the assertion `is_bound` sees the text before the start position
+            let re = build(r#"\bfoo"#);
+            let text = "xfoo foo";
+            assert_eq!(re.find_at(text, 1), Some(new_match(5, 8, "foo")));
+            assert_eq!(re.find(&text[1..]), Some(new_match(0, 3, "foo")));
+            assert_eq!(re.search(text, 1..4), None);
+            assert!(!re.is_match_in(text, 1..4));
+            assert!(re.is_match_at(text, 1));
+            assert!(!re.is_match_at(text, 6));
+
+            // the lookbehind sees the text before the start position
+            let re = build(r#"(?<=\$)\d+"#);
+            assert_eq!(re.search("$100", 1..4), Some(new_match(1, 4, "100")));
+
+            // the lookahead sees the text after the end position
+            let re = build(r#"\d+(?=px)"#);
+            assert_eq!(re.search("10px", 0..2), Some(new_match(0, 2, "10")));
+
+            // the match is inside the range
+            let re = build(r#"\d+"#);
+            assert_eq!(re.search("a12345", 0..4), Some(new_match(1, 4, "123")));
+            assert_eq!(re.search("a12345", 2..2), None);
+
+            // the assertion "end" sees the whole text
+            let re = build(r#"\d+$"#);
+            assert_eq!(re.search("12345", 0..3), None);
+            assert_eq!(re.search("12345", 2..5), Some(new_match(2, 5, "345")));
+
+            // the assertion "start" sees the whole text
+            let re = build(r#"^\d+"#);
+            assert_eq!(re.find_at("12345", 1), None);
+
+            let re = build(r#"(?<key>\w+)=(?<value>\w+)"#);
+            let text = "a=1, b=2, c=3";
+            assert_eq!(
+                re.captures_at(text, 3),
+                Some(new_captures(&[
+                    (5, 8, None, "b=2"),
+                    (5, 6, Some("key"), "b"),
+                    (7, 8, Some("value"), "2"),
+                ]))
+            );
+            assert_eq!(
+                re.search_captures(text, 0..9).unwrap().name("key"),
+                Some(&Match::new(0, 1, Some("key"), "a"))
+            );
+            assert_eq!(re.search_captures(text, 10..12), None);
+        }
+    }
+
+    #[test]
+    fn test_full_match() {
+        for engine in [Engine::Backtracking, Engine::PikeVm, Engine::Jit] {
+            let build = |pattern: &str| RegexBuilder::new(pattern).engine(engine).build().unwrap();
+
+            // the leftmost-first match is "a", but "ab" is a full match
+            let re = build(r#"a|ab"#);
+            assert_eq!(re.find("ab"), Some(new_match(0, 1, "a")));
+            assert_eq!(re.full_match("ab"), Some(new_match(0, 2, "ab")));
+            assert!(re.is_full_match("a"));
+            assert!(!re.is_full_match("abc"));
+            assert!(!re.is_full_match("xab"));
+            assert_eq!(re.try_is_full_match("ab"), Ok(true));
+
+            // the lazy quantifier is extended to the end
+            let re = build(r#"\d+?"#);
+            assert_eq!(re.full_match("123"), Some(new_match(0, 3, "123")));
+            assert_eq!(re.full_match("123x"), None);
+
+            // empty text
+            let re = build(r#"\d*"#);
+            assert!(re.is_full_match(""));
+            assert!(!build(r#"\d+"#).is_full_match(""));
+
+            // the lookaround assertions are not anchored
+            let re = build(r#"a(?=b)\w+"#);
+            assert!(re.is_full_match("abc"));
+            assert!(!re.is_full_match("acb"));
+        }
+    }
+
+    #[test]
+    fn test_builder_anchored() {
+        for engine in [Engine::Backtracking, Engine::PikeVm, Engine::Jit] {
+            let build = |pattern: &str, anchored: Anchored| {
+                RegexBuilder::new(pattern)
+                    .engine(engine)
+                    .anchored(anchored)
+                    .build()
+                    .unwrap()
+            };
+
+            let re = build(r#"\d+"#, Anchored::No);
+            assert_eq!(re.find("a123"), Some(new_match(1, 4, "123")));
+
+            // the match starts at the start of the range
+            let re = build(r#"\d+"#, Anchored::Start);
+            assert_eq!(re.find("a123"), None);
+            assert_eq!(re.find("123a"), Some(new_match(0, 3, "123")));
+            assert_eq!(re.find_at("a123", 1), Some(new_match(1, 4, "123")));
+
+            // the match also ends at the end of the range
+            let re = build(r#"a|ab"#, Anchored::Both);
+            assert_eq!(re.find("ab"), Some(new_match(0, 2, "ab")));
+            assert_eq!(re.find("abc"), None);
+            assert_eq!(re.search("xabc", 1..3), Some(new_match(1, 3, "ab")));
+
+            // the "start" and "end" assertions of the multi-line mode are not affected
+            let re = RegexBuilder::new(r#"^\d+$"#)
+                .engine(engine)
+                .multi_line(true)
+                .anchored(Anchored::Start)
+                .build()
+                .unwrap();
+            assert_eq!(re.find("12\n34"), Some(new_match(0, 2, "12")));
+            assert_eq!(re.find_at("12\n34", 3), Some(new_match(3, 5, "34")));
+            assert_eq!(re.find_at("12\n34", 2), None);
+        }
+    }
+
+    #[test]
+    fn test_search_with_prefilter() {
+        for engine in [Engine::Backtracking, Engine::PikeVm, Engine::Jit] {
+            let build = |pattern: &str| RegexBuilder::new(pattern).engine(engine).build().unwrap();
+
+            // the prefix
+            let re = build(r#"ERROR: (\d+)"#);
+            let text = "INFO: 1\nERROR: 2\nERROR: x\nERROR: 34";
+            let values: Vec<&str> = re.captures_iter(text).map(|c| c.matches[1].value).collect();
+            assert_eq!(values, vec!["2", "34"]);
+            assert_eq!(re.find_at(text, 9), Some(new_match(26, 35, "ERROR: 34")));
+
+            // the assertions before the prefix see the skipped text
+            let re = build(r#"(?<=x)ab"#);
+            assert_eq!(re.find("ab xab"), Some(new_match(4, 6, "ab")));
+            let re = build(r#"\bcd"#);
+            assert_eq!(re.find("xcd cd"), Some(new_match(4, 6, "cd")));
+
+            // the first bytes
+            let re = build(r#"[文字]\d"#);
+            assert_eq!(re.find("a1文x字2"), Some(new_match(6, 10, "字2")));
+
+            // the inner literal
+            let re = build(r#"\w+@\w+"#);
+            assert!(!re.is_match("abc def"));
+            assert_eq!(re.find("x a@b"), Some(new_match(2, 5, "a@b")));
+            assert_eq!(re.search("x a@b", 0..3), None);
+
+            // the states visited before skipping to the candidate are discarded
+            let cases = [
+                (r#" ?\Bb(?!c)"#, " Ab", 2, 3, "b"),
+                (r#"\s?\Bb"#, "ABC Ab", 5, 6, "b"),
+                (r#" ?\Bbc"#, " Abc", 2, 4, "bc"),
+            ];
+            for (pattern, text, start, end, value) in cases {
+                let expected = Some(new_match(start, end, value));
+                assert_eq!(build(pattern).find(text), expected);
+
+                let re = RegexBuilder::new(pattern)
+                    .engine(engine)
+                    .lazy_dfa(false)
+                    .build()
+                    .unwrap();
+                assert_eq!(re.find(text), expected);
+
+                let re = RegexBuilder::new(pattern)
+                    .engine(engine)
+                    .step_limit(10_000)
+                    .build()
+                    .unwrap();
+                assert_eq!(re.find(text), expected);
+            }
+        }
+    }
+
+    #[test]
+    fn test_lazy_dfa() {
+        // the results with and without the lazy DFA should be the same
+        let cases = [
+            (r#"a+?b*"#, "xaaabbb aab"),
+            (r#"(\d+)-(\d+)"#, "tel: 010-1234, 020-5678"),
+            (r#"(foo|foobar)(bar|baz)"#, "foobar foobaz"),
+            (r#"a|ab|abc"#, "abc ab"),
+            (r#"^\w+"#, "hello world"),
+            (r#"\w+$"#, "hello world"),
+            (r#"(?m)^\w+$"#, "foo\nbar\r\nbaz"),
+            (r#"(?m)^$"#, "a\n\nb\n"),
+            (r#"\bfoo\b"#, "foobar foo barfoo"),
+            (r#"\Bo\B"#, "foo o boo"),
+            (r#"(?<y>\d{4})-(?<m>\d{1,2})"#, "2025-03-15, 2024-12-1"),
+            (r#"(?:ab){2,}"#, "abababx abx abab"),
+            (r#"(?i)hello"#, "Hello HELLO"),
+            (r#"(?i)[a-c]+"#, "xAbC yz"),
+            (r#"文字\w"#, "中文字a 文字b"),
+            (r#"[^a-z\s]+"#, "abc ABC 中文 123"),
+            (r#"(?s)a.c"#, "a\nc abc a文c"),
+            (r#"a.c"#, "a\nc abc a文c"),
+            (r#"x*"#, "axxb"),
+            (r#""#, "ab"),
+            (
+                r#"one|two|three|four|five|six|seven|eight"#,
+                "zero two ten eight",
+            ),
+        ];
+
+        for (pattern, text) in cases {
+            for engine in [Engine::Backtracking, Engine::PikeVm] {
+                let build = |lazy_dfa: bool| {
+                    RegexBuilder::new(pattern)
+                        .engine(engine)
+                        .lazy_dfa(lazy_dfa)
+                        .build()
+                        .unwrap()
+                };
+                let (re, expected) = (build(true), build(false));
+
+                assert_eq!(
+                    re.captures_iter(text).collect::<Vec<_>>(),
+                    expected.captures_iter(text).collect::<Vec<_>>(),
+                    "pattern: {}",
+                    pattern
+                );
+
+                for start in (0..=text.len()).filter(|idx| text.is_char_boundary(*idx)) {
+                    let range = start..text.len();
+                    assert_eq!(
+                        re.search(text, range.clone()),
+                        expected.search(text, range.clone()),
+                        "pattern: {}, start: {}",
+                        pattern,
+                        start
+                    );
+                    assert_eq!(
+                        re.is_match_in(text, range.clone()),
+                        expected.is_match_in(text, range),
+                        "pattern: {}, start: {}",
+                        pattern,
+                        start
+                    );
+                }
+
+                assert_eq!(re.is_full_match(text), expected.is_full_match(text));
+            }
+        }
+
+        // the assertions see the text outside the range
+        let re = Regex::new(r#"\bfoo$"#).unwrap();
+        assert_eq!(re.search("xfoo foo", 1..4), None);
+        assert_eq!(re.search("x foo", 2..5), Some(new_match(2, 5, "foo")));
+        assert_eq!(re.search("x foo ", 2..5), None);
+
+        // a long text
+        let re = Regex::new(r#"ERROR \w+: (\d+)"#).unwrap();
+        let text = format!("{}ERROR disk: 42\n", "INFO ok: 1\n".repeat(10_000));
+        assert!(re.is_match(&text));
+        assert_eq!(&re.captures(&text).unwrap()[1], "42");
+        assert!(!re.is_match(&text[..text.len() - 16]));
+
+        // the states are more than the capacity of the cache
+        let re = Regex::new(r#"^[ab]{50000}$"#).unwrap();
+        let text = "ab".repeat(25_000);
+        assert!(re.is_match(&text));
+        assert!(!re.is_match(&text[1..]));
+
+        // the lazy DFA is not used with the limits
+        let re = RegexBuilder::new(r#"(a+)+b"#)
+            .engine(Engine::Backtracking)
+            .step_limit(10_000)
+            .build()
+            .unwrap();
+        assert_eq!(
+            re.try_is_match(&"a".repeat(100)),
+            Err(MatchError::BudgetExceeded)
+        );
+    }
+
+    #[test]
+    fn test_optimize() {
+        // the results with and without the optimization should be the same
+        let cases = [
+            (r#"abc|abd"#, "xabd abc ab"),
+            (r#"a(bc)+d"#, "abcbcd abd"),
+            (r#"(?:ab){2,3}?c"#, "abababc ababc abc"),
+            (r#"(a|b){2}c"#, "abc bbc ac"),
+            (r#"x(?:yz)*?z"#, "xyzyzz xz"),
+            (r#"(?i)hello, world"#, "HELLO, World"),
+            (r#"[z-a0-9_a-fc-k]+"#, "--a9_kz--"),
+            (r#"[^\dxa-c]+"#, "ab123 xyzé"),
+            (r#"(\w+)@(\w+)\.com"#, "foo@bar.com, a@b.org"),
+            (r#"(?<=ab)cd(?=ef)"#, "abcdef cdef abcd"),
+            (r#"(\w)\1"#, "abccd eef"),
+            (r#"^$"#, ""),
+            (r#""#, "ab"),
+        ];
+
+        for (pattern, text) in cases {
+            for engine in [Engine::Backtracking, Engine::PikeVm, Engine::Jit] {
+                let build = |optimize: bool| {
+                    RegexBuilder::new(pattern)
+                        .engine(engine)
+                        .optimize(optimize)
+                        .build()
+                };
+                let (Ok(re), Ok(expected)) = (build(true), build(false)) else {
+                    // the Pike VM does not support back references
+                    continue;
+                };
+
+                assert_eq!(
+                    re.captures_iter(text).collect::<Vec<_>>(),
+                    expected.captures_iter(text).collect::<Vec<_>>(),
+                    "pattern: {}",
+                    pattern
+                );
+            }
+        }
+
+        // the stats are shown in the debug text
+        let re = Regex::new(r#"a(b|c)"#).unwrap();
+        assert_eq!(
+            re.object_file.get_debug_text().lines().last(),
+            Some("* optimized: nodes 12 -> 9, transitions 12 -> 9")
+        );
+
+        let re = RegexBuilder::new(r#"a(b|c)"#)
+            .optimize(false)
+            .build()
+            .unwrap();
+        assert!(!re.object_file.get_debug_text().contains("optimized"));
+    }
+
+    #[test]
+    fn test_from_object_file() {
+        let re = Regex::new(r#"(?<key>\w+)=(?<value>\d+)"#).unwrap();
+        let bytes = re.object_file.to_bytes();
+
+        let loaded = Regex::from_object_file(ObjectFile::from_bytes(&bytes).unwrap()).unwrap();
+        let text = "a=1, bc=23, d=x";
+        assert_eq!(
+            loaded.captures_iter(text).collect::<Vec<_>>(),
+            re.captures_iter(text).collect::<Vec<_>>()
+        );
+        assert_eq!(&loaded.captures(text).unwrap()["value"], "1");
+
+        // the anchoring set by the builder is kept
+        let re = RegexBuilder::new(r#"\d+"#)
+            .anchored(Anchored::Both)
+            .build()
+            .unwrap();
+        let bytes = re.object_file.to_bytes();
+        let loaded = Regex::from_object_file(ObjectFile::from_bytes(&bytes).unwrap()).unwrap();
+        assert!(loaded.is_match("123"));
+        assert!(!loaded.is_match("123a"));
+
+        // corrupted data
+        assert!(matches!(
+            ObjectFile::from_bytes(&bytes[..bytes.len() - 1]),
+            Err(AnreError::InvalidObjectFile(_))
+        ));
+
+        // err: the object file which matches bytes
+        let object_file = ObjectFile::from_debug_text("> 0\n  -> 1, Any byte\n< 1").unwrap();
+        assert!(matches!(
+            Regex::from_object_file(object_file),
+            Err(AnreError::InvalidObjectFile(_))
+        ));
+    }
+
+    #[test]
+    fn test_search_with_trie() {
+        for engine in [Engine::Backtracking, Engine::PikeVm, Engine::Jit] {
+            let build = |pattern: &str| RegexBuilder::new(pattern).engine(engine).build().unwrap();
+
+            let words: Vec<String> = (0..300).map(|i| format!("w{}x", i)).collect();
+            let re = build(&format!(r#"\b(?:{})\b"#, words.join("|")));
+            assert!(re.is_match("a w123x b"));
+            assert!(!re.is_match("a w123 b"));
+            assert_eq!(re.find("w1 w299x"), Some(new_match(3, 8, "w299x")));
+
+            let re = build(r#"(?<key>one|two|three|four|five|six|seven|eight|文字)=(\d+)"#);
+            let text = "zero=0, two=2, 文字=8, ten=10";
+            let values: Vec<&str> = re.captures_iter(text).map(|c| c.matches[2].value).collect();
+            assert_eq!(values, vec!["2", "8"]);
+            assert_eq!(re.find("seven=7"), Some(new_match(0, 7, "seven=7")));
+
+            // repetition
+            let re = build(r#"^(?:aa|bb|cc|dd|ee|ff|gg|hh)+$"#);
+            assert!(re.is_match("aaffhh"));
+            assert!(!re.is_match("aaf"));
+
+            // ignore case
+            let re = RegexBuilder::new(r#"one|two|three|four|five|six|seven|eight"#)
+                .engine(engine)
+                .case_insensitive(true)
+                .build()
+                .unwrap();
+            assert_eq!(re.find("Zero, TWO"), Some(new_match(6, 9, "TWO")));
+
+            // a string is the prefix of another string, the order is kept
+            let re = build(r#"a|b|c|d|e|f|g|gh"#);
+            assert_eq!(re.find("xgh"), Some(new_match(1, 2, "g")));
+        }
+    }
+
+    #[test]
+    fn test_captures_read() {
+        for engine in [Engine::Backtracking, Engine::PikeVm, Engine::Jit] {
+            let re = RegexBuilder::new(r#"(?<key>\w+)=(?<value>\d+)?"#)
+                .engine(engine)
+                .build()
+                .unwrap();
+
+            let mut locations = re.capture_locations();
+            assert_eq!(locations.len(), 3);
+
+            let text = "a=1, bc=23";
+            assert_eq!(
+                re.captures_read(&mut locations, text),
+                Some(new_match(0, 3, "a=1"))
+            );
+            assert_eq!(locations.get(0), Some((0, 3)));
+            assert_eq!(locations.get(1), Some((0, 1)));
+            assert_eq!(locations.get(2), Some((2, 3)));
+            assert_eq!(locations.get(3), None);
+
+            // the locations are reused
+            assert_eq!(
+                re.captures_read_at(&mut locations, text, 3),
+                Some(new_match(5, 10, "bc=23"))
+            );
+            assert_eq!(locations.get(1), Some((5, 7)));
+            assert_eq!(locations.get(2), Some((8, 10)));
+
+            assert_eq!(re.captures_read_at(&mut locations, text, 10), None);
+        }
+    }
+
+    #[test]
+    fn test_search_with_cache() {
+        let mut cache = Cache::new();
+
+        // a cache can be used by different expressions
+        let re1 = Regex::new(r#"(\w)(\d)"#).unwrap();
+        let re2 = Regex::new(r#"\d+"#).unwrap();
+        for _ in 0..2 {
+            assert_eq!(
+                re1.try_search_with(&mut cache, "a1b2", 1..4),
+                Ok(Some(new_match(2, 4, "b2")))
+            );
+            assert_eq!(
+                re2.try_search_with(&mut cache, "a1b2", 0..4),
+                Ok(Some(new_match(1, 2, "1")))
+            );
+            assert_eq!(re2.try_is_match_with(&mut cache, "abc", 0..3), Ok(false));
+        }
+
+        // the cache is still valid after the matching is aborted
+        let re3 = RegexBuilder::new(r#"(a+)+b"#)
+            .engine(Engine::Backtracking)
+            .step_limit(100)
+            .build()
+            .unwrap();
+        let text = "aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaac";
+        assert_eq!(
+            re3.try_search_with(&mut cache, text, 0..text.len()),
+            Err(MatchError::BudgetExceeded)
+        );
+        assert_eq!(
+            re1.try_search_with(&mut cache, "a1b2", 0..4),
+            Ok(Some(new_match(0, 2, "a1")))
+        );
+
+        // the internal pool of caches is shared by threads
+        let re = Regex::new(r#"(?<year>\d{4})-(?<month>\d{2})"#).unwrap();
+        std::thread::scope(|scope| {
+            for _ in 0..4 {
+                scope.spawn(|| {
+                    for _ in 0..100 {
+                        let captures = re.captures("on 2025-06").unwrap();
+                        assert_eq!(captures.name("month").unwrap().value, "06");
+                    }
+                });
+            }
+        });
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_search_invalid_range() {
+        let re = Regex::new(r#"\w"#).unwrap();
+
+        // the position 1 is in the middle of the char '文'
+        re.search("文字", 1..6);
+    }
+
+    fn generate_builders(anre: &str, regex: &str) -> [RegexBuilder; 2] {
+        [RegexBuilder::from_anre(anre), RegexBuilder::new(regex)]
+    }
+
+    #[test]
+    fn test_builder_case_insensitive() {
+        for mut builder in generate_builders(
+            r#"'a', "bc", ['x'..'z', '_']"#, // ANRE
+            r#"abc[x-z_]"#,                  // traditional
+        ) {
+            let re = builder.build().unwrap();
+            assert!(re.is_match("abcx"));
+            assert!(!re.is_match("ABCX"));
+
+            let re = builder.case_insensitive(true).build().unwrap();
+            assert_eq!(re.find("..aBcY..").unwrap().as_str(), "aBcY");
+            assert_eq!(re.find("ABC_").unwrap().as_str(), "ABC_");
+            assert!(!re.is_match("ABCW"));
+        }
+
+        // negative charset
+        for mut builder in generate_builders(
+            r#"!['a'..'c']"#, // ANRE
+            r#"[^a-c]"#,      // traditional
+        ) {
+            let re = builder.case_insensitive(true).build().unwrap();
+            assert!(!re.is_match("B"));
+            assert_eq!(re.find("BD").unwrap().as_str(), "D");
+        }
+
+        // the byte length of the matched text differs from the pattern,
+        // 'K' (U+212A, Kelvin sign) is folded to 'k'.
+        for mut builder in generate_builders(
+            r#""ok""#, // ANRE
+            r#"ok"#,   // traditional
+        ) {
+            let re = builder.case_insensitive(true).build().unwrap();
+            assert_eq!(re.find("O\u{212a}!").unwrap().as_str(), "O\u{212a}");
+        }
+    }
+
+    #[test]
+    fn test_builder_multi_line() {
+        for mut builder in generate_builders(
+            r#"start, char_word+, end"#, // ANRE
+            r#"^\w+$"#,                  // traditional
+        ) {
+            let text = "foo\nbar\nbaz";
+
+            let re = builder.build().unwrap();
+            assert!(re.find(text).is_none());
+
+            let re = builder.multi_line(true).build().unwrap();
+            let mut matches = re.find_iter(text);
+            assert_eq!(matches.next().unwrap().as_str(), "foo");
+            assert_eq!(matches.next().unwrap().as_str(), "bar");
+            assert_eq!(matches.next().unwrap().as_str(), "baz");
+            assert!(matches.next().is_none());
+        }
+
+        // the assertions are not restricted to the beginning and end in multi-line mode
+        for mut builder in generate_builders(
+            r#"start, 'a' || start, 'b'"#, // ANRE
+            r#"^a|^b"#,                    // traditional
+        ) {
+            assert!(builder.build().is_err());
+
+            let re = builder.multi_line(true).build().unwrap();
+            let mut matches = re.find_iter("ba\nab");
+            assert_eq!(matches.next().unwrap().start(), 0);
+            assert_eq!(matches.next().unwrap().start(), 3);
+            assert!(matches.next().is_none());
+        }
+    }
+
+    #[test]
+    fn test_builder_crlf() {
+        for mut builder in generate_builders(
+            r#"start, char_word+, end"#, // ANRE
+            r#"^\w+$"#,                  // traditional
+        ) {
+            let text = "foo\r\nbar";
+
+            let re = builder.multi_line(true).build().unwrap();
+            let mut matches = re.find_iter(text);
+            assert_eq!(matches.next().unwrap().as_str(), "bar");
+            assert!(matches.next().is_none());
+
+            let re = builder.multi_line(true).crlf(true).build().unwrap();
+            let mut matches = re.find_iter(text);
+            assert_eq!(matches.next().unwrap().as_str(), "foo");
+            assert_eq!(matches.next().unwrap().as_str(), "bar");
+            assert!(matches.next().is_none());
+        }
+    }
+
+    #[test]
+    fn test_builder_dot_matches_new_line() {
+        for mut builder in generate_builders(
+            r#"'a', char_any+, 'b'"#, // ANRE
+            r#"a.+b"#,                // traditional
+        ) {
+            let text = "a\r\nb";
+
+            let re = builder.build().unwrap();
+            assert!(!re.is_match(text));
+
+            let re = builder.dot_matches_new_line(true).build().unwrap();
+            assert_eq!(re.find(text).unwrap().as_str(), "a\r\nb");
+        }
+    }
+
+    #[test]
+    fn test_builder_swap_greed() {
+        for mut builder in generate_builders(
+            r#"'a', char_any+, 'b', char_any+?, 'c'"#, // ANRE
+            r#"a.+b.+?c"#,                             // traditional
+        ) {
+            let text = "a1b2b3c4c";
+
+            let re = builder.build().unwrap();
+            assert_eq!(re.find(text).unwrap().as_str(), "a1b2b3c");
+
+            let re = builder.swap_greed(true).build().unwrap();
+            assert_eq!(re.find(text).unwrap().as_str(), "a1b2b3c4c");
+        }
+    }
+
+    #[test]
+    fn test_process_inline_flags() {
+        // global flags
+        let re = Regex::new(r#"(?i)^hello$"#).unwrap();
+        assert!(re.is_match("HeLLo"));
+        assert!(!re.is_match("HeLLo!"));
+
+        // scoped flags
+        let re = Regex::new(r#"a(?i:b)c"#).unwrap();
+        assert!(re.is_match("aBc"));
+        assert!(!re.is_match("aBC"));
+
+        let re = Regex::new(r#"(?i)a(?-i:b)c"#).unwrap();
+        assert!(re.is_match("AbC"));
+        assert!(!re.is_match("ABC"));
+
+        // the flags apply to the rest of the enclosing group only
+        let re = Regex::new(r#"(a(?i)b)c"#).unwrap();
+        assert!(re.is_match("aBc"));
+        assert!(!re.is_match("aBC"));
+
+        // the flags apply to the subsequent alternatives
+        let re = Regex::new(r#"a(?i)b|c"#).unwrap();
+        assert_eq!(re.find("xC").unwrap().as_str(), "C");
+
+        // multi-line and dot-all
+        let re = Regex::new(r#"(?m)^\w+$"#).unwrap();
+        let mut matches = re.find_iter("foo\nbar");
+        assert_eq!(matches.next().unwrap().as_str(), "foo");
+        assert_eq!(matches.next().unwrap().as_str(), "bar");
+        assert!(matches.next().is_none());
+
+        let re = Regex::new(r#"a(?s:.)b."#).unwrap();
+        assert!(re.is_match("a\nbc"));
+        assert!(!re.is_match("a\nb\n"));
+
+        // extended mode
+        let re = Regex::new(
+            r#"(?x)
+            (?<year> \d{4} ) - # year
+            (?<month> \d{2} )  # month
+            "#,
+        )
+        .unwrap();
+        let captures = re.captures("date: 2025-03").unwrap();
+        assert_eq!(&captures["year"], "2025");
+        assert_eq!(&captures["month"], "03");
+    }
+
+    #[test]
+    fn test_process_modifiers() {
+        for re in generate_res(
+            r#"'a', "bc".ignore_case()"#, // ANRE
+            r#"a(?i:bc)"#,                // traditional
+        ) {
+            assert!(re.is_match("aBc"));
+            assert!(!re.is_match("ABC"));
+        }
+
+        for re in generate_res(
+            r#"
+ignore_case()
+start, "abc", end"#, // ANRE
+            r#"(?i)^abc$"#, // traditional
+        ) {
+            assert!(re.is_match("aBC"));
+            assert!(!re.is_match("aBC!"));
+        }
+
+        for re in generate_res(
+            r#"multiline((start, char_word.one_or_more(), end))"#, // ANRE
+            r#"(?m)^\w+$"#,                                        // traditional
+        ) {
+            let mut matches = re.find_iter("foo\nbar");
+            assert_eq!(matches.next(), Some(new_match(0, 3, "foo")));
+            assert_eq!(matches.next(), Some(new_match(4, 7, "bar")));
+            assert_eq!(matches.next(), None);
+        }
+
+        for re in generate_res(
+            r#"'a', dot_all(char_any), 'b', char_any"#, // ANRE
+            r#"a(?s:.)b."#,                             // traditional
+        ) {
+            assert!(re.is_match("a\nbc"));
+            assert!(!re.is_match("a\nb\n"));
+        }
+
+        // err: more than one expression
+        assert!(Regex::from_anre(r#"ignore_case('a', 'b')"#).is_err());
+
+        // the conversion from traditional to ANRE keeps the modifiers
+        for regex in [r#"(?i)^abc$"#, r#"a(?i:b(?-i)c)"#, r#"(?ms)^a.b$"#] {
+            let program = traditional::parse_from_str(regex).unwrap();
+            let anre = program.to_string();
+
+            let [re1, re2] = generate_res(&anre, regex);
+            for text in ["abc", "ABC", "aBc", "aBC", "a\nb", "x\na\nb\ny"] {
+                assert_eq!(re1.is_match(text), re2.is_match(text));
+            }
+        }
+    }
+
+    #[test]
+    fn test_engine_selection() {
+        assert_eq!(Regex::new(r#"a+b"#).unwrap().engine(), Engine::PikeVm);
+        assert_eq!(
+            Regex::new(r#"(a)\1"#).unwrap().engine(),
+            if IS_JIT_AVAILABLE {
+                Engine::Jit
+            } else {
+                Engine::Backtracking
+            }
+        );
+
+        assert_eq!(
+            RegexBuilder::new(r#"a+b"#)
+                .engine(Engine::Backtracking)
+                .build()
+                .unwrap()
+                .engine(),
+            Engine::Backtracking
+        );
+
+        // err: back references are not supported by the Pike VM
+        assert!(RegexBuilder::new(r#"(a)\1"#)
+            .engine(Engine::PikeVm)
+            .build()
+            .is_err());
+    }
+
+    #[test]
+    fn test_engine_pike_vm() {
+        // the results of both engines should be the same
+        let cases = [
+            (r#"a+?b*"#, "xaaabbb aab"),
+            (r#"(\d+)-(\d+)"#, "tel: 010-1234, 020-5678"),
+            (r#"(foo|foobar)(bar|baz)"#, "foobar foobaz"),
+            (r#"^\w+"#, "hello world"),
+            (r#"\w+$"#, "hello world"),
+            (r#"\bfoo\b"#, "foobar foo barfoo"),
+            (r#"(?<y>\d{4})-(?<m>\d{1,2})"#, "2025-03-15, 2024-12-1"),
+            (r#"(?:ab){2,}"#, "abababx abx abab"),
+            (r#"\w+(?=,)"#, "foo, bar, baz"),
+            (r#"(?<=\$)\d+"#, "price: $100, 200"),
+            (r#"\d+(?!px)\b"#, "10px 20em 30"),
+            (r#"(?i)hello"#, "Hello HELLO"),
+            (r#"文字\w"#, "中文字a 文字b"),
+        ];
+
+        for (pattern, text) in cases {
+            let backtracking = RegexBuilder::new(pattern)
+                .engine(Engine::Backtracking)
+                .build()
+                .unwrap();
+            let pike_vm = RegexBuilder::new(pattern)
+                .engine(Engine::PikeVm)
+                .build()
+                .unwrap();
+
+            assert_eq!(
+                backtracking.captures_iter(text).collect::<Vec<_>>(),
+                pike_vm.captures_iter(text).collect::<Vec<_>>(),
+                "pattern: {}",
+                pattern
+            );
+        }
+
+        // the repetition of an expression which matches empty string
+        let re = Regex::new(r#"(a?){3,}b"#).unwrap();
+        let mut matches = re.find_iter("aab b");
+        assert_eq!(matches.next(), Some(new_match(0, 3, "aab")));
+        assert_eq!(matches.next(), Some(new_match(4, 5, "b")));
+        assert_eq!(matches.next(), None);
+
+        // the nested quantifiers take exponential time in the backtracking engine
+        let re = Regex::new(r#"(a+)+b"#).unwrap();
+        assert_eq!(re.engine(), Engine::PikeVm);
+
+        let text = "a".repeat(1000);
+        assert!(!re.is_match(&text));
+        assert_eq!(
+            re.find(&format!("{}b", text)),
+            Some(new_match(0, 1001, &format!("{}b", text)))
+        );
+    }
+
+    #[test]
+    fn test_match_limits() {
+        // the nested quantifiers take exponential time in the backtracking engine
+        let re = RegexBuilder::new(r#"(a+)+b"#)
+            .engine(Engine::Backtracking)
+            .step_limit(10_000)
+            .build()
+            .unwrap();
+
+        let text = "a".repeat(100);
+        assert_eq!(re.try_is_match(&text), Err(MatchError::BudgetExceeded));
+        assert_eq!(re.try_find(&text), Err(MatchError::BudgetExceeded));
+        assert!(matches!(
+            re.try_captures(&text),
+            Err(MatchError::BudgetExceeded)
+        ));
+        assert!(!re.is_match(&text));
+        assert_eq!(re.find(&text), None);
+        assert_eq!(re.find_iter(&text).next(), None);
+
+        // within the limit
+        assert_eq!(re.try_is_match("aab"), Ok(true));
+        assert_eq!(re.try_find("xaab"), Ok(Some(new_match(1, 4, "aab"))));
+        assert_eq!(re.try_is_match("xyz"), Ok(false));
+
+        // the steps of the lookaround assertions are also counted
+        let re = RegexBuilder::new(r#"\w(?=(a+)+b)"#)
+            .engine(Engine::Backtracking)
+            .step_limit(10_000)
+            .build()
+            .unwrap();
+        assert_eq!(
+            re.try_is_match(&format!("x{}", text)),
+            Err(MatchError::BudgetExceeded)
+        );
+
+        // the Pike VM
+        let re = RegexBuilder::new(r#"a+b"#)
+            .engine(Engine::PikeVm)
+            .step_limit(10)
+            .build()
+            .unwrap();
+        assert_eq!(re.try_is_match(&text), Err(MatchError::BudgetExceeded));
+        assert_eq!(re.try_is_match("aab"), Ok(true));
+
+        // the lookaround assertion runs at most once at each position,
+        // and stops at the first match, so the steps grow linearly
+        let re = RegexBuilder::new(r#"a(?=a+)a*b"#)
+            .engine(Engine::PikeVm)
+            .step_limit(200_000)
+            .build()
+            .unwrap();
+        let long_text = "a".repeat(10_000);
+        assert_eq!(re.try_is_match(&long_text), Ok(false));
+        assert_eq!(
+            re.try_find(&format!("{}b", long_text)),
+            Ok(Some(new_match(0, 10_001, &format!("{}b", long_text))))
+        );
+
+        // time limit
+        let re = RegexBuilder::new(r#"(a+)+b"#)
+            .engine(Engine::Backtracking)
+            .time_limit(Duration::from_millis(10))
+            .build()
+            .unwrap();
+        assert_eq!(re.try_is_match(&text), Err(MatchError::Timeout));
+    }
+
+    #[test]
+    fn test_engine_jit() {
+        let re = RegexBuilder::new(r#"a+b"#)
+            .engine(Engine::Jit)
+            .build()
+            .unwrap();
+        assert_eq!(
+            re.engine(),
+            if IS_JIT_AVAILABLE {
+                Engine::Jit
+            } else {
+                Engine::Backtracking
+            }
+        );
+
+        // the results of both engines should be the same
+        let cases = [
+            (r#"a+?b*"#, "xaaabbb aab"),
+            (r#"(\d+)-(\d+)"#, "tel: 010-1234, 020-5678"),
+            (r#"(foo|foobar)(bar|baz)"#, "foobar foobaz"),
+            (r#"^\w+"#, "hello world"),
+            (r#"\w+$"#, "hello world"),
+            (r#"(?m)^\w+$"#, "foo\nbar\r\nbaz"),
+            (r#"\bfoo\b"#, "foobar foo barfoo"),
+            (r#"(?<y>\d{4})-(?<m>\d{1,2})"#, "2025-03-15, 2024-12-1"),
+            (r#"(?:ab){2,}"#, "abababx abx abab"),
+            (r#"\w+(?=,)"#, "foo, bar, baz"),
+            (r#"(?<=\$)\d+"#, "price: $100, 200"),
+            (r#"\d+(?!px)\b"#, "10px 20em 30"),
+            (r#"(?<!\w)\w+(?=(\d))"#, "ab1 cd ef2"),
+            (r#"(?i)hello"#, "Hello HELLO"),
+            (r#"(?i)[a-c]+"#, "xAbC yz"),
+            (r#"文字\w"#, "中文字a 文字b"),
+            (r#"[^a-z\s]+"#, "abc ABC 中文 123"),
+            (r#"[中-文]+"#, "abc 中文 123"),
+            (r#"(?s)a.c"#, "a\nc abc a文c"),
+            (r#"a.c"#, "a\nc abc a文c"),
+            (r#"(\w)\1"#, "abcc ddx"),
+            (r#"<(?<tag>\w+)>.*?</\k<tag>>"#, "<b>x</i></b> <i>y</i>"),
+            (r#"the quick brown fox"#, "see the quick brown fox jumps"),
+        ];
+
+        for (pattern, text) in cases {
+            let backtracking = RegexBuilder::new(pattern)
+                .engine(Engine::Backtracking)
+                .build()
+                .unwrap();
+            let jit = RegexBuilder::new(pattern)
+                .engine(Engine::Jit)
+                .build()
+                .unwrap();
+
+            assert_eq!(
+                backtracking.captures_iter(text).collect::<Vec<_>>(),
+                jit.captures_iter(text).collect::<Vec<_>>(),
+                "pattern: {}",
+                pattern
+            );
+        }
+
+        // the backtracking stack grows
+        let re = RegexBuilder::new(r#"(\w|\s)*x"#)
+            .engine(Engine::Jit)
+            .build()
+            .unwrap();
+        let text = "ab ".repeat(200);
+        assert!(!re.is_match(&text));
+        assert!(re.is_match(&format!("{}x", text)));
+
+        // the limits
+        let re = RegexBuilder::new(r#"(a+)+\1b"#)
+            .engine(Engine::Jit)
+            .step_limit(10_000)
+            .build()
+            .unwrap();
+        let text = "a".repeat(100);
+        assert_eq!(re.try_is_match(&text), Err(MatchError::BudgetExceeded));
+        assert_eq!(re.try_is_match("aab"), Ok(true));
+    }
 }
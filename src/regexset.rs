@@ -0,0 +1,409 @@
+// Copyright (c) 2025 Hemashushu <hippospark@gmail.com>, All rights reserved.
+//
+// This Source Code Form is subject to the terms of
+// the Mozilla Public License version 2.0 and additional exceptions.
+// For more details, see the LICENSE, LICENSE.additional, and CONTRIBUTING files.
+
+use crate::{
+    compiler::{compile_from_anre, compile_from_regex},
+    context::Context,
+    object_file::{ObjectFile, MAIN_ROUTE_INDEX},
+    optimizer, pikevm,
+    transition::{JumpTransition, Transition},
+    AnreError,
+};
+
+/// A set of expressions which are matched against a text in a single scan.
+///
+/// The API is intended to be compatible with the 'RegexSet' of crate 'regex':
+/// https://docs.rs/regex/latest/regex/struct.RegexSet.html
+///
+/// All the expressions are compiled into one object file, the main route
+/// contains one branch for each expression:
+///
+/// ```diagram
+///          jump   /--------------\
+///      /--------==o expression 0 o==   (exit node 0)
+///   in |   jump   /--------------\
+///  ==o==--------==o expression 1 o==   (exit node 1)
+///      |   jump   /--------------\
+///      \--------==o expression 2 o==   (exit node 2)
+/// ```
+///
+/// The set is matched by the Pike VM, so back references are not supported.
+pub struct RegexSet {
+    pub object_file: ObjectFile,
+    patterns: Vec<String>,
+    exit_node_indices: Vec<usize>,
+}
+
+impl RegexSet {
+    /// Creates a set of the traditional regular expressions.
+    pub fn new<I, S>(patterns: I) -> Result<Self, AnreError>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        RegexSet::build(patterns, compile_from_regex)
+    }
+
+    /// Creates a set of the ANRE expressions.
+    pub fn from_anre<I, S>(expressions: I) -> Result<Self, AnreError>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        RegexSet::build(expressions, compile_from_anre)
+    }
+
+    fn build<I, S>(
+        patterns: I,
+        compile: fn(&str) -> Result<ObjectFile, AnreError>,
+    ) -> Result<Self, AnreError>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let mut object_files = vec![];
+        let mut pattern_strings = vec![];
+
+        for pattern in patterns {
+            let pattern = pattern.as_ref();
+            let mut object_file = compile(pattern)?;
+
+            if object_file.has_back_reference() {
+                return Err(AnreError::SyntaxIncorrect(format!(
+                    "The expression \"{}\" of the set contains back references, which are not supported.",
+                    pattern
+                )));
+            }
+
+            optimizer::optimize(&mut object_file);
+            object_files.push(object_file);
+            pattern_strings.push(pattern.to_owned());
+        }
+
+        let (object_file, exit_node_indices) = merge_object_files(object_files);
+
+        Ok(RegexSet {
+            object_file,
+            patterns: pattern_strings,
+            exit_node_indices,
+        })
+    }
+
+    /// Returns whether any expression of the set matches the text.
+    pub fn is_match(&self, text: &str) -> bool {
+        self.matches(text).matched_any()
+    }
+
+    /// Returns the expressions of the set which match the text.
+    pub fn matches(&self, text: &str) -> SetMatches {
+        let bytes = text.as_bytes();
+        let number_of_capture_groups = self.object_file.capture_group_names.len();
+        let mut context = Context::from_bytes(bytes, number_of_capture_groups);
+
+        let matched =
+            pikevm::start_set_process(&mut context, &self.object_file, &self.exit_node_indices);
+
+        SetMatches { matched }
+    }
+
+    /// Returns an iterator over the indices of the expressions which match the text,
+    /// in ascending order.
+    pub fn matches_iter(&self, text: &str) -> SetMatchesIntoIter {
+        self.matches(text).into_iter()
+    }
+
+    /// Returns the number of expressions in the set.
+    pub fn len(&self) -> usize {
+        self.patterns.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.patterns.is_empty()
+    }
+
+    /// Returns the expressions of the set, in the order they were given.
+    pub fn patterns(&self) -> &[String] {
+        &self.patterns
+    }
+}
+
+// Merges the object files of the expressions into one object file.
+//
+// The nodes of the main routes are appended to the new main route,
+// and the sub-routes (generated by lookaround assertions) are appended
+// to the new object file, the indices of nodes, routes and capture groups
+// are updated accordingly.
+//
+// Returns the new object file and the exit node indices of the expressions.
+fn merge_object_files(object_files: Vec<ObjectFile>) -> (ObjectFile, Vec<usize>) {
+    let mut merged = ObjectFile::new();
+    let route_index = merged.create_route();
+    let entry_node_index = merged.routes[route_index].create_node();
+
+    // The main route requires an exit node, it is not connected,
+    // the exit nodes of the expressions are recorded separately.
+    let exit_node_index = merged.routes[route_index].create_node();
+
+    merged.routes[route_index].start_node_index = entry_node_index;
+    merged.routes[route_index].end_node_index = exit_node_index;
+
+    let mut is_fixed_start_position = true;
+    let mut exit_node_indices = vec![];
+
+    for object_file in object_files {
+        // The sub-route `n` of the expression becomes the route `route_offset + n`.
+        let route_offset = merged.routes.len() - 1;
+        let capture_group_offset = merged.capture_group_names.len();
+
+        merged
+            .capture_group_names
+            .extend(object_file.capture_group_names);
+
+        for (index, mut route) in object_file.routes.into_iter().enumerate() {
+            for node in &mut route.nodes {
+                for transition_item in &mut node.transition_items {
+                    match &mut transition_item.transition {
+                        Transition::CaptureStart(transition) => {
+                            transition.capture_group_index += capture_group_offset
+                        }
+                        Transition::CaptureEnd(transition) => {
+                            transition.capture_group_index += capture_group_offset
+                        }
+                        Transition::LookAheadAssertion(transition) => {
+                            transition.route_index += route_offset
+                        }
+                        Transition::LookBehindAssertion(transition) => {
+                            transition.route_index += route_offset
+                        }
+                        _ => {}
+                    }
+                }
+            }
+
+            if index != MAIN_ROUTE_INDEX {
+                merged.routes.push(route);
+                continue;
+            }
+
+            let main_route = &mut merged.routes[MAIN_ROUTE_INDEX];
+            let node_offset = main_route.nodes.len();
+
+            for mut node in route.nodes {
+                for transition_item in &mut node.transition_items {
+                    transition_item.target_node_index += node_offset;
+                }
+                main_route.nodes.push(node);
+            }
+
+            main_route.create_transition_item(
+                entry_node_index,
+                route.start_node_index + node_offset,
+                Transition::Jump(JumpTransition),
+            );
+
+            exit_node_indices.push(route.end_node_index + node_offset);
+            is_fixed_start_position &= route.is_fixed_start_position;
+        }
+    }
+
+    merged.routes[MAIN_ROUTE_INDEX].is_fixed_start_position = is_fixed_start_position;
+
+    (merged, exit_node_indices)
+}
+
+/// The result of `RegexSet::matches`.
+#[derive(Debug, PartialEq, Clone)]
+pub struct SetMatches {
+    matched: Vec<bool>,
+}
+
+impl SetMatches {
+    /// Returns whether any expression matches.
+    pub fn matched_any(&self) -> bool {
+        self.matched.iter().any(|item| *item)
+    }
+
+    /// Returns whether all the expressions match.
+    pub fn matched_all(&self) -> bool {
+        self.matched.iter().all(|item| *item)
+    }
+
+    /// Returns whether the expression of the specified index matches.
+    pub fn matched(&self, index: usize) -> bool {
+        self.matched[index]
+    }
+
+    /// Returns the number of expressions in the set.
+    pub fn len(&self) -> usize {
+        self.matched.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.matched.is_empty()
+    }
+
+    /// Returns an iterator over the indices of the matched expressions.
+    pub fn iter(&self) -> SetMatchesIter<'_> {
+        SetMatchesIter {
+            matched: self.matched.iter().enumerate(),
+        }
+    }
+}
+
+impl IntoIterator for SetMatches {
+    type Item = usize;
+    type IntoIter = SetMatchesIntoIter;
+
+    fn into_iter(self) -> Self::IntoIter {
+        SetMatchesIntoIter {
+            matched: self.matched.into_iter().enumerate(),
+        }
+    }
+}
+
+impl<'a> IntoIterator for &'a SetMatches {
+    type Item = usize;
+    type IntoIter = SetMatchesIter<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+pub struct SetMatchesIter<'a> {
+    matched: std::iter::Enumerate<std::slice::Iter<'a, bool>>,
+}
+
+impl Iterator for SetMatchesIter<'_> {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.matched
+            .find(|(_, is_matched)| **is_matched)
+            .map(|(index, _)| index)
+    }
+}
+
+pub struct SetMatchesIntoIter {
+    matched: std::iter::Enumerate<std::vec::IntoIter<bool>>,
+}
+
+impl Iterator for SetMatchesIntoIter {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.matched
+            .find(|(_, is_matched)| *is_matched)
+            .map(|(index, _)| index)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use crate::AnreError;
+
+    use super::RegexSet;
+
+    #[test]
+    fn test_regex_set_matches() {
+        // err: the invalid expression
+        assert!(RegexSet::new([r#"\w+"#, r#"(ab"#]).is_err());
+
+        let set = RegexSet::new([r#"\w+"#, r#"\d+"#, r#"foo"#, r#"bar$"#, r#"^x"#]).unwrap();
+        assert_eq!(set.len(), 5);
+
+        let matches = set.matches("foobar");
+        assert!(matches.matched_any());
+        assert!(!matches.matched_all());
+        assert_eq!(matches.iter().collect::<Vec<_>>(), vec![0, 2, 3]);
+
+        // the expressions which match at different positions
+        assert_eq!(
+            set.matches_iter("x 123 foo").collect::<Vec<_>>(),
+            vec![0, 1, 2, 4]
+        );
+
+        // the anchored expression only matches at the beginning
+        assert_eq!(set.matches_iter("ax").collect::<Vec<_>>(), vec![0]);
+
+        // the overlapping expressions
+        let set = RegexSet::new([r#"foo"#, r#"foobar"#, r#"o+b"#, r#"r$"#]).unwrap();
+        let matches = set.matches("foobar");
+        assert!(matches.matched_all());
+        assert_eq!((&matches).into_iter().count(), 4);
+
+        // no match
+        let matches = set.matches("hello");
+        assert!(!matches.matched_any());
+        assert!(!set.is_match("hello"));
+        assert_eq!(matches.len(), 4);
+        assert!(!matches.matched(0));
+
+        // empty set
+        let set = RegexSet::new::<_, &str>([]).unwrap();
+        assert!(set.is_empty());
+        assert!(!set.is_match("abc"));
+        assert!(set.matches("abc").is_empty());
+    }
+
+    #[test]
+    fn test_regex_set_routing() {
+        let set = RegexSet::new([
+            r#"^/users/\d+$"#,
+            r#"^/users/(?<name>[a-z]+)/posts$"#,
+            r#"^/static/.+\.(?:css|js)$"#,
+            r#"^/(?:users|posts)\b"#,
+            r#"(?i)^/ADMIN"#,
+        ])
+        .unwrap();
+
+        assert_eq!(set.patterns()[0], r#"^/users/\d+$"#);
+
+        assert_eq!(
+            set.matches_iter("/users/42").collect::<Vec<_>>(),
+            vec![0, 3]
+        );
+        assert_eq!(
+            set.matches_iter("/users/alice/posts").collect::<Vec<_>>(),
+            vec![1, 3]
+        );
+        assert_eq!(
+            set.matches_iter("/static/css/site.css").collect::<Vec<_>>(),
+            vec![2]
+        );
+        assert_eq!(set.matches_iter("/posts").collect::<Vec<_>>(), vec![3]);
+        assert_eq!(
+            set.matches_iter("/admin/users").collect::<Vec<_>>(),
+            vec![4]
+        );
+        assert_eq!(set.matches_iter("/unknown").count(), 0);
+    }
+
+    #[test]
+    fn test_regex_set_anre() {
+        let set = RegexSet::from_anre([
+            r#"start, "foo""#,
+            r#"char_digit.one_or_more(), end"#,
+            r#"'a'.is_before('b')"#,
+            r#"'b'.is_after('a')"#,
+            r#"'c'.is_not_after('a')"#,
+        ])
+        .unwrap();
+
+        assert_eq!(set.matches_iter("foo 123").collect::<Vec<_>>(), vec![0, 1]);
+        assert_eq!(set.matches_iter("xab").collect::<Vec<_>>(), vec![2, 3]);
+        assert_eq!(set.matches_iter("ac bc").collect::<Vec<_>>(), vec![4]);
+        assert_eq!(set.matches_iter("ac").count(), 0);
+
+        // err: back references are not supported
+        assert!(matches!(
+            RegexSet::from_anre([r#"'a'"#, r#"('a').name("x"), x"#]),
+            Err(AnreError::SyntaxIncorrect(_))
+        ));
+    }
+}
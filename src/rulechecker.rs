@@ -6,7 +6,10 @@
 
 use std::ops::{Add, BitOr, Mul};
 
-use crate::ast::{Expression, FunctionName, Literal};
+use crate::{
+    ast::{BackReference, Expression, FunctionName, Literal, Program},
+    AnreError,
+};
 
 pub enum MatchLength {
     Variable,
@@ -130,9 +133,119 @@ pub fn get_match_length(exp: &Expression) -> MatchLength {
                 let base_exp = &function_call.args[0];
                 get_match_length(base_exp)
             }
+            FunctionName::IgnoreCase
+            | FunctionName::MultiLine
+            | FunctionName::DotAll
+            | FunctionName::NotIgnoreCase
+            | FunctionName::NotMultiLine
+            | FunctionName::NotDotAll => {
+                let base_exp = &function_call.args[0];
+                get_match_length(base_exp)
+            }
         },
         Expression::Or(left_exp, right_exp) => {
             get_match_length(left_exp) | get_match_length(right_exp)
         }
     }
 }
+
+/// Checks the number and the types of the arguments of all function calls,
+/// e.g. `repeat` requires an expression and a number.
+///
+/// The ANRE parser accepts any arguments, so the AST should be checked
+/// before it is compiled or printed.
+pub fn check_program(program: &Program) -> Result<(), AnreError> {
+    program.expressions.iter().try_for_each(check_expression)
+}
+
+fn check_expression(exp: &Expression) -> Result<(), AnreError> {
+    match exp {
+        Expression::Literal(Literal::Number(n)) => Err(AnreError::SyntaxIncorrect(format!(
+            "Number literal is only allowed in repetition, number: {}.",
+            n
+        ))),
+        Expression::Group(exps) => exps.iter().try_for_each(check_expression),
+        Expression::FunctionCall(function_call) => {
+            let name = function_call.name;
+            let args = &function_call.args;
+
+            let Some(base_exp) = args.first() else {
+                return Err(AnreError::SyntaxIncorrect(format!(
+                    "Expect an expression for the argument of function \"{}\".",
+                    name
+                )));
+            };
+            check_expression(base_exp)?;
+
+            let is_number =
+                |exp: &Expression| matches!(exp, Expression::Literal(Literal::Number(_)));
+
+            let is_valid = match name {
+                FunctionName::Repeat | FunctionName::AtLeast | FunctionName::AtLeastLazy => {
+                    matches!(&args[1..], [times] if is_number(times))
+                }
+                FunctionName::RepeatRange | FunctionName::RepeatRangeLazy => {
+                    matches!(&args[1..], [from, to] if is_number(from) && is_number(to))
+                }
+                FunctionName::IsBefore
+                | FunctionName::IsNotBefore
+                | FunctionName::IsAfter
+                | FunctionName::IsNotAfter => args.len() == 2,
+                FunctionName::Name => matches!(
+                    &args[1..],
+                    [Expression::Literal(Literal::String(_))
+                        | Expression::BackReference(BackReference::Name(_))]
+                ),
+                _ => args.len() == 1,
+            };
+
+            if !is_valid {
+                return Err(AnreError::SyntaxIncorrect(get_arguments_error_message(
+                    name,
+                )));
+            }
+
+            // the lookaround assertions take two expressions
+            if matches!(
+                name,
+                FunctionName::IsBefore
+                    | FunctionName::IsNotBefore
+                    | FunctionName::IsAfter
+                    | FunctionName::IsNotAfter
+            ) {
+                check_expression(&args[1])?;
+            }
+
+            Ok(())
+        }
+        Expression::Or(left_exp, right_exp) => {
+            check_expression(left_exp)?;
+            check_expression(right_exp)
+        }
+        _ => Ok(()),
+    }
+}
+
+fn get_arguments_error_message(name: FunctionName) -> String {
+    match name {
+        FunctionName::Repeat | FunctionName::AtLeast | FunctionName::AtLeastLazy => format!(
+            "Expect a number for the repetition of function \"{}\".",
+            name
+        ),
+        FunctionName::RepeatRange | FunctionName::RepeatRangeLazy => format!(
+            "Expect two numbers for the repetition range of function \"{}\".",
+            name
+        ),
+        FunctionName::IsBefore | FunctionName::IsNotBefore => {
+            "Expect an expression for the argument of lookahead assertion.".to_owned()
+        }
+        FunctionName::IsAfter | FunctionName::IsNotAfter => {
+            "Expect an expression for the argument of lookbehind assertion.".to_owned()
+        }
+        FunctionName::Name => "Expect a name for the argument of function \"name\".".to_owned(),
+        _ if name.is_modifier() => {
+            format!("The modifier \"{}\" accepts only one expression.", name)
+        }
+        _ => format!("The function \"{}\" accepts only one expression.", name),
+    }
+}
@@ -0,0 +1,1027 @@
+// Copyright (c) 2025 Hemashushu <hippospark@gmail.com>, All rights reserved.
+//
+// This Source Code Form is subject to the terms of
+// the Mozilla Public License version 2.0 and additional exceptions.
+// For more details, see the LICENSE, LICENSE.additional, and CONTRIBUTING files.
+
+// The binary format of the object file:
+//
+// ```diagram
+// | magic "ANRE" | version (u16) |
+// | number of capture groups | capture group names ... |
+// | number of routes | routes ... |
+// | optimization stats |
+// ```
+//
+// - All integers are little-endian, the `usize` values are stored as u64,
+//   and `usize::MAX` (used by the unbounded repetitions) is stored as `u64::MAX`.
+// - A boolean is a byte of 0 or 1.
+// - A string is the byte length (u64) followed by the UTF-8 bytes.
+// - An optional value is a byte of 0 (none) or 1 (some) followed by the value.
+// - A route is the start node index, the end node index, the two anchoring flags
+//   and the list of nodes, a node is the list of transition items, and
+//   a transition item is the target node index followed by the transition,
+//   see `write_transition` for the encoding of transitions.
+//
+// The prefilter is not stored, it is rebuilt from the routes when loading.
+
+use crate::{
+    ast::{AnchorAssertionName, BoundaryAssertionName},
+    object_file::{Node, ObjectFile, OptimizationStats, Route, TransitionItem, MAIN_ROUTE_INDEX},
+    optimizer::compact_char_set,
+    prefilter::Prefilter,
+    transition::{
+        AnchorAssertionTransition, BackReferenceTransition, BoundaryAssertionTransition,
+        CaptureEndTransition, CaptureStartTransition, CharRange, CharSetItem, CharSetTransition,
+        CharTransition, CounterCheckTransition, CounterIncTransition, CounterResetTransition,
+        CounterSaveTransition, JumpTransition, LookAheadAssertionTransition,
+        LookBehindAssertionTransition, RepetitionTransition, RepetitionType, SpecialCharTransition,
+        StringTransition, Transition, TrieTransition,
+    },
+    AnreError,
+};
+
+const MAGIC: &[u8; 4] = b"ANRE";
+
+// The version of the binary format, it is increased whenever
+// the format is changed.
+pub const FORMAT_VERSION: u16 = 1;
+
+// Tags of transitions
+const TAG_JUMP: u8 = 0;
+const TAG_CHAR: u8 = 1;
+const TAG_SPECIAL_CHAR: u8 = 2;
+const TAG_STRING: u8 = 3;
+const TAG_CHAR_SET: u8 = 4;
+const TAG_TRIE: u8 = 5;
+const TAG_BACK_REFERENCE: u8 = 6;
+const TAG_ANCHOR_ASSERTION: u8 = 7;
+const TAG_BOUNDARY_ASSERTION: u8 = 8;
+const TAG_CAPTURE_START: u8 = 9;
+const TAG_CAPTURE_END: u8 = 10;
+const TAG_COUNTER_RESET: u8 = 11;
+const TAG_COUNTER_SAVE: u8 = 12;
+const TAG_COUNTER_INC: u8 = 13;
+const TAG_COUNTER_CHECK: u8 = 14;
+const TAG_REPETITION: u8 = 15;
+const TAG_LOOK_AHEAD_ASSERTION: u8 = 16;
+const TAG_LOOK_BEHIND_ASSERTION: u8 = 17;
+
+/// Serializes the object file to bytes.
+pub fn serialize(object_file: &ObjectFile) -> Vec<u8> {
+    let mut writer = Writer { bytes: vec![] };
+    writer.bytes.extend_from_slice(MAGIC);
+    writer
+        .bytes
+        .extend_from_slice(&FORMAT_VERSION.to_le_bytes());
+
+    writer.write_usize(object_file.capture_group_names.len());
+    for name in &object_file.capture_group_names {
+        match name {
+            Some(name) => {
+                writer.write_bool(true);
+                writer.write_str(name);
+            }
+            None => writer.write_bool(false),
+        }
+    }
+
+    writer.write_usize(object_file.routes.len());
+    for route in &object_file.routes {
+        writer.write_usize(route.start_node_index);
+        writer.write_usize(route.end_node_index);
+        writer.write_bool(route.is_fixed_start_position);
+        writer.write_bool(route.is_fixed_end_position);
+
+        writer.write_usize(route.nodes.len());
+        for node in &route.nodes {
+            writer.write_usize(node.transition_items.len());
+            for item in &node.transition_items {
+                writer.write_usize(item.target_node_index);
+                writer.write_transition(&item.transition);
+            }
+        }
+    }
+
+    match &object_file.optimization_stats {
+        Some(stats) => {
+            writer.write_bool(true);
+            writer.write_usize(stats.nodes_before);
+            writer.write_usize(stats.nodes_after);
+            writer.write_usize(stats.transitions_before);
+            writer.write_usize(stats.transitions_after);
+        }
+        None => writer.write_bool(false),
+    }
+
+    writer.bytes
+}
+
+/// Deserializes the object file from bytes.
+///
+/// The data is validated, e.g. the node, route and capture group indices must be
+/// in range and the chars must be valid, an error is returned if the data is
+/// corrupted or the version of the format does not match.
+pub fn deserialize(bytes: &[u8]) -> Result<ObjectFile, AnreError> {
+    let mut reader = Reader { bytes, position: 0 };
+
+    if reader.read_bytes(MAGIC.len())? != MAGIC {
+        return Err(AnreError::InvalidObjectFile(
+            "The data is not an object file.".to_owned(),
+        ));
+    }
+
+    let version = u16::from_le_bytes(reader.read_array()?);
+    if version != FORMAT_VERSION {
+        return Err(AnreError::InvalidObjectFile(format!(
+            "Unsupported object file version: {}, expected: {}.",
+            version, FORMAT_VERSION
+        )));
+    }
+
+    let mut object_file = ObjectFile::new();
+
+    let number_of_capture_groups = reader.read_usize()?;
+    for _ in 0..number_of_capture_groups {
+        let name = if reader.read_bool()? {
+            Some(reader.read_string()?)
+        } else {
+            None
+        };
+        object_file.capture_group_names.push(name);
+    }
+
+    let number_of_routes = reader.read_usize()?;
+    for _ in 0..number_of_routes {
+        let start_node_index = reader.read_usize()?;
+        let end_node_index = reader.read_usize()?;
+        let is_fixed_start_position = reader.read_bool()?;
+        let is_fixed_end_position = reader.read_bool()?;
+
+        let mut nodes = vec![];
+        let number_of_nodes = reader.read_usize()?;
+        for _ in 0..number_of_nodes {
+            let mut transition_items = vec![];
+            let number_of_items = reader.read_usize()?;
+            for _ in 0..number_of_items {
+                let target_node_index = reader.read_usize()?;
+                let transition = reader.read_transition()?;
+                transition_items.push(TransitionItem {
+                    transition,
+                    target_node_index,
+                });
+            }
+            nodes.push(Node { transition_items });
+        }
+
+        object_file.routes.push(Route {
+            nodes,
+            start_node_index,
+            end_node_index,
+            is_fixed_start_position,
+            is_fixed_end_position,
+        });
+    }
+
+    if reader.read_bool()? {
+        object_file.optimization_stats = Some(OptimizationStats {
+            nodes_before: reader.read_usize()?,
+            nodes_after: reader.read_usize()?,
+            transitions_before: reader.read_usize()?,
+            transitions_after: reader.read_usize()?,
+        });
+    }
+
+    if reader.position != bytes.len() {
+        return Err(reader.error("unexpected trailing data"));
+    }
+
+    validate_indices(&object_file)?;
+    validate_transitions(&object_file)?;
+
+    object_file.prefilter = Prefilter::new(&object_file);
+    Ok(object_file)
+}
+
+// Checks the indices of nodes, routes and capture groups.
+//
+// The routes of the lookaround assertions must be sub-routes and must not
+// run themselves (directly or through other routes), and the back references
+// must refer to a capture group (other than the group 0) with exactly
+// one start and one end, otherwise the engines would recurse endlessly
+// or read a reversed range of the text.
+fn validate_indices(object_file: &ObjectFile) -> Result<(), AnreError> {
+    let number_of_routes = object_file.routes.len();
+    let number_of_capture_groups = object_file.capture_group_names.len();
+
+    if number_of_routes == 0 {
+        return Err(AnreError::InvalidObjectFile(
+            "The object file has no routes.".to_owned(),
+        ));
+    }
+
+    for (route_index, route) in object_file.routes.iter().enumerate() {
+        let number_of_nodes = route.nodes.len();
+        let error = |message: &str| {
+            Err(AnreError::InvalidObjectFile(format!(
+                "Invalid object file: {} in route {}.",
+                message, route_index
+            )))
+        };
+
+        if route.start_node_index >= number_of_nodes || route.end_node_index >= number_of_nodes {
+            return error("the entry or exit node index is out of range");
+        }
+
+        for item in route.nodes.iter().flat_map(|node| &node.transition_items) {
+            if item.target_node_index >= number_of_nodes {
+                return error("the target node index is out of range");
+            }
+
+            let is_valid = match &item.transition {
+                Transition::BackReference(BackReferenceTransition {
+                    capture_group_index,
+                })
+                | Transition::CaptureStart(CaptureStartTransition {
+                    capture_group_index,
+                })
+                | Transition::CaptureEnd(CaptureEndTransition {
+                    capture_group_index,
+                }) => *capture_group_index < number_of_capture_groups,
+                // The main route can not be run by lookaround assertions.
+                Transition::LookAheadAssertion(LookAheadAssertionTransition {
+                    route_index,
+                    ..
+                })
+                | Transition::LookBehindAssertion(LookBehindAssertionTransition {
+                    route_index,
+                    ..
+                }) => *route_index != MAIN_ROUTE_INDEX && *route_index < number_of_routes,
+                _ => true,
+            };
+
+            if !is_valid {
+                return error("the capture group or route index is invalid");
+            }
+        }
+    }
+
+    if let Some(route_index) = object_file.find_recursive_route() {
+        return Err(AnreError::InvalidObjectFile(format!(
+            "Invalid object file: the lookaround assertions run the route {} recursively.",
+            route_index
+        )));
+    }
+
+    // The number of the start and end transitions of each capture group.
+    let mut capture_start_counts = vec![0; number_of_capture_groups];
+    let mut capture_end_counts = vec![0; number_of_capture_groups];
+    let transitions = object_file
+        .routes
+        .iter()
+        .flat_map(|route| &route.nodes)
+        .flat_map(|node| &node.transition_items)
+        .map(|item| &item.transition);
+
+    for transition in transitions.clone() {
+        match transition {
+            Transition::CaptureStart(transition) => {
+                capture_start_counts[transition.capture_group_index] += 1
+            }
+            Transition::CaptureEnd(transition) => {
+                capture_end_counts[transition.capture_group_index] += 1
+            }
+            _ => {}
+        }
+    }
+
+    for transition in transitions {
+        if let Transition::BackReference(transition) = transition {
+            let index = transition.capture_group_index;
+            if index == 0 || capture_start_counts[index] != 1 || capture_end_counts[index] != 1 {
+                return Err(AnreError::InvalidObjectFile(format!(
+                    "Invalid object file: the back reference refers to the capture group {}, \
+                    which is not a single group.",
+                    index
+                )));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+// Checks the transitions which would break the assumptions of the engines,
+// i.e. the words of trie transitions are prefix-free, and the counter
+// transitions (of repetitions) are balanced, otherwise the engines panic
+// when popping the counter stack.
+//
+// The indices should be checked before calling this function.
+fn validate_transitions(object_file: &ObjectFile) -> Result<(), AnreError> {
+    for (route_index, route) in object_file.routes.iter().enumerate() {
+        let error = |message: &str| {
+            Err(AnreError::InvalidObjectFile(format!(
+                "Invalid object file: {} in route {}.",
+                message, route_index
+            )))
+        };
+
+        let has_invalid_trie = route
+            .nodes
+            .iter()
+            .flat_map(|node| &node.transition_items)
+            .any(|item| match &item.transition {
+                Transition::Trie(transition) => !transition.is_prefix_free(),
+                _ => false,
+            });
+
+        if has_invalid_trie {
+            return error("the words of the trie are empty or not prefix-free");
+        }
+
+        // The depth of the counter stack at each node, it must be the same
+        // for all the paths to the node.
+        let mut depths: Vec<Option<usize>> = vec![None; route.nodes.len()];
+        depths[route.start_node_index] = Some(0);
+        let mut pending = vec![route.start_node_index];
+
+        while let Some(node_index) = pending.pop() {
+            let depth = depths[node_index].unwrap();
+
+            for item in &route.nodes[node_index].transition_items {
+                let next_depth = match &item.transition {
+                    Transition::CounterSave(_) => depth + 1,
+                    Transition::CounterInc(_) if depth == 0 => {
+                        return error("the counter transitions are unbalanced");
+                    }
+                    Transition::CounterInc(_) => depth - 1,
+                    _ => depth,
+                };
+
+                match depths[item.target_node_index] {
+                    None => {
+                        depths[item.target_node_index] = Some(next_depth);
+                        pending.push(item.target_node_index);
+                    }
+                    Some(target_depth) if target_depth != next_depth => {
+                        return error("the counter transitions are unbalanced");
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        if depths[route.end_node_index].is_some_and(|depth| depth != 0) {
+            return error("the counter transitions are unbalanced");
+        }
+    }
+
+    Ok(())
+}
+
+struct Writer {
+    bytes: Vec<u8>,
+}
+
+impl Writer {
+    fn write_u8(&mut self, value: u8) {
+        self.bytes.push(value);
+    }
+
+    fn write_bool(&mut self, value: bool) {
+        self.bytes.push(value as u8);
+    }
+
+    fn write_u32(&mut self, value: u32) {
+        self.bytes.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn write_usize(&mut self, value: usize) {
+        let value = if value == usize::MAX {
+            u64::MAX
+        } else {
+            value as u64
+        };
+        self.bytes.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn write_str(&mut self, value: &str) {
+        self.write_usize(value.len());
+        self.bytes.extend_from_slice(value.as_bytes());
+    }
+
+    fn write_repetition_type(&mut self, repetition_type: &RepetitionType) {
+        match repetition_type {
+            RepetitionType::Specified(times) => {
+                self.write_u8(0);
+                self.write_usize(*times);
+            }
+            RepetitionType::Range(from, to) => {
+                self.write_u8(1);
+                self.write_usize(*from);
+                self.write_usize(*to);
+            }
+        }
+    }
+
+    // Writes the tag of the transition followed by its fields.
+    // The byte lengths of chars and strings are not stored since
+    // they are calculated from the codepoints, and the trie is
+    // rebuilt from its words.
+    fn write_transition(&mut self, transition: &Transition) {
+        match transition {
+            Transition::Jump(_) => self.write_u8(TAG_JUMP),
+            Transition::Char(transition) => {
+                self.write_u8(TAG_CHAR);
+                self.write_u32(transition.codepoint);
+                self.write_bool(transition.ignore_case);
+            }
+            Transition::SpecialChar(transition) => {
+                self.write_u8(TAG_SPECIAL_CHAR);
+                self.write_bool(transition.dot_matches_new_line);
+                self.write_bool(transition.matches_any_byte);
+            }
+            Transition::String(transition) => {
+                self.write_u8(TAG_STRING);
+                self.write_usize(transition.codepoints.len());
+                for codepoint in &transition.codepoints {
+                    self.write_u32(*codepoint);
+                }
+                self.write_bool(transition.ignore_case);
+            }
+            Transition::CharSet(transition) => {
+                self.write_u8(TAG_CHAR_SET);
+                self.write_usize(transition.items.len());
+                for item in &transition.items {
+                    match item {
+                        CharSetItem::Char(codepoint) => {
+                            self.write_u8(0);
+                            self.write_u32(*codepoint);
+                        }
+                        CharSetItem::Range(range) => {
+                            self.write_u8(1);
+                            self.write_u32(range.start);
+                            self.write_u32(range.end_included);
+                        }
+                    }
+                }
+                self.write_bool(transition.negative);
+                self.write_bool(transition.ignore_case);
+
+                // The bitmap is rebuilt by the optimizer when loading.
+                self.write_bool(transition.ascii_bitmap.is_some());
+            }
+            Transition::Trie(transition) => {
+                self.write_u8(TAG_TRIE);
+                self.write_usize(transition.words.len());
+                for word in &transition.words {
+                    self.write_str(word);
+                }
+                self.write_bool(transition.ignore_case);
+            }
+            Transition::BackReference(transition) => {
+                self.write_u8(TAG_BACK_REFERENCE);
+                self.write_usize(transition.capture_group_index);
+            }
+            Transition::AnchorAssertion(transition) => {
+                self.write_u8(TAG_ANCHOR_ASSERTION);
+                self.write_u8(match transition.name {
+                    AnchorAssertionName::Start => 0,
+                    AnchorAssertionName::End => 1,
+                });
+                self.write_bool(transition.multi_line);
+                self.write_bool(transition.crlf);
+            }
+            Transition::BoundaryAssertion(transition) => {
+                self.write_u8(TAG_BOUNDARY_ASSERTION);
+                self.write_u8(match transition.name {
+                    BoundaryAssertionName::IsBound => 0,
+                    BoundaryAssertionName::IsNotBound => 1,
+                });
+            }
+            Transition::CaptureStart(transition) => {
+                self.write_u8(TAG_CAPTURE_START);
+                self.write_usize(transition.capture_group_index);
+            }
+            Transition::CaptureEnd(transition) => {
+                self.write_u8(TAG_CAPTURE_END);
+                self.write_usize(transition.capture_group_index);
+            }
+            Transition::CounterReset(_) => self.write_u8(TAG_COUNTER_RESET),
+            Transition::CounterSave(_) => self.write_u8(TAG_COUNTER_SAVE),
+            Transition::CounterInc(_) => self.write_u8(TAG_COUNTER_INC),
+            Transition::CounterCheck(transition) => {
+                self.write_u8(TAG_COUNTER_CHECK);
+                self.write_repetition_type(&transition.repetition_type);
+            }
+            Transition::Repetition(transition) => {
+                self.write_u8(TAG_REPETITION);
+                self.write_repetition_type(&transition.repetition_type);
+            }
+            Transition::LookAheadAssertion(transition) => {
+                self.write_u8(TAG_LOOK_AHEAD_ASSERTION);
+                self.write_usize(transition.route_index);
+                self.write_bool(transition.negative);
+            }
+            Transition::LookBehindAssertion(transition) => {
+                self.write_u8(TAG_LOOK_BEHIND_ASSERTION);
+                self.write_usize(transition.route_index);
+                self.write_bool(transition.negative);
+                self.write_usize(transition.match_length_in_char);
+            }
+        }
+    }
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl Reader<'_> {
+    fn error(&self, message: &str) -> AnreError {
+        AnreError::InvalidObjectFile(format!(
+            "Invalid object file: {} at offset {}.",
+            message, self.position
+        ))
+    }
+
+    fn read_bytes(&mut self, length: usize) -> Result<&[u8], AnreError> {
+        if length > self.bytes.len() - self.position {
+            return Err(self.error("unexpected end of data"));
+        }
+
+        let bytes = &self.bytes[self.position..self.position + length];
+        self.position += length;
+        Ok(bytes)
+    }
+
+    fn read_array<const N: usize>(&mut self) -> Result<[u8; N], AnreError> {
+        let mut array = [0u8; N];
+        array.copy_from_slice(self.read_bytes(N)?);
+        Ok(array)
+    }
+
+    fn read_u8(&mut self) -> Result<u8, AnreError> {
+        Ok(self.read_array::<1>()?[0])
+    }
+
+    fn read_bool(&mut self) -> Result<bool, AnreError> {
+        match self.read_u8()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => {
+                self.position -= 1;
+                Err(self.error("invalid boolean"))
+            }
+        }
+    }
+
+    fn read_usize(&mut self) -> Result<usize, AnreError> {
+        let value = u64::from_le_bytes(self.read_array()?);
+        if value == u64::MAX {
+            Ok(usize::MAX)
+        } else {
+            usize::try_from(value).map_err(|_| self.error("integer out of range"))
+        }
+    }
+
+    // Reads a codepoint and checks whether it is a valid char.
+    fn read_codepoint(&mut self) -> Result<u32, AnreError> {
+        let codepoint = u32::from_le_bytes(self.read_array()?);
+        match char::from_u32(codepoint) {
+            Some(_) => Ok(codepoint),
+            None => {
+                self.position -= 4;
+                Err(self.error("invalid char"))
+            }
+        }
+    }
+
+    fn read_string(&mut self) -> Result<String, AnreError> {
+        let length = self.read_usize()?;
+        let bytes = self.read_bytes(length)?.to_vec();
+        String::from_utf8(bytes).map_err(|_| self.error("invalid UTF-8 string"))
+    }
+
+    fn read_repetition_type(&mut self) -> Result<RepetitionType, AnreError> {
+        match self.read_u8()? {
+            0 => Ok(RepetitionType::Specified(self.read_usize()?)),
+            1 => Ok(RepetitionType::Range(
+                self.read_usize()?,
+                self.read_usize()?,
+            )),
+            _ => Err(self.error("invalid repetition type")),
+        }
+    }
+
+    fn read_transition(&mut self) -> Result<Transition, AnreError> {
+        let transition = match self.read_u8()? {
+            TAG_JUMP => Transition::Jump(JumpTransition),
+            TAG_CHAR => {
+                let codepoint = self.read_codepoint()?;
+                let c = char::from_u32(codepoint).unwrap();
+                if self.read_bool()? {
+                    Transition::Char(CharTransition::new_ignore_case(c))
+                } else {
+                    Transition::Char(CharTransition::new(c))
+                }
+            }
+            TAG_SPECIAL_CHAR => {
+                let dot_matches_new_line = self.read_bool()?;
+                let matches_any_byte = self.read_bool()?;
+                Transition::SpecialChar(SpecialCharTransition::new(
+                    dot_matches_new_line,
+                    matches_any_byte,
+                ))
+            }
+            TAG_STRING => {
+                let mut s = String::new();
+                let length = self.read_usize()?;
+                for _ in 0..length {
+                    s.push(char::from_u32(self.read_codepoint()?).unwrap());
+                }
+                if self.read_bool()? {
+                    Transition::String(StringTransition::new_ignore_case(&s))
+                } else {
+                    Transition::String(StringTransition::new(&s))
+                }
+            }
+            TAG_CHAR_SET => {
+                let mut items = vec![];
+                let length = self.read_usize()?;
+                for _ in 0..length {
+                    let item = match self.read_u8()? {
+                        0 => CharSetItem::Char(self.read_codepoint()?),
+                        1 => {
+                            let start = self.read_codepoint()?;
+                            let end_included = self.read_codepoint()?;
+                            if start > end_included {
+                                return Err(self.error("invalid char range"));
+                            }
+                            CharSetItem::Range(CharRange {
+                                start,
+                                end_included,
+                            })
+                        }
+                        _ => return Err(self.error("invalid char set item")),
+                    };
+                    items.push(item);
+                }
+
+                let negative = self.read_bool()?;
+                let mut transition = if self.read_bool()? {
+                    CharSetTransition::new_ignore_case(items, negative)
+                } else {
+                    CharSetTransition::new(items, negative)
+                };
+
+                if self.read_bool()? {
+                    if transition.ignore_case {
+                        return Err(self.error("invalid compact char set"));
+                    }
+                    compact_char_set(&mut transition);
+                }
+
+                Transition::CharSet(transition)
+            }
+            TAG_TRIE => {
+                let mut words = vec![];
+                let length = self.read_usize()?;
+                for _ in 0..length {
+                    words.push(self.read_string()?);
+                }
+                let ignore_case = self.read_bool()?;
+                Transition::Trie(TrieTransition::new(words, ignore_case))
+            }
+            TAG_BACK_REFERENCE => {
+                Transition::BackReference(BackReferenceTransition::new(self.read_usize()?))
+            }
+            TAG_ANCHOR_ASSERTION => {
+                let name = match self.read_u8()? {
+                    0 => AnchorAssertionName::Start,
+                    1 => AnchorAssertionName::End,
+                    _ => return Err(self.error("invalid anchor assertion name")),
+                };
+                let multi_line = self.read_bool()?;
+                let crlf = self.read_bool()?;
+                Transition::AnchorAssertion(AnchorAssertionTransition {
+                    name,
+                    multi_line,
+                    crlf,
+                })
+            }
+            TAG_BOUNDARY_ASSERTION => {
+                let name = match self.read_u8()? {
+                    0 => BoundaryAssertionName::IsBound,
+                    1 => BoundaryAssertionName::IsNotBound,
+                    _ => return Err(self.error("invalid boundary assertion name")),
+                };
+                Transition::BoundaryAssertion(BoundaryAssertionTransition::new(name))
+            }
+            TAG_CAPTURE_START => {
+                Transition::CaptureStart(CaptureStartTransition::new(self.read_usize()?))
+            }
+            TAG_CAPTURE_END => {
+                Transition::CaptureEnd(CaptureEndTransition::new(self.read_usize()?))
+            }
+            TAG_COUNTER_RESET => Transition::CounterReset(CounterResetTransition),
+            TAG_COUNTER_SAVE => Transition::CounterSave(CounterSaveTransition),
+            TAG_COUNTER_INC => Transition::CounterInc(CounterIncTransition),
+            TAG_COUNTER_CHECK => {
+                Transition::CounterCheck(CounterCheckTransition::new(self.read_repetition_type()?))
+            }
+            TAG_REPETITION => {
+                Transition::Repetition(RepetitionTransition::new(self.read_repetition_type()?))
+            }
+            TAG_LOOK_AHEAD_ASSERTION => {
+                let route_index = self.read_usize()?;
+                let negative = self.read_bool()?;
+                Transition::LookAheadAssertion(LookAheadAssertionTransition::new(
+                    route_index,
+                    negative,
+                ))
+            }
+            TAG_LOOK_BEHIND_ASSERTION => {
+                let route_index = self.read_usize()?;
+                let negative = self.read_bool()?;
+                let match_length_in_char = self.read_usize()?;
+                Transition::LookBehindAssertion(LookBehindAssertionTransition::new(
+                    route_index,
+                    negative,
+                    match_length_in_char,
+                ))
+            }
+            _ => {
+                self.position -= 1;
+                return Err(self.error("unknown transition"));
+            }
+        };
+
+        Ok(transition)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_str_eq;
+
+    use crate::{
+        compiler::{
+            compile_from_anre, compile_from_regex, compile_from_regex_with_options, CompileOptions,
+        },
+        object_file::ObjectFile,
+        optimizer::optimize,
+        transition::{CounterIncTransition, JumpTransition, Transition, TrieTransition},
+        AnreError,
+    };
+
+    use super::{deserialize, serialize, FORMAT_VERSION};
+
+    #[test]
+    fn test_serialize_and_deserialize() {
+        let patterns = [
+            r#"abc"#,
+            r#"a.c"#,
+            r#"(?s)a.c"#,
+            r#"(?i)hello[a-f\d]+"#,
+            r#"[^\w\s]"#,
+            r#"(?i)[^a-z]"#,
+            r#"^(?<year>\d{4})-(?<month>\d{1,2})$"#,
+            r#"(?m)^\w+$"#,
+            r#"\bfoo\B"#,
+            r#"(a|b)\1"#,
+            r#"(?:ab){2,}?c*"#,
+            r#"x{3}y{2,5}z+"#,
+            r#"(?<=ab)cd(?=ef)"#,
+            r#"(?<!ij)x(?!gh)"#,
+            r#"one|two|three|four|five|six|seven|eight"#,
+            r#"文字|中文"#,
+            r#""#,
+        ];
+
+        let options = [
+            CompileOptions::default(),
+            CompileOptions {
+                multi_line: true,
+                crlf: true,
+                dot_matches_any_byte: true,
+                ..CompileOptions::default()
+            },
+        ];
+
+        for (pattern, options) in patterns
+            .iter()
+            .flat_map(|pattern| options.iter().map(move |options| (pattern, options)))
+        {
+            let raw = compile_from_regex_with_options(pattern, options).unwrap();
+            let mut optimized = compile_from_regex_with_options(pattern, options).unwrap();
+            optimize(&mut optimized);
+
+            for object_file in [raw, optimized] {
+                let bytes = serialize(&object_file);
+                let loaded = deserialize(&bytes).unwrap();
+
+                assert_str_eq!(
+                    loaded.get_debug_text(),
+                    object_file.get_debug_text(),
+                    "pattern: {}",
+                    pattern
+                );
+                assert_eq!(serialize(&loaded), bytes, "pattern: {}", pattern);
+            }
+        }
+    }
+
+    #[test]
+    fn test_deserialize_invalid_data() {
+        let object_file = compile_from_regex(r#"(?<foo>a|bc)[x-z]\k<foo>"#).unwrap();
+        let bytes = serialize(&object_file);
+
+        let is_invalid =
+            |bytes: &[u8]| matches!(deserialize(bytes), Err(AnreError::InvalidObjectFile(_)));
+
+        // truncated data
+        for length in 0..bytes.len() {
+            assert!(is_invalid(&bytes[..length]), "length: {}", length);
+        }
+
+        // trailing data
+        let mut data = bytes.clone();
+        data.push(0);
+        assert!(is_invalid(&data));
+
+        // wrong magic
+        let mut data = bytes.clone();
+        data[0] = b'X';
+        assert_eq!(
+            deserialize(&data).err(),
+            Some(AnreError::InvalidObjectFile(
+                "The data is not an object file.".to_owned()
+            ))
+        );
+
+        // version mismatch
+        let mut data = bytes.clone();
+        data[4..6].copy_from_slice(&(FORMAT_VERSION + 1).to_le_bytes());
+        assert_eq!(
+            deserialize(&data).err(),
+            Some(AnreError::InvalidObjectFile(format!(
+                "Unsupported object file version: {}, expected: {}.",
+                FORMAT_VERSION + 1,
+                FORMAT_VERSION
+            )))
+        );
+
+        // the target node index is out of range
+        let mut object_file = ObjectFile::new();
+        let route_index = object_file.create_route();
+        let route = &mut object_file.routes[route_index];
+        let node_index = route.create_node();
+        route.create_transition_item(node_index, 1, Transition::Jump(JumpTransition));
+        assert_eq!(
+            deserialize(&serialize(&object_file)).err(),
+            Some(AnreError::InvalidObjectFile(
+                "Invalid object file: the target node index is out of range in route 0.".to_owned()
+            ))
+        );
+
+        // the counter transitions are unbalanced
+        let mut object_file = ObjectFile::new();
+        let route_index = object_file.create_route();
+        let route = &mut object_file.routes[route_index];
+        let in_node_index = route.create_node();
+        let out_node_index = route.create_node();
+        route.create_transition_item(
+            in_node_index,
+            out_node_index,
+            Transition::CounterInc(CounterIncTransition),
+        );
+        route.end_node_index = out_node_index;
+        assert_eq!(
+            deserialize(&serialize(&object_file)).err(),
+            Some(AnreError::InvalidObjectFile(
+                "Invalid object file: the counter transitions are unbalanced in route 0."
+                    .to_owned()
+            ))
+        );
+
+        // the words of the trie are not prefix-free
+        for words in [vec!["a", "ab"], vec![""]] {
+            let mut object_file = ObjectFile::new();
+            let route_index = object_file.create_route();
+            let route = &mut object_file.routes[route_index];
+            let in_node_index = route.create_node();
+            let out_node_index = route.create_node();
+            let words = words.iter().map(|word| word.to_string()).collect();
+            route.create_transition_item(
+                in_node_index,
+                out_node_index,
+                Transition::Trie(TrieTransition::new(words, false)),
+            );
+            route.end_node_index = out_node_index;
+            assert_eq!(
+                deserialize(&serialize(&object_file)).err(),
+                Some(AnreError::InvalidObjectFile(
+                    "Invalid object file: the words of the trie are empty or not prefix-free in route 0."
+                        .to_owned()
+                ))
+            );
+        }
+
+        // the lookaround assertion runs the main route
+        let mut object_file = compile_from_regex(r#"a(?=b)|(?<!c)d"#).unwrap();
+        for transition in transitions_mut(&mut object_file) {
+            if let Transition::LookBehindAssertion(transition) = transition {
+                transition.route_index = 0;
+            }
+        }
+        assert_eq!(
+            deserialize(&serialize(&object_file)).err(),
+            Some(AnreError::InvalidObjectFile(
+                "Invalid object file: the capture group or route index is invalid in route 0."
+                    .to_owned()
+            ))
+        );
+
+        // the lookaround assertions run the routes recursively
+        for (from_route_index, to_route_index) in [(1, 1), (2, 1)] {
+            let mut object_file = compile_from_regex(r#"a(?=b(?=c(?=d)))"#).unwrap();
+            for transition in object_file.routes[from_route_index]
+                .nodes
+                .iter_mut()
+                .flat_map(|node| &mut node.transition_items)
+                .map(|item| &mut item.transition)
+            {
+                if let Transition::LookAheadAssertion(transition) = transition {
+                    transition.route_index = to_route_index;
+                }
+            }
+            assert_eq!(
+                deserialize(&serialize(&object_file)).err(),
+                Some(AnreError::InvalidObjectFile(
+                    "Invalid object file: the lookaround assertions run the route 1 recursively."
+                        .to_owned()
+                ))
+            );
+        }
+
+        // the back reference refers to a group with two starts and no end
+        let mut object_file = compile_from_regex(r#"(?<y>\d{4})-(\d+)\1"#).unwrap();
+        for transition in transitions_mut(&mut object_file) {
+            if let Transition::CaptureStart(transition) = transition {
+                if transition.capture_group_index == 2 {
+                    transition.capture_group_index = 1;
+                }
+            }
+        }
+        assert_eq!(
+            deserialize(&serialize(&object_file)).err(),
+            Some(AnreError::InvalidObjectFile(
+                "Invalid object file: the back reference refers to the capture group 1, \
+                which is not a single group."
+                    .to_owned()
+            ))
+        );
+
+        // the back reference refers to the group 0
+        let mut object_file = compile_from_regex(r#"(a)\1"#).unwrap();
+        for transition in transitions_mut(&mut object_file) {
+            if let Transition::BackReference(transition) = transition {
+                transition.capture_group_index = 0;
+            }
+        }
+        assert_eq!(
+            deserialize(&serialize(&object_file)).err(),
+            Some(AnreError::InvalidObjectFile(
+                "Invalid object file: the back reference refers to the capture group 0, \
+                which is not a single group."
+                    .to_owned()
+            ))
+        );
+
+        // invalid char
+        let mut data = serialize(&compile_from_anre("'a'").unwrap());
+        let position = data
+            .windows(4)
+            .position(|w| w == 97u32.to_le_bytes())
+            .unwrap();
+        data[position..position + 4].copy_from_slice(&0xD800u32.to_le_bytes());
+        assert_eq!(
+            deserialize(&data).err(),
+            Some(AnreError::InvalidObjectFile(format!(
+                "Invalid object file: invalid char at offset {}.",
+                position
+            )))
+        );
+    }
+
+    fn transitions_mut(object_file: &mut ObjectFile) -> impl Iterator<Item = &mut Transition> {
+        object_file
+            .routes
+            .iter_mut()
+            .flat_map(|route| &mut route.nodes)
+            .flat_map(|node| &mut node.transition_items)
+            .map(|item| &mut item.transition)
+    }
+}
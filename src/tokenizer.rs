@@ -0,0 +1,416 @@
+// Copyright (c) 2025 Hemashushu <hippospark@gmail.com>, All rights reserved.
+//
+// This Source Code Form is subject to the terms of
+// the Mozilla Public License version 2.0 and additional exceptions.
+// For more details, see the LICENSE, LICENSE.additional, and CONTRIBUTING files.
+
+use std::time::Duration;
+
+use crate::{
+    compiler::{compile_from_anre, compile_from_regex},
+    context::{Budget, Context},
+    location::Location,
+    object_file::ObjectFile,
+    optimizer,
+    process::start_process,
+    regex::{Anchored, Limits},
+    AnreError,
+};
+
+/// A tokenizer (lexer) which splits a text into tokens by a list of rules.
+///
+/// At each position, all the rules are matched at that position (i.e. anchored),
+/// and the rule with the longest match wins, if several rules have the same length,
+/// the rule added first wins. The text matched by a skip rule (e.g. white spaces
+/// and comments) does not generate a token, and the text which matches
+/// no rule generates an error token.
+///
+/// Note that the match of each rule is the same as `Regex::find`, i.e. the
+/// alternatives inside a rule are leftmost-first rather than the longest,
+/// e.g. the rule `a|ab` matches only "a" of the text "ab", so the longer
+/// alternative should be placed first (i.e. `ab|a`).
+///
+/// e.g.
+///
+/// ```
+/// use regex_anre::TokenizerBuilder;
+///
+/// let tokenizer = TokenizerBuilder::new("error")
+///     .rule("number", r"\d+")
+///     .rule("keyword", r"let")
+///     .rule("name", r"[a-z]+")
+///     .rule("symbol", r"[=;]")
+///     .skip(r"\s+")
+///     .build()
+///     .unwrap();
+///
+/// // "let" matches both "keyword" and "name" with the same length, the rule added first wins,
+/// // "letter" is a "name" since it is longer than the match of "keyword".
+/// let kinds: Vec<&str> = tokenizer.tokenize("let letter = 1;").map(|t| t.kind).collect();
+/// assert_eq!(kinds, vec!["keyword", "name", "symbol", "number", "symbol"]);
+/// ```
+pub struct Tokenizer<K> {
+    rules: Vec<Rule<K>>,
+    error_kind: K,
+    limits: Limits,
+}
+
+struct Rule<K> {
+    kind: Option<K>, // `None` for skip rules.
+    object_file: ObjectFile,
+}
+
+/// A builder for the rules of a `Tokenizer`.
+pub struct TokenizerBuilder<K> {
+    rules: Vec<(Option<K>, String, bool)>, // (kind, pattern, is_anre)
+    error_kind: K,
+    limits: Limits,
+}
+
+impl<K: Clone> TokenizerBuilder<K> {
+    /// Creates a builder, `error_kind` is the kind of the tokens
+    /// for the text which matches no rule.
+    pub fn new(error_kind: K) -> Self {
+        TokenizerBuilder {
+            rules: vec![],
+            error_kind,
+            limits: Limits::default(),
+        }
+    }
+
+    /// Adds a rule of the traditional regular expression.
+    pub fn rule(&mut self, kind: K, pattern: &str) -> &mut Self {
+        self.rules.push((Some(kind), pattern.to_owned(), false));
+        self
+    }
+
+    /// Adds a rule of the ANRE expression.
+    pub fn rule_anre(&mut self, kind: K, expression: &str) -> &mut Self {
+        self.rules.push((Some(kind), expression.to_owned(), true));
+        self
+    }
+
+    /// Adds a skip rule of the traditional regular expression.
+    pub fn skip(&mut self, pattern: &str) -> &mut Self {
+        self.rules.push((None, pattern.to_owned(), false));
+        self
+    }
+
+    /// Adds a skip rule of the ANRE expression.
+    pub fn skip_anre(&mut self, expression: &str) -> &mut Self {
+        self.rules.push((None, expression.to_owned(), true));
+        self
+    }
+
+    /// Limits the number of executed transitions of matching each rule
+    /// at each position, see `RegexBuilder::step_limit`.
+    ///
+    /// Once the limit is exceeded, the rule is treated as not matching
+    /// at that position, so a rule which takes exponential time
+    /// (e.g. `(a+)+b`) can not stall the tokenizer.
+    pub fn step_limit(&mut self, limit: usize) -> &mut Self {
+        self.limits.step_limit = Some(limit);
+        self
+    }
+
+    /// Limits the time of matching each rule at each position,
+    /// see `step_limit`.
+    pub fn time_limit(&mut self, limit: Duration) -> &mut Self {
+        self.limits.time_limit = Some(limit);
+        self
+    }
+
+    pub fn build(&self) -> Result<Tokenizer<K>, AnreError> {
+        let mut rules = vec![];
+
+        for (kind, pattern, is_anre) in &self.rules {
+            let mut object_file = if *is_anre {
+                compile_from_anre(pattern)?
+            } else {
+                compile_from_regex(pattern)?
+            };
+            optimizer::optimize(&mut object_file);
+
+            rules.push(Rule {
+                kind: kind.clone(),
+                object_file,
+            });
+        }
+
+        Ok(Tokenizer {
+            rules,
+            error_kind: self.error_kind.clone(),
+            limits: self.limits,
+        })
+    }
+}
+
+impl<K: Clone> Tokenizer<K> {
+    /// Returns an iterator over the tokens of the text.
+    pub fn tokenize<'a, 'b>(&'a self, text: &'b str) -> Tokens<'a, 'b, K> {
+        let contexts = self
+            .rules
+            .iter()
+            .map(|rule| {
+                let number_of_capture_groups = rule.object_file.capture_group_names.len();
+                Context::new(text, number_of_capture_groups)
+            })
+            .collect();
+
+        Tokens {
+            tokenizer: self,
+            text,
+            contexts,
+            position: 0,
+            location: Location::new_position(0, 0, 0),
+        }
+    }
+
+    // Matches all the rules at the position, returns the index
+    // of the rule with the longest match and the end position of the match.
+    //
+    // Empty matches are ignored, otherwise the tokenizer would never move forward.
+    // The rules which exceed the limits are treated as not matching.
+    fn match_rules(&self, contexts: &mut [Context], position: usize) -> Option<(usize, usize)> {
+        let mut longest: Option<(usize, usize)> = None;
+
+        for (rule_index, (rule, context)) in self.rules.iter().zip(contexts.iter_mut()).enumerate()
+        {
+            let end = context.bytes.len();
+            context.budget = Budget::new(self.limits.step_limit, self.limits.time_limit);
+            let matched = start_process(context, &rule.object_file, position, end, Anchored::Start);
+            if context.budget.error.take().is_some() || !matched {
+                continue;
+            }
+
+            let end = context.match_ranges[0].end;
+            if end > position && longest.is_none_or(|(_, longest_end)| end > longest_end) {
+                longest = Some((rule_index, end));
+            }
+        }
+
+        longest
+    }
+}
+
+/// A token of the text.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Token<'b, K> {
+    pub kind: K,
+    pub start: usize, // the position of utf-8 byte stream (value included)
+    pub end: usize,   // the position of utf-8 byte stream (value excluded)
+    pub value: &'b str,
+
+    // The char index, line and column (0-based) of the start position,
+    // and the length of the token in chars.
+    pub location: Location,
+}
+
+pub struct Tokens<'a, 'b, K> {
+    tokenizer: &'a Tokenizer<K>,
+    text: &'b str,
+    contexts: Vec<Context<'b>>,
+    position: usize,
+
+    // The location of the current position.
+    location: Location,
+}
+
+impl<'b, K: Clone> Tokens<'_, 'b, K> {
+    // Moves the current position forward to the specified position,
+    // returns the location of the text range.
+    fn move_to(&mut self, end: usize) -> Location {
+        let start_location = self.location;
+
+        for c in self.text[self.position..end].chars() {
+            if c == '\n' {
+                self.location.line += 1;
+                self.location.column = 0;
+            } else {
+                self.location.column += 1;
+            }
+            self.location.index += 1;
+        }
+
+        self.position = end;
+        Location::from_position_pair(&start_location, &self.location)
+    }
+
+    fn new_token(&mut self, kind: K, end: usize) -> Token<'b, K> {
+        let start = self.position;
+        let location = self.move_to(end);
+
+        Token {
+            kind,
+            start,
+            end,
+            value: &self.text[start..end],
+            location,
+        }
+    }
+}
+
+impl<'b, K: Clone> Iterator for Tokens<'_, 'b, K> {
+    type Item = Token<'b, K>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let tokenizer = self.tokenizer;
+
+        while self.position < self.text.len() {
+            match tokenizer.match_rules(&mut self.contexts, self.position) {
+                Some((rule_index, end)) => match &tokenizer.rules[rule_index].kind {
+                    Some(kind) => return Some(self.new_token(kind.clone(), end)),
+                    None => {
+                        // Skip rule.
+                        self.move_to(end);
+                    }
+                },
+                None => {
+                    // The consecutive chars which match no rule
+                    // generate a single error token.
+                    let mut end = self.position;
+                    for (offset, c) in self.text[self.position..].char_indices() {
+                        let char_position = self.position + offset;
+                        if char_position > self.position
+                            && tokenizer
+                                .match_rules(&mut self.contexts, char_position)
+                                .is_some()
+                        {
+                            break;
+                        }
+                        end = char_position + c.len_utf8();
+                    }
+
+                    let error_kind = tokenizer.error_kind.clone();
+                    return Some(self.new_token(error_kind, end));
+                }
+            }
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use crate::location::Location;
+
+    use super::{Token, TokenizerBuilder};
+
+    #[derive(Debug, PartialEq, Clone, Copy)]
+    enum Kind {
+        Number,
+        Name,
+        Keyword,
+        String,
+        Symbol,
+        Error,
+    }
+
+    fn new_token(
+        kind: Kind,
+        start: usize,
+        value: &str,
+        (index, line, column, length): (usize, usize, usize, usize),
+    ) -> Token<'_, Kind> {
+        Token {
+            kind,
+            start,
+            end: start + value.len(),
+            value,
+            location: Location::new_range(index, line, column, length),
+        }
+    }
+
+    #[test]
+    fn test_tokenize() {
+        let tokenizer = TokenizerBuilder::new(Kind::Error)
+            .rule(Kind::Keyword, r#"let|fn"#)
+            .rule(Kind::Name, r#"[a-zA-Z_]\w*"#)
+            .rule(Kind::Number, r#"\d+(?:\.\d+)?"#)
+            .rule_anre(Kind::String, r#"'"', char_any+?, '"'"#)
+            .rule(Kind::Symbol, r#"==|[=+;(){}]"#)
+            .skip(r#"\s+"#)
+            .skip_anre(r#""//", char_any*"#)
+            .build()
+            .unwrap();
+
+        let text = "let letter = 3.14; // pi\nfn f() { \"文字\" == x }";
+        let tokens: Vec<Token<Kind>> = tokenizer.tokenize(text).collect();
+
+        assert_eq!(
+            tokens,
+            vec![
+                // the keyword wins the name, since it is added first
+                new_token(Kind::Keyword, 0, "let", (0, 0, 0, 3)),
+                // the longest match wins
+                new_token(Kind::Name, 4, "letter", (4, 0, 4, 6)),
+                new_token(Kind::Symbol, 11, "=", (11, 0, 11, 1)),
+                new_token(Kind::Number, 13, "3.14", (13, 0, 13, 4)),
+                new_token(Kind::Symbol, 17, ";", (17, 0, 17, 1)),
+                new_token(Kind::Keyword, 25, "fn", (25, 1, 0, 2)),
+                new_token(Kind::Name, 28, "f", (28, 1, 3, 1)),
+                new_token(Kind::Symbol, 29, "(", (29, 1, 4, 1)),
+                new_token(Kind::Symbol, 30, ")", (30, 1, 5, 1)),
+                new_token(Kind::Symbol, 32, "{", (32, 1, 7, 1)),
+                new_token(Kind::String, 34, "\"文字\"", (34, 1, 9, 4)),
+                new_token(Kind::Symbol, 43, "==", (39, 1, 14, 2)),
+                new_token(Kind::Name, 46, "x", (42, 1, 17, 1)),
+                new_token(Kind::Symbol, 48, "}", (44, 1, 19, 1)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_tokenize_error() {
+        let tokenizer = TokenizerBuilder::new(Kind::Error)
+            .rule(Kind::Number, r#"\d+"#)
+            .skip(r#" +"#)
+            .build()
+            .unwrap();
+
+        // the consecutive unmatched chars generate a single error token
+        let tokens: Vec<Token<Kind>> = tokenizer.tokenize("12 ab文3 #").collect();
+        assert_eq!(
+            tokens,
+            vec![
+                new_token(Kind::Number, 0, "12", (0, 0, 0, 2)),
+                new_token(Kind::Error, 3, "ab文", (3, 0, 3, 3)),
+                new_token(Kind::Number, 8, "3", (6, 0, 6, 1)),
+                new_token(Kind::Error, 10, "#", (8, 0, 8, 1)),
+            ]
+        );
+
+        // empty text
+        assert_eq!(tokenizer.tokenize("").count(), 0);
+
+        // the rules which match empty string never generate tokens
+        let tokenizer = TokenizerBuilder::new(Kind::Error)
+            .rule(Kind::Number, r#"\d*"#)
+            .build()
+            .unwrap();
+        let kinds: Vec<Kind> = tokenizer.tokenize("1a").map(|t| t.kind).collect();
+        assert_eq!(kinds, vec![Kind::Number, Kind::Error]);
+
+        // the rule which exceeds the step limit is treated as not matching
+        let tokenizer = TokenizerBuilder::new(Kind::Error)
+            .rule(Kind::Name, r#"(a+)+b"#)
+            .rule(Kind::Number, r#"\d+"#)
+            .step_limit(10_000)
+            .build()
+            .unwrap();
+        let kinds: Vec<Kind> = tokenizer
+            .tokenize("aaaaaaaaaaaaaaaaaaaaaaaaaaaaaa1")
+            .map(|t| t.kind)
+            .collect();
+        assert_eq!(kinds, vec![Kind::Error, Kind::Number]);
+
+        // err: invalid rule
+        assert!(TokenizerBuilder::new(Kind::Error)
+            .rule(Kind::Number, r#"(\d"#)
+            .build()
+            .is_err());
+    }
+}
@@ -6,6 +6,8 @@
 
 mod lexer;
 mod parser;
+mod printer;
 mod token;
 
 pub use parser::parse_from_str;
+pub use printer::print_program;
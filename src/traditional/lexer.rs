@@ -68,6 +68,17 @@
 // - (?!...)  Negative lookahead
 // - (?<=...) Positive lookbehind
 // - (?<!...) Negative lookbehind
+//
+// Inline flags:
+//
+// - i        Case-insensitive
+// - m        Multi-line, `^` and `$` also match at the beginning and end of lines
+// - s        Allow `.` to match `\r` and `\n`
+// - x        Extended mode, whitespace is ignored, and `#` starts a comment which ends at the end of line
+//
+// - (?im)    Enable flags for the rest of the enclosing group
+// - (?-im)   Disable flags for the rest of the enclosing group
+// - (?i-m:...) Enable or disable flags for the group only
 
 use crate::{
     charwithposition::{CharWithPosition, CharsWithPositionIter},
@@ -93,6 +104,9 @@ struct Lexer<'a> {
     upstream: &'a mut PeekableIter<'a, CharWithPosition>,
     last_position: Location, // last position consumed
     saved_positions: Vec<Location>,
+
+    // The state of extended mode (i.e. the flag `x`) of each level of the nested groups.
+    extended_modes: Vec<bool>,
 }
 
 impl<'a> Lexer<'a> {
@@ -101,6 +115,7 @@ impl<'a> Lexer<'a> {
             upstream,
             last_position: Location::new_position(/*0,*/ 0, 0, 0),
             saved_positions: vec![],
+            extended_modes: vec![false],
         }
     }
 
@@ -167,6 +182,21 @@ impl<'a> Lexer<'a> {
             ))),
         }
     }
+
+    fn is_extended_mode(&self) -> bool {
+        *self.extended_modes.last().unwrap()
+    }
+
+    // A group inherits the extended mode of its parent.
+    fn enter_group(&mut self) {
+        self.extended_modes.push(self.is_extended_mode());
+    }
+
+    fn exit_group(&mut self) {
+        if self.extended_modes.len() > 1 {
+            self.extended_modes.pop();
+        }
+    }
 }
 
 impl Lexer<'_> {
@@ -175,6 +205,18 @@ impl Lexer<'_> {
 
         while let Some(current_char) = self.peek_char(0) {
             match current_char {
+                ' ' | '\t' | '\r' | '\n' if self.is_extended_mode() => {
+                    // whitespace is ignored in extended mode
+                    self.next_char(); // consume whitespace
+                }
+                '#' if self.is_extended_mode() => {
+                    // comment in extended mode
+                    while let Some(previous_char) = self.next_char() {
+                        if previous_char == '\n' {
+                            break;
+                        }
+                    }
+                }
                 '[' => {
                     // charset start
                     let mut twrs = self.lex_charset()?;
@@ -194,8 +236,16 @@ impl Lexer<'_> {
                     let twr = self.lex_repetition()?;
                     token_with_ranges.push(twr);
                 }
+                '(' if self.peek_char_and_equals(1, '?')
+                    && matches!(self.peek_char(2), Some('i' | 'm' | 's' | 'x' | '-')) =>
+                {
+                    if let Some(twr) = self.lex_inline_flags()? {
+                        token_with_ranges.push(twr);
+                    }
+                }
                 '(' if self.peek_char_and_equals(1, '?') => {
                     if matches!(self.peek_char(2), Some(':' | '<' | '=' | '!')) {
+                        self.enter_group();
                         self.push_peek_position();
 
                         self.next_char(); // consume '('
@@ -284,6 +334,7 @@ impl Lexer<'_> {
                     }
                 }
                 '(' => {
+                    self.enter_group();
                     self.next_char(); // consume '('
                     token_with_ranges.push(TokenWithRange::from_position_and_length(
                         Token::GroupStart,
@@ -292,6 +343,7 @@ impl Lexer<'_> {
                     ));
                 }
                 ')' => {
+                    self.exit_group();
                     self.next_char(); // consume ')'
                     token_with_ranges.push(TokenWithRange::from_position_and_length(
                         Token::GroupEnd,
@@ -596,6 +648,12 @@ impl Lexer<'_> {
                         self.next_char();
                         Token::Char(c)
                     }
+                    // the chars which are ignored in extended mode
+                    ' ' | '#' => {
+                        let c = *previous_char;
+                        self.next_char();
+                        Token::Char(c)
+                    }
                     // preset charsets
                     'w' | 'W' | 'd' | 'D' | 's' | 'S' => {
                         let c = *previous_char;
@@ -657,6 +715,97 @@ impl Lexer<'_> {
         Ok(TokenWithRange::new(token, token_range))
     }
 
+    fn lex_inline_flags(&mut self) -> Result<Option<TokenWithRange>, AnreError> {
+        // (?imsx-imsx)?  //
+        // ^           ^__// to here
+        // |______________// current char, validated
+        //
+        // also `(?imsx-imsx:...)`
+
+        self.push_peek_position();
+
+        self.next_char(); // consume '('
+        self.next_char(); // consume '?'
+
+        let mut flags = vec![];
+        let mut extended_mode: Option<bool> = None;
+        let mut enabled = true;
+        let mut is_flag_expected = false;
+
+        let is_group = loop {
+            match self.next_char() {
+                Some(previous_char) => match previous_char {
+                    ':' | ')' => {
+                        if is_flag_expected {
+                            return Err(AnreError::MessageWithLocation(
+                                "Expect an inline flag after \"-\".".to_owned(),
+                                self.last_position,
+                            ));
+                        }
+                        break previous_char == ':';
+                    }
+                    '-' if enabled => {
+                        enabled = false;
+                        is_flag_expected = true;
+                    }
+                    'i' | 'm' | 's' => {
+                        flags.push((previous_char, enabled));
+                        is_flag_expected = false;
+                    }
+                    'x' => {
+                        extended_mode = Some(enabled);
+                        is_flag_expected = false;
+                    }
+                    _ => {
+                        return Err(AnreError::MessageWithLocation(
+                            format!("Unsupported inline flag '{}'.", previous_char),
+                            self.last_position,
+                        ));
+                    }
+                },
+                None => {
+                    return Err(AnreError::UnexpectedEndOfDocument(
+                        "Incomplete inline flags.".to_owned(),
+                    ));
+                }
+            }
+        };
+
+        let range = Location::from_position_pair_with_end_included(
+            &self.pop_saved_position(),
+            &self.last_position,
+        );
+
+        if is_group {
+            // the flags apply to the group only, e.g. `(?i:...)`
+            self.enter_group();
+            if let Some(e) = extended_mode {
+                *self.extended_modes.last_mut().unwrap() = e;
+            }
+
+            let token = if flags.is_empty() {
+                // e.g. `(?x:...)`
+                Token::NonCapturing
+            } else {
+                Token::InlineFlagsGroup(flags)
+            };
+
+            Ok(Some(TokenWithRange::new(token, range)))
+        } else {
+            // the flags apply to the rest of the enclosing group, e.g. `(?i)`
+            if let Some(e) = extended_mode {
+                *self.extended_modes.last_mut().unwrap() = e;
+            }
+
+            if flags.is_empty() {
+                // e.g. `(?x)`
+                Ok(None)
+            } else {
+                Ok(Some(TokenWithRange::new(Token::InlineFlags(flags), range)))
+            }
+        }
+    }
+
     fn lex_charset_escaping(&mut self) -> Result<TokenWithRange, AnreError> {
         // [\xxxx...]  //
         //  ^    ^_____// to here
@@ -1915,4 +2064,106 @@ mod tests {
             ]
         );
     }
+
+    #[test]
+    fn test_inline_flags() {
+        assert_eq!(
+            lex_from_str(r#"(?i)a(?m-s:b)"#).unwrap(),
+            vec![
+                TokenWithRange::from_position_and_length(
+                    Token::InlineFlags(vec![('i', true)]),
+                    &Location::new_position(/*0,*/ 0, 0, 0),
+                    4
+                ),
+                TokenWithRange::from_position_and_length(
+                    Token::Char('a'),
+                    &Location::new_position(/*0,*/ 4, 0, 4),
+                    1
+                ),
+                TokenWithRange::from_position_and_length(
+                    Token::InlineFlagsGroup(vec![('m', true), ('s', false)]),
+                    &Location::new_position(/*0,*/ 5, 0, 5),
+                    6
+                ),
+                TokenWithRange::from_position_and_length(
+                    Token::Char('b'),
+                    &Location::new_position(/*0,*/ 11, 0, 11),
+                    1
+                ),
+                TokenWithRange::from_position_and_length(
+                    Token::GroupEnd,
+                    &Location::new_position(/*0,*/ 12, 0, 12),
+                    1
+                ),
+            ]
+        );
+
+        // extended mode
+        assert_eq!(
+            lex_from_str_without_location("(?x) a b # comment\n [ ] \\  \\# ").unwrap(),
+            vec![
+                Token::Char('a'),
+                Token::Char('b'),
+                Token::CharSetStart,
+                Token::Char(' '),
+                Token::CharSetEnd,
+                Token::Char(' '),
+                Token::Char('#'),
+            ]
+        );
+
+        // extended mode is scoped to the group
+        assert_eq!(
+            lex_from_str_without_location("a (?x: b (c) ) d").unwrap(),
+            vec![
+                Token::Char('a'),
+                Token::Char(' '),
+                Token::NonCapturing,
+                Token::Char('b'),
+                Token::GroupStart,
+                Token::Char('c'),
+                Token::GroupEnd,
+                Token::GroupEnd,
+                Token::Char(' '),
+                Token::Char('d'),
+            ]
+        );
+
+        assert_eq!(
+            lex_from_str_without_location("(?ix) a (?-x) b").unwrap(),
+            vec![
+                Token::InlineFlags(vec![('i', true)]),
+                Token::Char('a'),
+                Token::Char(' '),
+                Token::Char('b'),
+            ]
+        );
+
+        // err: unsupported flag
+        assert!(matches!(
+            lex_from_str(r#"(?iq)"#),
+            Err(AnreError::MessageWithLocation(
+                _,
+                Location {
+                    // unit: 0,
+                    index: 3,
+                    line: 0,
+                    column: 3,
+                    length: 0
+                }
+            ))
+        ));
+
+        // err: missing flag after '-'
+        assert!(matches!(
+            lex_from_str(r#"(?i-)"#),
+            Err(AnreError::MessageWithLocation(_, _))
+        ));
+
+        // err: incomplete flags
+        assert!(matches!(
+            lex_from_str(r#"(?i"#),
+            Err(AnreError::UnexpectedEndOfDocument(_))
+        ));
+    }
 }
@@ -25,6 +25,13 @@ use super::{
 pub struct Parser<'a> {
     upstream: &'a mut PeekableIter<'a, TokenWithRange>,
     last_range: Location,
+
+    // The inline flags (e.g. `(?i)`) of each level of the nested groups.
+    //
+    // An inline flag applies to the rest of the enclosing group,
+    // including the subsequent alternatives of the "logic or", e.g.
+    // the flag `i` in `a(?i)b|c` applies to both 'b' and 'c'.
+    inline_flags_stack: Vec<Vec<(char, bool)>>,
 }
 
 impl<'a> Parser<'a> {
@@ -32,6 +39,7 @@ impl<'a> Parser<'a> {
         Self {
             upstream,
             last_range: Location::new_range(/*0,*/ 0, 0, 0, 0),
+            inline_flags_stack: vec![vec![]],
         }
     }
 
@@ -182,13 +190,49 @@ impl Parser<'_> {
         // ^
         // | current, None or Some(...)
 
+        // the inline flags which are present in the previous alternatives
+        // of the current group also apply to this alternative.
+        let inline_flags = self.inline_flags_stack.last().unwrap().clone();
+        let expression = self.continue_parse_consecutive_expression()?;
+        Ok(wrap_with_inline_flags(expression, &inline_flags))
+    }
+
+    fn continue_parse_consecutive_expression(&mut self) -> Result<Expression, AnreError> {
+        // token ...
+        // -----
+        // ^
+        // | current, None or Some(...)
+
         let mut expressions = vec![];
+        let mut has_inline_flags = false;
+
         while let Some(token) = self.peek_token(0) {
             match token {
                 // terminator
                 Token::GroupEnd | Token::LogicOr => {
                     break;
                 }
+                Token::InlineFlags(flags_ref) => {
+                    // the flags apply to the rest of the consecutive expression,
+                    // e.g. `a(?i)bc` == `a(?i:bc)`
+                    let flags = flags_ref.to_owned();
+                    self.next_token(); // consume "(?...)"
+
+                    self.inline_flags_stack
+                        .last_mut()
+                        .unwrap()
+                        .extend_from_slice(&flags);
+                    has_inline_flags = true;
+
+                    if !matches!(
+                        self.peek_token(0),
+                        None | Some(Token::GroupEnd | Token::LogicOr)
+                    ) {
+                        let expression = self.continue_parse_consecutive_expression()?;
+                        expressions.push(wrap_with_inline_flags(expression, &flags));
+                    }
+                    break;
+                }
                 _ => {
                     let expression = self.parse_notations()?;
                     expressions.push(expression);
@@ -197,6 +241,11 @@ impl Parser<'_> {
         }
 
         if expressions.is_empty() {
+            if has_inline_flags {
+                // e.g. `(?i)` or `(?i)|a`
+                return Ok(Expression::Group(vec![]));
+            }
+
             return Err(AnreError::MessageWithLocation(
                 "Encountered a blank expression.".to_owned(),
                 self.last_range,
@@ -356,7 +405,7 @@ impl Parser<'_> {
                     };
 
                     self.next_token(); // consume "(?=" or "(?!"
-                    let arg0 = self.parse_expression()?;
+                    let arg0 = self.parse_group_content()?;
                     self.next_token(); // consume ")"
 
                     let function_call = FunctionCall {
@@ -414,7 +463,7 @@ impl Parser<'_> {
                 };
 
                 self.next_token(); // consume "(?<=" or "(?<!"
-                let arg0 = self.parse_expression()?;
+                let arg0 = self.parse_group_content()?;
                 self.next_token(); // consume ")"
 
                 let expression = self.parse_expression()?;
@@ -425,9 +474,10 @@ impl Parser<'_> {
                 };
                 Expression::FunctionCall(Box::new(function_call))
             }
-            Token::GroupStart | Token::NonCapturing | Token::NamedCapture(_) => {
-                self.parse_group()?
-            }
+            Token::GroupStart
+            | Token::NonCapturing
+            | Token::NamedCapture(_)
+            | Token::InlineFlagsGroup(_) => self.parse_group()?,
             Token::BackReferenceNumber(index_ref) => {
                 let index = *index_ref;
                 self.next_token(); // consume '\num'
@@ -456,10 +506,11 @@ impl Parser<'_> {
         // also:
         // - "(?:" {expression} ")"
         // - "(?<...>" {expression} ")"
+        // - "(?imsx-imsx:" {expression} ")"
 
-        // consume "(", "(?:" or "(?<...>"
+        // consume "(", "(?:", "(?<...>" or "(?...:"
         let head_token = self.next_token().unwrap();
-        let expression = self.parse_expression()?;
+        let expression = self.parse_group_content()?;
 
         // consume ")"
         self.consume_token(&Token::GroupEnd, "right parenthese \")\"")?;
@@ -488,12 +539,24 @@ impl Parser<'_> {
                 };
                 Expression::FunctionCall(Box::new(function_call))
             }
+            Token::InlineFlagsGroup(flags) => {
+                // regex non-capturing group with flags == ANRE modifier functions
+                wrap_with_inline_flags(expression, &flags)
+            }
             _ => unreachable!(),
         };
 
         Ok(group_expression)
     }
 
+    fn parse_group_content(&mut self) -> Result<Expression, AnreError> {
+        // the inline flags present in a group do not apply outside of the group.
+        self.inline_flags_stack.push(vec![]);
+        let expression = self.parse_expression();
+        self.inline_flags_stack.pop();
+        expression
+    }
+
     fn parse_literal(&mut self) -> Result<Literal, AnreError> {
         // token ...
         // -----
@@ -596,6 +659,31 @@ impl Parser<'_> {
     }
 }
 
+// Wrap the expression with modifier functions, e.g.
+// `(?i-s:abc)` -> `ignore_case(not_dot_all("abc"))`
+fn wrap_with_inline_flags(expression: Expression, flags: &[(char, bool)]) -> Expression {
+    flags
+        .iter()
+        .rev()
+        .fold(expression, |expression, (flag, enabled)| {
+            let name = match (flag, enabled) {
+                ('i', true) => FunctionName::IgnoreCase,
+                ('m', true) => FunctionName::MultiLine,
+                ('s', true) => FunctionName::DotAll,
+                ('i', false) => FunctionName::NotIgnoreCase,
+                ('m', false) => FunctionName::NotMultiLine,
+                ('s', false) => FunctionName::NotDotAll,
+                _ => unreachable!(),
+            };
+
+            let function_call = FunctionCall {
+                name,
+                args: vec![expression],
+            };
+            Expression::FunctionCall(Box::new(function_call))
+        })
+}
+
 fn preset_charset_name_from_char(name_char: char) -> PresetCharSetName {
     match name_char {
         'w' => PresetCharSetName::CharWord,
@@ -876,6 +964,43 @@ tag, ^1"#
         );
     }
 
+    #[test]
+    fn test_parse_expression_inline_flags() {
+        assert_eq!(
+            parse_from_str(r#"(?i)abc"#,).unwrap().to_string(),
+            r#"ignore_case("abc")"#
+        );
+
+        assert_eq!(
+            parse_from_str(r#"a(?i)bc"#,).unwrap().to_string(),
+            r#"'a'
+ignore_case("bc")"#
+        );
+
+        assert_eq!(
+            parse_from_str(r#"a(?im-s:b)c"#,).unwrap().to_string(),
+            r#"'a'
+ignore_case(multiline(not_dot_all('b')))
+'c'"#
+        );
+
+        // the flags apply to the subsequent alternatives
+        assert_eq!(
+            parse_from_str(r#"a(?i)b|c"#,).unwrap().to_string(),
+            r#"('a', ignore_case('b')) || ignore_case('c')"#
+        );
+
+        // the flags do not apply outside of the group
+        assert_eq!(
+            parse_from_str(r#"(a(?i)b)c"#,).unwrap().to_string(),
+            r#"index(('a', ignore_case('b')))
+'c'"#
+        );
+
+        // empty
+        assert_eq!(parse_from_str(r#"(?i)"#,).unwrap().to_string(), "");
+    }
+
     #[test]
     fn test_parse_examples() {
         assert_eq!(
@@ -920,7 +1045,7 @@ end"#
                 .to_string(),
             r#"'<'
 name(one_or_more(char_word), "tag_name")
-zero_or_more(index((char_space, one_or_more(char_word), "="", one_or_more(char_word), '"')))
+zero_or_more(index((char_space, one_or_more(char_word), "=\"", one_or_more(char_word), '"')))
 '>'
 one_or_more_lazy(char_any)
 "</", tag_name, '>'"#
@@ -0,0 +1,483 @@
+// Copyright (c) 2025 Hemashushu <hippospark@gmail.com>, All rights reserved.
+//
+// This Source Code Form is subject to the terms of
+// the Mozilla Public License version 2.0 and additional exceptions.
+// For more details, see the LICENSE, LICENSE.additional, and CONTRIBUTING files.
+
+use crate::{
+    ast::{
+        AnchorAssertionName, BackReference, BoundaryAssertionName, CharSet, CharSetElement,
+        Expression, FunctionCall, FunctionName, Literal, PresetCharSetName, Program,
+        SpecialCharName,
+    },
+    AnreError,
+};
+
+// Where the expression is printed, it determines whether the expression
+// needs to be enclosed by a non-capturing group `(?:...)`.
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum Context {
+    // The whole content of a group (or of the pattern).
+    Expression,
+
+    // The left side of the "logic or", the "logic or" needs to be grouped.
+    Alternative,
+
+    // An item of a sequence, the sequences also need to be grouped.
+    // `followed` indicates whether there are other items after it.
+    Item { followed: bool },
+
+    // The expression before a lookahead, it can be a primary expression
+    // or an expression with notations, e.g. `a+(?=b)`.
+    LookAheadOperand,
+
+    // The expression before a quantifier, it can be a primary expression only.
+    QuantifierOperand,
+}
+
+/// Prints the AST `Program` as a traditional regular expression.
+///
+/// The printed expression matches the same text as the program, and the AST
+/// generated by the traditional parser is printed back into the same AST.
+///
+/// The parser merges the adjacent chars into a string, so the adjacent
+/// chars and strings of ANRE are kept apart by enclosing the latter ones
+/// in non-capturing groups, e.g. `('a', 'b')` is printed as `a(?:b)`.
+/// The nested char sets of ANRE are flattened.
+///
+/// Returns an error if the program contains an expression which can not be
+/// written in the traditional regular expression, e.g. an empty group,
+/// or a negative preset charset in a charset.
+pub fn print_program(program: &Program) -> Result<String, AnreError> {
+    print_sequence(&program.expressions, false)
+}
+
+fn print_sequence(expressions: &[Expression], followed: bool) -> Result<String, AnreError> {
+    if let [expression] = expressions {
+        let context = if followed {
+            Context::Alternative
+        } else {
+            Context::Expression
+        };
+        return print_expression(expression, context);
+    }
+
+    let mut ss = vec![];
+
+    for (index, expression) in expressions.iter().enumerate() {
+        let context = Context::Item {
+            followed: followed || index + 1 < expressions.len(),
+        };
+        let mut s = print_expression(expression, context)?;
+
+        if index > 0 {
+            let previous_expression = &expressions[index - 1];
+
+            // The adjacent chars are merged into a string by the parser, e.g.
+            // `('a', "bc")` is printed as `a(?:bc)` instead of `abc`.
+            let is_adjacent_literal =
+                is_char_or_string(previous_expression) && is_char_or_string(expression);
+
+            // The digits after a back reference are part of the index, e.g.
+            // `(^1, '0')` is printed as `\1(?:0)` instead of `\10`.
+            let is_after_back_reference_index = matches!(
+                previous_expression,
+                Expression::BackReference(BackReference::Index(_))
+            ) && s.starts_with(|c: char| c.is_ascii_digit());
+
+            if is_adjacent_literal || is_after_back_reference_index {
+                s = group(&s);
+            }
+        }
+
+        ss.push(s);
+    }
+
+    Ok(ss.join(""))
+}
+
+fn print_expression(expression: &Expression, context: Context) -> Result<String, AnreError> {
+    let s = match expression {
+        Expression::Literal(literal) => print_literal(literal, context)?,
+        Expression::BackReference(back_reference) => match back_reference {
+            BackReference::Index(index) => format!("\\{}", index),
+            BackReference::Name(name) => format!("\\k<{}>", check_identifier(name)?),
+        },
+        Expression::AnchorAssertion(name) => match name {
+            AnchorAssertionName::Start => "^".to_owned(),
+            AnchorAssertionName::End => "$".to_owned(),
+        },
+        Expression::BoundaryAssertion(name) => match name {
+            BoundaryAssertionName::IsBound => "\\b".to_owned(),
+            BoundaryAssertionName::IsNotBound => "\\B".to_owned(),
+        },
+        Expression::Group(expressions) => {
+            if expressions.is_empty() {
+                return Err(AnreError::SyntaxIncorrect(
+                    "The empty group can not be written in the traditional regular expression."
+                        .to_owned(),
+                ));
+            }
+
+            match context {
+                Context::Expression => print_sequence(expressions, false)?,
+                Context::Alternative => print_sequence(expressions, true)?,
+                _ => group(&print_sequence(expressions, false)?),
+            }
+        }
+        Expression::FunctionCall(function_call) => print_function_call(function_call, context)?,
+        Expression::Or(left, right) => {
+            // The "logic or" has the lowest precedence, and it is right-associative.
+            let s = format!(
+                "{}|{}",
+                print_expression(left, Context::Alternative)?,
+                print_expression(right, Context::Expression)?
+            );
+
+            if context == Context::Expression {
+                s
+            } else {
+                group(&s)
+            }
+        }
+    };
+
+    Ok(s)
+}
+
+fn print_literal(literal: &Literal, context: Context) -> Result<String, AnreError> {
+    let s = match literal {
+        Literal::Number(_) => {
+            return Err(AnreError::SyntaxIncorrect(
+                "A number can only be the argument of the repetition functions.".to_owned(),
+            ));
+        }
+        Literal::Char(c) => escape_char(*c),
+        Literal::String(s) => {
+            if s.is_empty() {
+                return Err(AnreError::SyntaxIncorrect(
+                    "The empty string can not be written in the traditional regular expression."
+                        .to_owned(),
+                ));
+            }
+
+            let escaped: String = s.chars().map(escape_char).collect();
+            if s.chars().count() > 1
+                && matches!(
+                    context,
+                    Context::LookAheadOperand | Context::QuantifierOperand
+                )
+            {
+                group(&escaped)
+            } else {
+                escaped
+            }
+        }
+        Literal::Special(SpecialCharName::CharAny) => ".".to_owned(),
+        Literal::CharSet(charset) => print_charset(charset)?,
+        Literal::PresetCharSet(name) => match name {
+            PresetCharSetName::CharWord => "\\w".to_owned(),
+            PresetCharSetName::CharNotWord => "\\W".to_owned(),
+            PresetCharSetName::CharDigit => "\\d".to_owned(),
+            PresetCharSetName::CharNotDigit => "\\D".to_owned(),
+            PresetCharSetName::CharSpace => "\\s".to_owned(),
+            PresetCharSetName::CharNotSpace => "\\S".to_owned(),
+            PresetCharSetName::CharHex => "[0-9a-fA-F]".to_owned(),
+        },
+    };
+
+    Ok(s)
+}
+
+fn print_function_call(
+    function_call: &FunctionCall,
+    context: Context,
+) -> Result<String, AnreError> {
+    let name = function_call.name;
+    let args = &function_call.args;
+
+    let s = match name {
+        FunctionName::Optional
+        | FunctionName::OptionalLazy
+        | FunctionName::OneOrMore
+        | FunctionName::OneOrMoreLazy
+        | FunctionName::ZeroOrMore
+        | FunctionName::ZeroOrMoreLazy
+        | FunctionName::Repeat
+        | FunctionName::RepeatRange
+        | FunctionName::RepeatRangeLazy
+        | FunctionName::AtLeast
+        | FunctionName::AtLeastLazy => {
+            let notation = match name {
+                FunctionName::Optional => "?".to_owned(),
+                FunctionName::OptionalLazy => "??".to_owned(),
+                FunctionName::OneOrMore => "+".to_owned(),
+                FunctionName::OneOrMoreLazy => "+?".to_owned(),
+                FunctionName::ZeroOrMore => "*".to_owned(),
+                FunctionName::ZeroOrMoreLazy => "*?".to_owned(),
+                FunctionName::Repeat => format!("{{{}}}", args[1].get_number()),
+                FunctionName::RepeatRange => {
+                    format!("{{{},{}}}", args[1].get_number(), args[2].get_number())
+                }
+                FunctionName::RepeatRangeLazy => {
+                    format!("{{{},{}}}?", args[1].get_number(), args[2].get_number())
+                }
+                FunctionName::AtLeast => format!("{{{},}}", args[1].get_number()),
+                _ => format!("{{{},}}?", args[1].get_number()),
+            };
+
+            let s = format!(
+                "{}{}",
+                print_expression(&args[0], Context::QuantifierOperand)?,
+                notation
+            );
+
+            // e.g. `(a+)?` is printed as `(?:a+)?` instead of `a+?`.
+            if context == Context::QuantifierOperand {
+                group(&s)
+            } else {
+                s
+            }
+        }
+        FunctionName::IsBefore | FunctionName::IsNotBefore => {
+            let head = if name == FunctionName::IsBefore {
+                "(?="
+            } else {
+                "(?!"
+            };
+
+            let s = format!(
+                "{}{}{})",
+                print_expression(&args[0], Context::LookAheadOperand)?,
+                head,
+                print_expression(&args[1], Context::Expression)?
+            );
+
+            if context == Context::QuantifierOperand {
+                group(&s)
+            } else {
+                s
+            }
+        }
+        FunctionName::IsAfter | FunctionName::IsNotAfter => {
+            let head = if name == FunctionName::IsAfter {
+                "(?<="
+            } else {
+                "(?<!"
+            };
+
+            // The expression after the lookbehind is the rest of the enclosing group,
+            // so the lookbehind needs to be grouped if there is anything after it.
+            let s = format!(
+                "{}{}){}",
+                head,
+                print_expression(&args[1], Context::Expression)?,
+                print_expression(&args[0], Context::Expression)?
+            );
+
+            if matches!(
+                context,
+                Context::Expression | Context::Item { followed: false }
+            ) {
+                s
+            } else {
+                group(&s)
+            }
+        }
+        FunctionName::Name => {
+            let name = match &args[1] {
+                Expression::Literal(Literal::String(s)) => s,
+                Expression::BackReference(BackReference::Name(s)) => s,
+                _ => unreachable!(),
+            };
+
+            format!(
+                "(?<{}>{})",
+                check_identifier(name)?,
+                print_expression(&args[0], Context::Expression)?
+            )
+        }
+        FunctionName::Index => format!("({})", print_expression(&args[0], Context::Expression)?),
+        FunctionName::IgnoreCase
+        | FunctionName::MultiLine
+        | FunctionName::DotAll
+        | FunctionName::NotIgnoreCase
+        | FunctionName::NotMultiLine
+        | FunctionName::NotDotAll => {
+            let flags = match name {
+                FunctionName::IgnoreCase => "i",
+                FunctionName::MultiLine => "m",
+                FunctionName::DotAll => "s",
+                FunctionName::NotIgnoreCase => "-i",
+                FunctionName::NotMultiLine => "-m",
+                _ => "-s",
+            };
+
+            format!(
+                "(?{}:{})",
+                flags,
+                print_expression(&args[0], Context::Expression)?
+            )
+        }
+    };
+
+    Ok(s)
+}
+
+// ANRE accepts any string as the name of a capture group, but the traditional
+// regular expression accepts only the identifiers, the chars are the same
+// as `Lexer::lex_identifier`.
+fn check_identifier(name: &str) -> Result<&str, AnreError> {
+    let is_identifier = !name.is_empty()
+        && name.chars().all(|c| {
+            matches!(
+                c,
+                '0'..='9' | 'a'..='z' | 'A'..='Z' | '_' | '\u{a0}'..='\u{d7ff}' | '\u{e000}'..='\u{10ffff}'
+            )
+        });
+
+    if is_identifier {
+        Ok(name)
+    } else {
+        Err(AnreError::SyntaxIncorrect(format!(
+            "The name \"{}\" is not a valid identifier of the traditional regular expression.",
+            name
+        )))
+    }
+}
+
+fn print_charset(charset: &CharSet) -> Result<String, AnreError> {
+    let mut items = vec![];
+    append_charset_items(charset, &mut items)?;
+
+    let head = if charset.negative { "[^" } else { "[" };
+    Ok(format!("{}{}]", head, print_charset_items(&items).join("")))
+}
+
+fn print_charset_item(item: &CharSetItem) -> String {
+    match item {
+        CharSetItem::Char('-') => "-".to_owned(),
+        CharSetItem::Char(c) => escape_charset_char(*c),
+        CharSetItem::Range(start, end_included) => format!(
+            "{}-{}",
+            escape_charset_char(*start),
+            escape_charset_char(*end_included)
+        ),
+        CharSetItem::Preset(s) => (*s).to_owned(),
+    }
+}
+
+// The char '-' can not be escaped in the charset, it is a literal char only
+// when it is the first or the last item, and the range which ends with '-'
+// (e.g. `!--`) is valid only when it is the last item, otherwise they are
+// recognized as the range operator.
+//
+// The items are printed in order if possible, otherwise the char '-' is
+// moved to the start and the ranges which end with '-' are moved to the end.
+fn print_charset_items(items: &[CharSetItem]) -> Vec<String> {
+    let last_index = items.len().saturating_sub(1);
+    let in_order = items.iter().enumerate().all(|(index, item)| match item {
+        CharSetItem::Char('-') => index == 0 || index == last_index,
+        CharSetItem::Range(_, '-') => index == last_index,
+        _ => true,
+    });
+
+    if in_order {
+        return items.iter().map(print_charset_item).collect();
+    }
+
+    let mut ss = vec![];
+    if items.contains(&CharSetItem::Char('-')) {
+        ss.push("-".to_owned());
+    }
+
+    for item in items {
+        if !matches!(item, CharSetItem::Char('-') | CharSetItem::Range(_, '-')) {
+            ss.push(print_charset_item(item));
+        }
+    }
+
+    for item in items {
+        if matches!(item, CharSetItem::Range(_, '-')) {
+            ss.push(print_charset_item(item));
+        }
+    }
+
+    ss
+}
+
+#[derive(Debug, PartialEq)]
+enum CharSetItem {
+    Char(char),
+    Range(char, char),
+    Preset(&'static str),
+}
+
+// The nested char sets are positive, so their elements are merged
+// into the outer char set.
+fn append_charset_items(charset: &CharSet, items: &mut Vec<CharSetItem>) -> Result<(), AnreError> {
+    for element in &charset.elements {
+        match element {
+            CharSetElement::Char(c) => items.push(CharSetItem::Char(*c)),
+            CharSetElement::CharRange(range) => {
+                items.push(CharSetItem::Range(range.start, range.end_included))
+            }
+            CharSetElement::PresetCharSet(name) => match name {
+                PresetCharSetName::CharWord => items.push(CharSetItem::Preset("\\w")),
+                PresetCharSetName::CharDigit => items.push(CharSetItem::Preset("\\d")),
+                PresetCharSetName::CharSpace => items.push(CharSetItem::Preset("\\s")),
+                PresetCharSetName::CharHex => {
+                    items.push(CharSetItem::Range('0', '9'));
+                    items.push(CharSetItem::Range('a', 'f'));
+                    items.push(CharSetItem::Range('A', 'F'));
+                }
+                _ => {
+                    return Err(AnreError::SyntaxIncorrect(format!(
+                        "The preset charset \"{}\" in charset can not be written in the traditional regular expression.",
+                        name
+                    )));
+                }
+            },
+            CharSetElement::CharSet(charset) => append_charset_items(charset, items)?,
+        }
+    }
+
+    Ok(())
+}
+
+fn is_char_or_string(expression: &Expression) -> bool {
+    matches!(
+        expression,
+        Expression::Literal(Literal::Char(_) | Literal::String(_))
+    )
+}
+
+fn group(s: &str) -> String {
+    format!("(?:{})", s)
+}
+
+fn escape_char(c: char) -> String {
+    match c {
+        '\\' | '(' | ')' | '{' | '}' | '[' | ']' | '+' | '*' | '?' | '.' | '|' | '^' | '$' => {
+            format!("\\{}", c)
+        }
+        _ => escape_control_char(c),
+    }
+}
+
+fn escape_charset_char(c: char) -> String {
+    match c {
+        '\\' | '[' | ']' | '^' => format!("\\{}", c),
+        _ => escape_control_char(c),
+    }
+}
+
+fn escape_control_char(c: char) -> String {
+    match c {
+        '\t' => "\\t".to_owned(),
+        '\r' => "\\r".to_owned(),
+        '\n' => "\\n".to_owned(),
+        _ if c.is_control() => format!("\\u{{{:x}}}", c as u32),
+        _ => c.to_string(),
+    }
+}
@@ -38,6 +38,9 @@ pub enum Token {
     LookBehindNegative,   // (?<!...)
     GroupEnd,             // )
 
+    InlineFlags(Vec<(char, /* enabled */ bool)>), // (?im-s)
+    InlineFlagsGroup(Vec<(char, /* enabled */ bool)>), // (?im-s:...)
+
     BackReferenceNumber(usize),      // \number
     BackReferenceIdentifier(String), // \k<name>
 }
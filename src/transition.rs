@@ -4,7 +4,7 @@
 // the Mozilla Public License version 2.0 and additional exceptions.
 // For more details, see the LICENSE, LICENSE.additional, and CONTRIBUTING files.
 
-use std::fmt::Display;
+use std::{cmp::Ordering, fmt::Display};
 
 use crate::{
     ast::{AnchorAssertionName, BoundaryAssertionName},
@@ -26,6 +26,7 @@ pub enum Transition {
     SpecialChar(SpecialCharTransition),
     String(StringTransition),
     CharSet(CharSetTransition),
+    Trie(TrieTransition),
     BackReference(BackReferenceTransition),
     AnchorAssertion(AnchorAssertionTransition),
     BoundaryAssertion(BoundaryAssertionTransition),
@@ -55,17 +56,22 @@ pub struct JumpTransition;
 pub struct CharTransition {
     pub codepoint: u32,     // Unicode codepoint of the character
     pub byte_length: usize, // Length of the character in bytes
+    pub ignore_case: bool,  // Whether the case of letters is ignored
 }
 
 // Represents a transition for special characters (e.g., any character).
 #[derive(Debug)]
-pub struct SpecialCharTransition;
+pub struct SpecialCharTransition {
+    pub dot_matches_new_line: bool, // Whether `\n` and `\r` are also matched
+    pub matches_any_byte: bool,     // Whether a single byte is matched instead of a char
+}
 
 /// Represents a transition that matches a specific string.
 #[derive(Debug)]
 pub struct StringTransition {
     pub codepoints: Vec<u32>, // Unicode codepoints of the string
     pub byte_length: usize,   // Total byte length of the string
+    pub ignore_case: bool,    // Whether the case of letters is ignored
 }
 
 /// Represents a transition that matches a set of characters or ranges.
@@ -73,6 +79,12 @@ pub struct StringTransition {
 pub struct CharSetTransition {
     pub items: Vec<CharSetItem>, // List of characters or ranges
     pub negative: bool,          // Whether the set is negated
+    pub ignore_case: bool,       // Whether the case of letters is ignored
+
+    // The bitmap of the ASCII chars of the set, it is set by the optimizer
+    // after the items are sorted and merged, so that the ASCII chars are
+    // looked up directly and other chars are found by binary search.
+    pub ascii_bitmap: Option<[u64; 2]>,
 }
 
 /// Represents an item in a character set, either a single character or a range.
@@ -89,6 +101,26 @@ pub struct CharRange {
     pub end_included: u32, // End of the range (inclusive)
 }
 
+/// Represents a transition that matches one of a set of strings,
+/// the strings are stored in a trie (prefix tree).
+///
+/// None of the strings is a prefix of another string, so at most
+/// one string can be matched at a position, and the order
+/// of the strings (i.e. the priority of the alternatives) does not matter.
+#[derive(Debug)]
+pub struct TrieTransition {
+    pub words: Vec<String>,   // The strings, they are used for displaying only
+    pub nodes: Vec<TrieNode>, // Nodes of the trie, the first node is the root
+    pub ignore_case: bool,    // Whether the case of letters is ignored
+}
+
+/// Represents a node of the trie.
+#[derive(Debug, Default)]
+pub struct TrieNode {
+    pub children: Vec<(u32, usize)>, // Codepoints and the indices of the child nodes, sorted by codepoint
+    pub is_terminal: bool,           // Whether a string ends at this node
+}
+
 /// Represents a transition that matches a backreference to a capture group.
 #[derive(Debug)]
 pub struct BackReferenceTransition {
@@ -99,6 +131,8 @@ pub struct BackReferenceTransition {
 #[derive(Debug)]
 pub struct AnchorAssertionTransition {
     pub name: AnchorAssertionName, // Name of the anchor assertion
+    pub multi_line: bool,          // Whether it also matches at the line boundaries
+    pub crlf: bool,                // Whether "\r\n" is treated as a line terminator
 }
 
 /// Represents a transition that asserts a boundary (e.g., word boundary).
@@ -164,6 +198,25 @@ impl CharTransition {
         CharTransition {
             codepoint: (c as u32),
             byte_length,
+            ignore_case: false,
+        }
+    }
+
+    pub fn new_ignore_case(c: char) -> Self {
+        let byte_length = c.len_utf8();
+        CharTransition {
+            codepoint: (c as u32),
+            byte_length,
+            ignore_case: true,
+        }
+    }
+}
+
+impl SpecialCharTransition {
+    pub fn new(dot_matches_new_line: bool, matches_any_byte: bool) -> Self {
+        SpecialCharTransition {
+            dot_matches_new_line,
+            matches_any_byte,
         }
     }
 }
@@ -175,10 +228,88 @@ impl StringTransition {
         StringTransition {
             codepoints: chars,
             byte_length,
+            ignore_case: false,
+        }
+    }
+
+    pub fn new_ignore_case(s: &str) -> Self {
+        let chars: Vec<u32> = s.chars().map(|item| item as u32).collect();
+        let byte_length = s.len();
+        StringTransition {
+            codepoints: chars,
+            byte_length,
+            ignore_case: true,
         }
     }
 }
 
+impl TrieTransition {
+    pub fn new(words: Vec<String>, ignore_case: bool) -> Self {
+        let mut nodes = vec![TrieNode::default()];
+
+        for word in &words {
+            let mut node_index = 0;
+            for c in word.chars() {
+                let codepoint = if ignore_case {
+                    fold_case(c as u32)
+                } else {
+                    c as u32
+                };
+
+                let children = &nodes[node_index].children;
+                node_index = match children.binary_search_by_key(&codepoint, |(cp, _)| *cp) {
+                    Ok(idx) => children[idx].1,
+                    Err(idx) => {
+                        let child_index = nodes.len();
+                        nodes[node_index]
+                            .children
+                            .insert(idx, (codepoint, child_index));
+                        nodes.push(TrieNode::default());
+                        child_index
+                    }
+                };
+            }
+            nodes[node_index].is_terminal = true;
+        }
+
+        TrieTransition {
+            words,
+            nodes,
+            ignore_case,
+        }
+    }
+
+    /// Checks whether the strings are non-empty and none of them is
+    /// a prefix of another string (when ignoring case, the case-folded strings).
+    pub fn is_prefix_free(&self) -> bool {
+        !self.nodes[0].is_terminal
+            && self
+                .nodes
+                .iter()
+                .all(|node| !node.is_terminal || node.children.is_empty())
+    }
+
+    /// Returns the index of the next node after reading the specified char,
+    /// or `None` if no string continues with the char.
+    pub fn step(&self, node_index: usize, codepoint: u32) -> Option<usize> {
+        let codepoint = if self.ignore_case {
+            fold_case(codepoint)
+        } else {
+            codepoint
+        };
+
+        let children = &self.nodes[node_index].children;
+        children
+            .binary_search_by_key(&codepoint, |(cp, _)| *cp)
+            .ok()
+            .map(|idx| children[idx].1)
+    }
+
+    pub fn is_terminal(&self, node_index: usize) -> bool {
+        self.nodes[node_index].is_terminal
+    }
+}
+
 impl CharSetItem {
     pub fn new_char(character: char) -> Self {
         CharSetItem::Char(character as u32)
@@ -195,7 +326,41 @@ impl CharSetItem {
 
 impl CharSetTransition {
     pub fn new(items: Vec<CharSetItem>, negative: bool) -> Self {
-        CharSetTransition { items, negative }
+        CharSetTransition {
+            items,
+            negative,
+            ignore_case: false,
+            ascii_bitmap: None,
+        }
+    }
+
+    pub fn new_ignore_case(items: Vec<CharSetItem>, negative: bool) -> Self {
+        CharSetTransition {
+            items,
+            negative,
+            ignore_case: true,
+            ascii_bitmap: None,
+        }
+    }
+
+    // Checks whether the char is in the sorted and merged items.
+    pub fn contains_sorted(&self, codepoint: u32) -> bool {
+        self.items
+            .binary_search_by(|item| {
+                let (start, end_included) = match item {
+                    CharSetItem::Char(c) => (*c, *c),
+                    CharSetItem::Range(r) => (r.start, r.end_included),
+                };
+
+                if end_included < codepoint {
+                    Ordering::Less
+                } else if start > codepoint {
+                    Ordering::Greater
+                } else {
+                    Ordering::Equal
+                }
+            })
+            .is_ok()
     }
 
     pub fn new_preset_word() -> Self {
@@ -290,7 +455,19 @@ impl BackReferenceTransition {
 
 impl AnchorAssertionTransition {
     pub fn new(name: AnchorAssertionName) -> Self {
-        AnchorAssertionTransition { name }
+        AnchorAssertionTransition {
+            name,
+            multi_line: false,
+            crlf: false,
+        }
+    }
+
+    pub fn new_multi_line(name: AnchorAssertionName, crlf: bool) -> Self {
+        AnchorAssertionTransition {
+            name,
+            multi_line: true,
+            crlf,
+        }
     }
 }
 
@@ -353,6 +530,24 @@ impl LookBehindAssertionTransition {
     }
 }
 
+// Escapes the char for displaying it in quotes, so that the listing
+// of the object file can be parsed back, see `assembler.rs`.
+fn escape_char(c: char, quote: char) -> String {
+    match c {
+        '\t' => "\\t".to_owned(),
+        '\r' => "\\r".to_owned(),
+        '\n' => "\\n".to_owned(),
+        '\\' => "\\\\".to_owned(),
+        _ if c == quote => format!("\\{}", c),
+        _ if c.is_control() => format!("\\u{{{:x}}}", c as u32),
+        _ => c.to_string(),
+    }
+}
+
+fn escape_str(s: &str) -> String {
+    s.chars().map(|c| escape_char(c, '"')).collect()
+}
+
 impl Display for Transition {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -360,6 +555,7 @@ impl Display for Transition {
             Transition::Char(t) => write!(f, "{}", t),
             Transition::String(t) => write!(f, "{}", t),
             Transition::CharSet(t) => write!(f, "{}", t),
+            Transition::Trie(t) => write!(f, "{}", t),
             Transition::SpecialChar(t) => write!(f, "{}", t),
             Transition::BackReference(t) => write!(f, "{}", t),
             Transition::AnchorAssertion(t) => write!(f, "{}", t),
@@ -386,13 +582,29 @@ impl Display for JumpTransition {
 impl Display for CharTransition {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let c = unsafe { char::from_u32_unchecked(self.codepoint) };
-        write!(f, "Char '{}'", c)
+        write!(f, "Char '{}'", escape_char(c, '\''))?;
+
+        if self.ignore_case {
+            f.write_str(", ignore case")?;
+        }
+
+        Ok(())
     }
 }
 
 impl Display for SpecialCharTransition {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        f.write_str("Any char")
+        let name = if self.matches_any_byte {
+            "Any byte"
+        } else {
+            "Any char"
+        };
+
+        if self.dot_matches_new_line {
+            write!(f, "{}, include new line", name)
+        } else {
+            f.write_str(name)
+        }
     }
 }
 
@@ -410,7 +622,13 @@ impl Display for StringTransition {
             .map(|item| unsafe { char::from_u32_unchecked(*item) })
             .collect();
         let s = String::from_iter(&cs);
-        write!(f, "String \"{}\"", s)
+        write!(f, "String \"{}\"", escape_str(&s))?;
+
+        if self.ignore_case {
+            f.write_str(", ignore case")?;
+        }
+
+        Ok(())
     }
 }
 
@@ -421,17 +639,16 @@ impl Display for CharSetTransition {
             let line = match item {
                 CharSetItem::Char(codepoint) => {
                     let c = unsafe { char::from_u32_unchecked(*codepoint) };
-                    match c {
-                        '\t' => "'\\t'".to_owned(),
-                        '\r' => "'\\r'".to_owned(),
-                        '\n' => "'\\n'".to_owned(),
-                        _ => format!("'{}'", c),
-                    }
+                    format!("'{}'", escape_char(c, '\''))
                 }
                 CharSetItem::Range(r) => {
                     let start = unsafe { char::from_u32_unchecked(r.start) };
                     let end_included = unsafe { char::from_u32_unchecked(r.end_included) };
-                    format!("'{}'..'{}'", start, end_included)
+                    format!(
+                        "'{}'..'{}'",
+                        escape_char(start, '\''),
+                        escape_char(end_included, '\'')
+                    )
                 }
             };
             lines.push(line);
@@ -439,10 +656,33 @@ impl Display for CharSetTransition {
 
         let content = lines.join(", ");
         if self.negative {
-            write!(f, "Charset ![{}]", content)
+            write!(f, "Charset ![{}]", content)?;
         } else {
-            write!(f, "Charset [{}]", content)
+            write!(f, "Charset [{}]", content)?;
+        }
+
+        if self.ignore_case {
+            f.write_str(", ignore case")?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Display for TrieTransition {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let words: Vec<String> = self
+            .words
+            .iter()
+            .map(|word| format!("\"{}\"", escape_str(word)))
+            .collect();
+        write!(f, "Trie {}", words.join(", "))?;
+
+        if self.ignore_case {
+            f.write_str(", ignore case")?;
         }
+
+        Ok(())
     }
 }
 
@@ -454,7 +694,17 @@ impl Display for BackReferenceTransition {
 
 impl Display for AnchorAssertionTransition {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "Anchor assertion \"{}\"", self.name)
+        write!(f, "Anchor assertion \"{}\"", self.name)?;
+
+        if self.multi_line {
+            f.write_str(", multi-line")?;
+
+            if self.crlf {
+                f.write_str(", crlf")?;
+            }
+        }
+
+        Ok(())
     }
 }
 
@@ -592,6 +842,16 @@ impl Transition {
 
                 if position >= thread.end_position {
                     ExecuteResult::Failure
+                } else if transition.ignore_case {
+                    // the byte length of the current char may differ from
+                    // the byte length of the expected char, e.g. 'K' (U+212A, Kelvin sign)
+                    // and 'k', so the length of the current char is used.
+                    let (cp, byte_length) = read_char(context.bytes, position);
+                    if fold_case(cp) == fold_case(transition.codepoint) {
+                        ExecuteResult::Success(byte_length, 0)
+                    } else {
+                        ExecuteResult::Failure
+                    }
                 } else {
                     let (cp, _) = read_char(context.bytes, position);
                     if cp == transition.codepoint {
@@ -601,7 +861,7 @@ impl Transition {
                     }
                 }
             }
-            Transition::SpecialChar(_) => {
+            Transition::SpecialChar(transition) => {
                 // "special char" currently contains only the "char_any".
                 //
                 // https://developer.mozilla.org/en-US/docs/Web/JavaScript/Guide/Regular_expressions/Character_classes
@@ -612,16 +872,45 @@ impl Transition {
                 if position >= thread.end_position {
                     ExecuteResult::Failure
                 } else {
-                    let (current_char, byte_length) = get_char(context.bytes, position);
+                    let (current_char, byte_length) = if transition.matches_any_byte {
+                        (context.bytes[position] as u32, 1)
+                    } else {
+                        get_char(context.bytes, position)
+                    };
 
-                    // "char_any" does not include new-line characters.
-                    if current_char != '\n' as u32 && current_char != '\r' as u32 {
+                    // "char_any" does not include new-line characters
+                    // unless the option "dot matches new line" is enabled.
+                    if transition.dot_matches_new_line
+                        || (current_char != '\n' as u32 && current_char != '\r' as u32)
+                    {
                         ExecuteResult::Success(byte_length, 0)
                     } else {
                         ExecuteResult::Failure
                     }
                 }
             }
+            Transition::String(transition) if transition.ignore_case => {
+                let thread = context.get_current_routine_ref();
+                let end_position = thread.end_position;
+
+                // the byte length of the matched text may differ from
+                // the byte length of the string, so check the range char by char.
+                let mut current_position: usize = position;
+
+                for codepoint in &transition.codepoints {
+                    if current_position >= end_position {
+                        return ExecuteResult::Failure;
+                    }
+
+                    let (cp, length) = read_char(context.bytes, current_position);
+                    if fold_case(*codepoint) != fold_case(cp) {
+                        return ExecuteResult::Failure;
+                    }
+                    current_position += length;
+                }
+
+                ExecuteResult::Success(current_position - position, 0)
+            }
             Transition::String(transition) => {
                 let thread = context.get_current_routine_ref();
 
@@ -647,6 +936,30 @@ impl Transition {
                     }
                 }
             }
+            Transition::Trie(transition) => {
+                let thread = context.get_current_routine_ref();
+                let end_position = thread.end_position;
+
+                // the strings are prefix-free, so the first string
+                // found is the only one which can be matched.
+                let mut node_index = 0;
+                let mut current_position: usize = position;
+
+                while !transition.is_terminal(node_index) {
+                    if current_position >= end_position {
+                        return ExecuteResult::Failure;
+                    }
+
+                    let (cp, length) = read_char(context.bytes, current_position);
+                    match transition.step(node_index, cp) {
+                        Some(next_node_index) => node_index = next_node_index,
+                        None => return ExecuteResult::Failure,
+                    }
+                    current_position += length;
+                }
+
+                ExecuteResult::Success(current_position - position, 0)
+            }
             Transition::CharSet(transition) => {
                 let thread = context.get_current_routine_ref();
 
@@ -657,16 +970,39 @@ impl Transition {
                 let (current_char, byte_length) = get_char(context.bytes, position);
                 let mut found: bool = false;
 
-                for item in &transition.items {
-                    found = match item {
-                        CharSetItem::Char(c) => current_char == *c,
-                        CharSetItem::Range(r) => {
-                            current_char >= r.start && current_char <= r.end_included
+                if transition.ignore_case {
+                    let lower_char = to_lower_case(current_char);
+                    let upper_char = to_upper_case(current_char);
+                    for item in &transition.items {
+                        found = match item {
+                            CharSetItem::Char(c) => fold_case(current_char) == fold_case(*c),
+                            CharSetItem::Range(r) => [current_char, lower_char, upper_char]
+                                .iter()
+                                .any(|c| *c >= r.start && *c <= r.end_included),
+                        };
+
+                        if found {
+                            break;
                         }
+                    }
+                } else if let Some(bitmap) = &transition.ascii_bitmap {
+                    found = if current_char < 128 {
+                        bitmap[(current_char >> 6) as usize] & (1 << (current_char & 63)) != 0
+                    } else {
+                        transition.contains_sorted(current_char)
                     };
-
-                    if found {
-                        break;
+                } else {
+                    for item in &transition.items {
+                        found = match item {
+                            CharSetItem::Char(c) => current_char == *c,
+                            CharSetItem::Range(r) => {
+                                current_char >= r.start && current_char <= r.end_included
+                            }
+                        };
+
+                        if found {
+                            break;
+                        }
                     }
                 }
 
@@ -680,7 +1016,11 @@ impl Transition {
                 let MatchRange { start, end } =
                     &context.match_ranges[transition.capture_group_index];
 
-                let bytes = &context.bytes[*start..*end];
+                // The range is reversed if the group has been started but not
+                // closed yet, e.g. the back reference inside the group `(a\1)`.
+                let Some(bytes) = context.bytes.get(*start..*end) else {
+                    return ExecuteResult::Failure;
+                };
                 let byte_length = end - start;
 
                 let thread = context.get_current_routine_ref();
@@ -706,9 +1046,18 @@ impl Transition {
             }
             Transition::AnchorAssertion(transition) => {
                 let bytes = context.bytes;
-                let success = match transition.name {
-                    AnchorAssertionName::Start => is_first_char(position),
-                    AnchorAssertionName::End => is_end(bytes, position),
+                let success = if transition.multi_line {
+                    match transition.name {
+                        AnchorAssertionName::Start => {
+                            is_line_start(bytes, position, transition.crlf)
+                        }
+                        AnchorAssertionName::End => is_line_end(bytes, position, transition.crlf),
+                    }
+                } else {
+                    match transition.name {
+                        AnchorAssertionName::Start => is_first_char(position),
+                        AnchorAssertionName::End => is_end(bytes, position),
+                    }
                 };
 
                 if success {
@@ -823,7 +1172,7 @@ impl Transition {
 }
 
 // return Err if the position it less than 0
-fn get_position_by_chars_backward(
+pub fn get_position_by_chars_backward(
     bytes: &[u8],
     mut current_position: usize,
     backward_chars: usize,
@@ -856,6 +1205,73 @@ fn is_end(bytes: &[u8], position: usize) -> bool {
     position >= total_byte_length
 }
 
+// In multi-line mode, "start" also matches after a line terminator ('\n').
+// When the option "crlf" is enabled, '\r' is a line terminator as well, but
+// "start" never matches between '\r' and '\n'.
+fn is_line_start(bytes: &[u8], position: usize, crlf: bool) -> bool {
+    if position == 0 {
+        return true;
+    }
+
+    let previous_byte = bytes[position - 1];
+    if crlf {
+        previous_byte == b'\n' || (previous_byte == b'\r' && bytes.get(position) != Some(&b'\n'))
+    } else {
+        previous_byte == b'\n'
+    }
+}
+
+// In multi-line mode, "end" also matches before a line terminator ('\n').
+// When the option "crlf" is enabled, '\r' is a line terminator as well, but
+// "end" never matches between '\r' and '\n'.
+fn is_line_end(bytes: &[u8], position: usize, crlf: bool) -> bool {
+    if position >= bytes.len() {
+        return true;
+    }
+
+    let current_byte = bytes[position];
+    if crlf {
+        current_byte == b'\r'
+            || (current_byte == b'\n' && (position == 0 || bytes[position - 1] != b'\r'))
+    } else {
+        current_byte == b'\n'
+    }
+}
+
+// Returns the simple case folding of the specified char, it is used
+// for comparing chars case-insensitively.
+pub fn fold_case(c: u32) -> u32 {
+    to_lower_case(c)
+}
+
+// Returns the lowercase char if it is a single char, otherwise returns the char itself.
+fn to_lower_case(c: u32) -> u32 {
+    match char::from_u32(c) {
+        Some(ch) => {
+            let mut lower = ch.to_lowercase();
+            match (lower.next(), lower.next()) {
+                (Some(l), None) => l as u32,
+                _ => c,
+            }
+        }
+        None => c,
+    }
+}
+
+// Returns the uppercase char if it is a single char, otherwise returns the char itself.
+fn to_upper_case(c: u32) -> u32 {
+    match char::from_u32(c) {
+        Some(ch) => {
+            let mut upper = ch.to_uppercase();
+            match (upper.next(), upper.next()) {
+                (Some(u), None) => u as u32,
+                _ => c,
+            }
+        }
+        None => c,
+    }
+}
+
 fn is_word_bound(bytes: &[u8], position: usize) -> bool {
     if bytes.is_empty() {
         false
@@ -863,11 +1279,11 @@ fn is_word_bound(bytes: &[u8], position: usize) -> bool {
         let (current_char, _) = get_char(bytes, position);
         is_word_char(current_char)
     } else if position >= bytes.len() {
-        let (previous_char, _) = get_char(bytes, position - 1);
+        let (previous_char, _) = read_previous_char(bytes, position);
         is_word_char(previous_char)
     } else {
         let (current_char, _) = get_char(bytes, position);
-        let (previous_char, _) = get_char(bytes, position - 1);
+        let (previous_char, _) = read_previous_char(bytes, position);
 
         if is_word_char(current_char) {
             !is_word_char(previous_char)
@@ -877,7 +1293,7 @@ fn is_word_bound(bytes: &[u8], position: usize) -> bool {
     }
 }
 
-fn is_word_char(c: u32) -> bool {
+pub fn is_word_char(c: u32) -> bool {
     (c >= 'a' as u32 && c <= 'z' as u32)
         || (c >= 'A' as u32 && c <= 'Z' as u32)
         || (c >= '0' as u32 && c <= '9' as u32)
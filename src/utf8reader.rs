@@ -4,13 +4,20 @@
 // the Mozilla Public License version 2.0 and additional exceptions.
 // For more details, see the LICENSE, LICENSE.additional, and CONTRIBUTING files.
 
+/// The code point returned for an invalid UTF-8 byte.
+///
+/// It is outside the range of Unicode, so it never equals
+/// any char of an expression.
+pub const INVALID_CODEPOINT: u32 = 0x11_0000;
+
 /// Read the next char
 /// Return `(codepoint:u32, byte_length:usize)`
 /// Convert the code point manually to char if you need:
 /// `char = unsafe { char::from_u32_unchecked(code) }`
 ///
-/// Note that ensure the data is a valid utf-8 stream before
-/// invoke this function
+/// If the data is not a valid UTF-8 sequence at the position
+/// (e.g. binary data, Latin-1 text, or the position is in the middle of a char),
+/// the byte is treated as a single char with the code point `INVALID_CODEPOINT`.
 #[inline]
 pub fn read_char(data: &[u8], position: usize) -> (u32, usize) {
     // 1 byte:  0_bbb_aaaa
@@ -20,57 +27,72 @@ pub fn read_char(data: &[u8], position: usize) -> (u32, usize) {
     // ref:
     // https://en.wikipedia.org/wiki/UTF-8
 
-    let mut code: u32 = 0;
-
     let first_byte = data[position];
     let byte_length = match first_byte.leading_ones() {
         0 => {
             // 0_bbb_aaaa
-            code |= first_byte as u32;
-            1
-        }
-        2 => {
-            // 110_ccc_bb, 10_bb_aaaa
-            code |= ((first_byte & 0b1_1111) as u32) << 6;
-            code |= (data[position + 1] & 0b11_1111) as u32;
-            2
+            return (first_byte as u32, 1);
         }
-        3 => {
-            // 1110_dddd, 10_cccc_bb, 10_bb_aaaa
-            code |= ((first_byte & 0b1111) as u32) << 12;
-            code |= ((data[position + 1] & 0b11_1111) as u32) << 6;
-            code |= (data[position + 2] & 0b11_1111) as u32;
-            3
+        length @ 2..=4 => length as usize,
+        _ => {
+            // A continuation byte `10_xx_xxxx` or an invalid byte.
+            return (INVALID_CODEPOINT, 1);
         }
-        4 => {
-            // 11110_f_ee, 10_ee_dddd, 10_cccc_bb, 10_bb_aaaa
-            code |= ((first_byte & 0b111) as u32) << 18;
-            code |= ((data[position + 1] & 0b11_1111) as u32) << 12;
-            code |= ((data[position + 2] & 0b11_1111) as u32) << 6;
-            code |= (data[position + 3] & 0b11_1111) as u32;
-            4
+    };
+
+    if position + byte_length > data.len() {
+        return (INVALID_CODEPOINT, 1);
+    }
+
+    let mut code = (first_byte & (0b111_1111 >> byte_length)) as u32;
+    for byte in &data[(position + 1)..(position + byte_length)] {
+        if byte.leading_ones() != 1 {
+            return (INVALID_CODEPOINT, 1);
         }
-        1 => panic!("Invalid UTF-8 position."),
-        _ => panic!("Invalid UTF-8 data."),
+        code = (code << 6) | (byte & 0b11_1111) as u32;
+    }
+
+    // Rejects the overlong encodings, the surrogates and
+    // the code points beyond the range of Unicode.
+    let min_code = match byte_length {
+        2 => 0x80,
+        3 => 0x800,
+        _ => 0x1_0000,
     };
 
+    if code < min_code || (0xD800..=0xDFFF).contains(&code) || code > 0x10_FFFF {
+        return (INVALID_CODEPOINT, 1);
+    }
+
     (code, byte_length)
 }
 
+/// Read the char before the position.
+///
+/// The invalid UTF-8 data is handled the same as `read_char`.
 #[allow(dead_code)]
-pub fn read_previous_char(data: &[u8], mut position: usize) -> (u32, usize) {
-    position -= 1;
-    while data[position].leading_ones() == 1 {
-        position -= 1;
+pub fn read_previous_char(data: &[u8], position: usize) -> (u32, usize) {
+    // Looks back for the leading byte, a char has at most 4 bytes.
+    let lowest_position = position.saturating_sub(4);
+    let mut start_position = position - 1;
+    while start_position > lowest_position && data[start_position].leading_ones() == 1 {
+        start_position -= 1;
+    }
+
+    let (code, byte_length) = read_char(data, start_position);
+    if start_position + byte_length == position {
+        (code, byte_length)
+    } else {
+        // The bytes before the position are not a complete char.
+        (INVALID_CODEPOINT, 1)
     }
-    read_char(data, position)
 }
 
 #[cfg(test)]
 mod tests {
     use pretty_assertions::assert_eq;
 
-    use crate::utf8reader::{read_char, read_previous_char};
+    use crate::utf8reader::{read_char, read_previous_char, INVALID_CODEPOINT};
 
     #[test]
     fn test_next_char() {
@@ -95,4 +117,53 @@ mod tests {
         assert_eq!(read_previous_char(data_ref, 9), ('😋' as u32, 4));
         assert_eq!(read_previous_char(data_ref, 10), ('c' as u32, 1));
     }
+
+    #[test]
+    fn test_invalid_char() {
+        // "文" is `e6 96 87`
+        let data = b"a\xe6\x96\x87\xff\xe6\x96b\xe9\xc0\x80\xed\xa0\x80\xe6";
+
+        assert_eq!(read_char(data, 0), ('a' as u32, 1));
+        assert_eq!(read_char(data, 1), ('文' as u32, 3));
+
+        // The middle of a char.
+        assert_eq!(read_char(data, 2), (INVALID_CODEPOINT, 1));
+        assert_eq!(read_char(data, 3), (INVALID_CODEPOINT, 1));
+
+        // Invalid byte.
+        assert_eq!(read_char(data, 4), (INVALID_CODEPOINT, 1));
+
+        // Incomplete char.
+        assert_eq!(read_char(data, 5), (INVALID_CODEPOINT, 1));
+        assert_eq!(read_char(data, 6), (INVALID_CODEPOINT, 1));
+        assert_eq!(read_char(data, 7), ('b' as u32, 1));
+
+        // Latin-1 char 'é'.
+        assert_eq!(read_char(data, 8), (INVALID_CODEPOINT, 1));
+
+        // Overlong encoding.
+        assert_eq!(read_char(data, 9), (INVALID_CODEPOINT, 1));
+
+        // Surrogate.
+        assert_eq!(read_char(data, 11), (INVALID_CODEPOINT, 1));
+
+        // Truncated at the end of data.
+        assert_eq!(read_char(data, 14), (INVALID_CODEPOINT, 1));
+
+        assert_eq!(read_previous_char(data, 1), ('a' as u32, 1));
+        assert_eq!(read_previous_char(data, 4), ('文' as u32, 3));
+        assert_eq!(read_previous_char(data, 3), (INVALID_CODEPOINT, 1));
+        assert_eq!(read_previous_char(data, 5), (INVALID_CODEPOINT, 1));
+        assert_eq!(read_previous_char(data, 7), (INVALID_CODEPOINT, 1));
+        assert_eq!(read_previous_char(data, 8), ('b' as u32, 1));
+        assert_eq!(read_previous_char(data, 9), (INVALID_CODEPOINT, 1));
+        assert_eq!(read_previous_char(data, 15), (INVALID_CODEPOINT, 1));
+
+        // Continuation bytes at the beginning of data.
+        let data = b"\x80\x80c";
+        assert_eq!(read_char(data, 0), (INVALID_CODEPOINT, 1));
+        assert_eq!(read_previous_char(data, 1), (INVALID_CODEPOINT, 1));
+        assert_eq!(read_previous_char(data, 2), (INVALID_CODEPOINT, 1));
+        assert_eq!(read_previous_char(data, 3), ('c' as u32, 1));
+    }
 }